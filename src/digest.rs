@@ -0,0 +1,176 @@
+// Digests of file contents, used to verify copies. The fast digest is XXH64, a
+// non-cryptographic hash which detects corrupted copies but not deliberate tampering.
+
+use crate::error::Result;
+use crate::file::Verify;
+use std::fs::File;
+use std::io::Read;
+
+const PRIME_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+// Size of the buffer the content is read through.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+// Reads up to `len` bytes from the current position of `file`, or up to its end, and returns
+// their digest. Returns `None` for `Verify::None`.
+pub(crate) fn digest(file: &mut File, len: u64, verify: Verify) -> Result<Option<Vec<u8>>> {
+    let mut hasher = match verify {
+        Verify::None => return Ok(None),
+        Verify::Fast => Hasher::Fast(Xxh64::new()),
+        #[cfg(feature = "sha256")]
+        Verify::Sha256 => Hasher::Sha256(crate::features::sha256::Sha256::new()),
+    };
+    let mut buf = vec![0; BUFFER_SIZE];
+    let mut reader = file.take(len);
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(Some(hasher.finish()))
+}
+
+enum Hasher {
+    Fast(Xxh64),
+    #[cfg(feature = "sha256")]
+    Sha256(crate::features::sha256::Sha256),
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Fast(hasher) => hasher.update(data),
+            #[cfg(feature = "sha256")]
+            Hasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        match self {
+            Hasher::Fast(hasher) => hasher.finish().to_be_bytes().to_vec(),
+            #[cfg(feature = "sha256")]
+            Hasher::Sha256(hasher) => hasher.finish(),
+        }
+    }
+}
+
+// Streaming XXH64 with seed 0.
+struct Xxh64 {
+    acc: [u64; 4],
+    // Input which does not fill a stripe of 32 bytes yet.
+    buf: [u8; 32],
+    buf_len: usize,
+    total_len: u64,
+}
+
+impl Xxh64 {
+    fn new() -> Xxh64 {
+        Xxh64 {
+            acc: [
+                PRIME_1.wrapping_add(PRIME_2),
+                PRIME_2,
+                0,
+                0u64.wrapping_sub(PRIME_1),
+            ],
+            buf: [0; 32],
+            buf_len: 0,
+            total_len: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        if self.buf_len > 0 {
+            let n = data.len().min(32 - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+            if self.buf_len < 32 {
+                return;
+            }
+            let stripe = self.buf;
+            self.stripe(&stripe);
+            self.buf_len = 0;
+        }
+        let mut stripes = data.chunks_exact(32);
+        for stripe in &mut stripes {
+            self.stripe(stripe);
+        }
+        let rest = stripes.remainder();
+        self.buf[..rest.len()].copy_from_slice(rest);
+        self.buf_len = rest.len();
+    }
+
+    fn stripe(&mut self, stripe: &[u8]) {
+        for (acc, lane) in self.acc.iter_mut().zip(stripe.chunks_exact(8)) {
+            *acc = round(*acc, read_u64(lane));
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let [v1, v2, v3, v4] = self.acc;
+        let mut hash = if self.total_len >= 32 {
+            let mut hash = v1
+                .rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+            for v in &self.acc {
+                hash = (hash ^ round(0, *v))
+                    .wrapping_mul(PRIME_1)
+                    .wrapping_add(PRIME_4);
+            }
+            hash
+        } else {
+            PRIME_5
+        };
+        hash = hash.wrapping_add(self.total_len);
+
+        let mut rest = &self.buf[..self.buf_len];
+        while rest.len() >= 8 {
+            hash ^= round(0, read_u64(rest));
+            hash = hash
+                .rotate_left(27)
+                .wrapping_mul(PRIME_1)
+                .wrapping_add(PRIME_4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            let lane = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            hash ^= u64::from(lane).wrapping_mul(PRIME_1);
+            hash = hash
+                .rotate_left(23)
+                .wrapping_mul(PRIME_2)
+                .wrapping_add(PRIME_3);
+            rest = &rest[4..];
+        }
+        for &byte in rest {
+            hash ^= u64::from(byte).wrapping_mul(PRIME_5);
+            hash = hash.rotate_left(11).wrapping_mul(PRIME_1);
+        }
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(PRIME_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(PRIME_3);
+        hash ^ (hash >> 32)
+    }
+}
+
+fn round(acc: u64, lane: u64) -> u64 {
+    acc.wrapping_add(lane.wrapping_mul(PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME_1)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut lane = [0; 8];
+    lane.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(lane)
+}
@@ -1,16 +1,28 @@
 use crate::error::*;
+use crate::file::{Backup, Durability, OverwritePolicy, OwnerMap, Reflink, Verify};
+use crate::glob::{Filter, Visit};
+use crate::journal::Journal;
 use std::collections::{HashMap, HashSet};
-use std::fs::{create_dir, create_dir_all, read_dir, remove_dir_all, Metadata};
+use std::fs::{
+    create_dir, create_dir_all, read_dir, read_link, remove_dir_all, remove_file, rename,
+    set_permissions, Metadata,
+};
+use std::io::ErrorKind as IoErrorKind;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::SystemTime;
 
 /// Options and flags which can be used to configure how a file will be copied or moved.
 #[derive(Clone)]
 pub struct CopyOptions {
-    /// Overwrite existing files if true (default: false).
+    /// Overwrite existing files if true (default: false). Shorthand for
+    /// `OverwritePolicy::Always`, which takes precedence over `overwrite_policy`.
     pub overwrite: bool,
-    /// Skip existing files if true (default: false).
+    /// Skip existing files if true (default: false). Shorthand for `OverwritePolicy::Skip`,
+    /// used if `overwrite_policy` is `OverwritePolicy::Never`.
     pub skip_exist: bool,
+    /// Sets how existing files are handled (default: OverwritePolicy::Never).
+    pub overwrite_policy: OverwritePolicy,
     /// Buffer size that specifies the amount of bytes to be moved or copied before the progress handler is called. This only affects functions with progress handlers. (default: 64000)
     pub buffer_size: usize,
     /// Recursively copy a directory with a new name or place it inside the destination (default: false, same behaviors as cp -r on Unix)
@@ -21,6 +33,85 @@ pub struct CopyOptions {
     ///
     /// Warning: Work only for copy operations!
     pub depth: u64,
+    /// Sets how symbolic links inside the directory are handled (default: SymlinkPolicy::Follow).
+    pub symlinks: SymlinkPolicy,
+    /// Copy access and modification times of files and directories (default: false).
+    pub preserve_times: bool,
+    /// Copy the owner (uid and gid) of files, directories and symlinks. Works only on Unix (default: false).
+    pub preserve_owner: bool,
+    /// Return an error if the owner can not be copied because the process lacks the privilege.
+    /// Otherwise such an error is ignored (default: false).
+    pub strict_owner: bool,
+    /// Function which maps the owner of a source entry to the owner of its copy (default: None).
+    pub owner_map: Option<OwnerMap>,
+    /// Copy extended attributes of files and directories. Works only on Linux (default: false).
+    ///
+    /// The functions with progress, also `copy_items_with_progress` and
+    /// `move_items_with_progress`, report entries whose attributes could not be copied with
+    /// `TransitState::XattrFailed` and go on. Other functions return the error.
+    pub preserve_xattrs: bool,
+    /// Namespaces of the extended attributes which are copied (default: ["user"]).
+    pub xattr_namespaces: Vec<String>,
+    /// Copy the data of files with several names inside the directory only once and create
+    /// the other names as hard links to the copy. Works only on Unix (default: false).
+    pub preserve_hard_links: bool,
+    /// Keep holes of sparse files. Works only on Linux (default: false).
+    pub sparse: bool,
+    /// Sets how reflinks are used to copy files (default: Reflink::Auto).
+    pub reflink: Reflink,
+    /// Write each file to a temporary file first, which replaces the destination once it is
+    /// complete, see `file::CopyOptions::atomic` (default: false).
+    pub atomic: bool,
+    /// Sets how existing destination files are kept before they are overwritten
+    /// (default: Backup::None).
+    pub backup: Backup,
+    /// Suffix of simple backups (default: "~").
+    pub backup_suffix: String,
+    /// Directory the backups are moved to, keeping their place in the copied tree, instead
+    /// of the directory of each destination file (default: None).
+    pub backup_dir: Option<PathBuf>,
+    /// Sets how copied files and directories are flushed to the disk. Moves flush each
+    /// destination before its source is removed, see `file::Durability`
+    /// (default: Durability::None).
+    pub durability: Durability,
+    /// Sets the digest to verify each copied file with, see `file::CopyOptions::verify`.
+    /// Progress functions report a mismatch with `TransitState::VerificationFailed`
+    /// (default: Verify::None).
+    pub verify: Verify,
+    /// Continue files whose copy was interrupted instead of copying them again, see
+    /// `file::CopyOptions::resume` (default: false).
+    pub resume: bool,
+    /// Journal file which records the progress of `copy_with_progress` and
+    /// `copy_items_with_progress`. Running the copy again with the same journal skips the
+    /// files which were completed and did not change since, and continues the file it was
+    /// interrupted in, see `journal::pending` (default: None).
+    pub journal: Option<PathBuf>,
+    /// Sets how FIFOs, device nodes and sockets are handled (default: SpecialFilePolicy::Recreate).
+    pub special_files: SpecialFilePolicy,
+    /// Remove files and directories from the destination which do not exist in the source,
+    /// like `rsync --delete`. Copies which `OverwritePolicy::Rename` wrote under another name
+    /// and backups made by the copy are kept. Works only for copy operations (default: false).
+    pub mirror_copy: bool,
+    /// Only count the entries which `mirror_copy` would remove (default: false).
+    pub mirror_dry_run: bool,
+    /// Paths relative to the copied directory in the destination which `mirror_copy` never
+    /// removes, together with their content (default: empty).
+    pub mirror_exclude: Vec<PathBuf>,
+    /// Glob patterns of the files to copy or move, relative to the source directory, see
+    /// `DirOptions::include` (default: empty).
+    pub include: Vec<String>,
+    /// Glob patterns of the entries to leave out, see `DirOptions::exclude`. Excluded entries
+    /// stay in the source when moving and are not removed by `mirror_copy` (default: empty).
+    pub exclude: Vec<String>,
+    /// Function which decides whether an entry of the source directory is copied or moved,
+    /// see `DirOptions::filter`. Entries it leaves out stay in the source when moving
+    /// (default: None).
+    pub filter: Option<EntryFilter>,
+    /// Leave out the entries ignored by git, see `DirOptions::gitignore`. Ignored entries stay
+    /// in the source when moving (default: false).
+    pub gitignore: bool,
+    /// Names of further ignore files, see `DirOptions::ignore_files` (default: empty).
+    pub ignore_files: Vec<String>,
 }
 
 impl CopyOptions {
@@ -39,10 +130,38 @@ impl CopyOptions {
         CopyOptions {
             overwrite: false,
             skip_exist: false,
+            overwrite_policy: OverwritePolicy::Never,
             buffer_size: 64000, // 64kb
             copy_inside: false,
             content_only: false,
             depth: 0,
+            symlinks: SymlinkPolicy::Follow,
+            preserve_times: false,
+            preserve_owner: false,
+            strict_owner: false,
+            owner_map: None,
+            preserve_xattrs: false,
+            xattr_namespaces: vec!["user".to_string()],
+            preserve_hard_links: false,
+            sparse: false,
+            reflink: Reflink::Auto,
+            atomic: false,
+            backup: Backup::None,
+            backup_suffix: "~".to_string(),
+            backup_dir: None,
+            durability: Durability::None,
+            verify: Verify::None,
+            resume: false,
+            journal: None,
+            special_files: SpecialFilePolicy::Recreate,
+            mirror_copy: false,
+            mirror_dry_run: false,
+            mirror_exclude: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            filter: None,
+            gitignore: false,
+            ignore_files: Vec::new(),
         }
     }
 
@@ -58,6 +177,12 @@ impl CopyOptions {
         self
     }
 
+    /// Sets how existing files are handled.
+    pub fn overwrite_policy(mut self, overwrite_policy: OverwritePolicy) -> Self {
+        self.overwrite_policy = overwrite_policy;
+        self
+    }
+
     /// Buffer size that specifies the amount of bytes to be moved or copied before the progress handler is called. This only affects functions with progress handlers.
     pub fn buffer_size(mut self, buffer_size: usize) -> Self {
         self.buffer_size = buffer_size;
@@ -81,6 +206,248 @@ impl CopyOptions {
         self.depth = depth;
         self
     }
+
+    /// Sets how symbolic links inside the directory are handled.
+    pub fn symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Copy access and modification times of files and directories if true.
+    pub fn preserve_times(mut self, preserve_times: bool) -> Self {
+        self.preserve_times = preserve_times;
+        self
+    }
+
+    /// Copy the owner (uid and gid) of files, directories and symlinks if true. Works only on Unix.
+    pub fn preserve_owner(mut self, preserve_owner: bool) -> Self {
+        self.preserve_owner = preserve_owner;
+        self
+    }
+
+    /// Return an error if the owner can not be copied because the process lacks the privilege.
+    pub fn strict_owner(mut self, strict_owner: bool) -> Self {
+        self.strict_owner = strict_owner;
+        self
+    }
+
+    /// Sets a function which maps the owner of a source entry to the owner of its copy.
+    pub fn owner_map<F>(mut self, owner_map: F) -> Self
+    where
+        F: Fn(u32, u32) -> (u32, u32) + Send + Sync + 'static,
+    {
+        self.owner_map = Some(Arc::new(owner_map));
+        self
+    }
+
+    /// Copy extended attributes of files and directories if true. Works only on Linux.
+    pub fn preserve_xattrs(mut self, preserve_xattrs: bool) -> Self {
+        self.preserve_xattrs = preserve_xattrs;
+        self
+    }
+
+    /// Sets namespaces of the extended attributes which are copied, e.g. `user` or `system`.
+    pub fn xattr_namespaces(mut self, xattr_namespaces: &[&str]) -> Self {
+        self.xattr_namespaces = xattr_namespaces.iter().map(|ns| ns.to_string()).collect();
+        self
+    }
+
+    /// Create further names of a file inside the directory as hard links to its copy if true.
+    /// Works only on Unix.
+    pub fn preserve_hard_links(mut self, preserve_hard_links: bool) -> Self {
+        self.preserve_hard_links = preserve_hard_links;
+        self
+    }
+
+    /// Keep holes of sparse files if true. Works only on Linux.
+    pub fn sparse(mut self, sparse: bool) -> Self {
+        self.sparse = sparse;
+        self
+    }
+
+    /// Sets how reflinks are used to copy files.
+    pub fn reflink(mut self, reflink: Reflink) -> Self {
+        self.reflink = reflink;
+        self
+    }
+
+    /// Sets the option true for replace each destination file only once its copy is complete.
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Sets how existing destination files are kept before they are overwritten.
+    pub fn backup(mut self, backup: Backup) -> Self {
+        self.backup = backup;
+        self
+    }
+
+    /// Sets the suffix of simple backups.
+    pub fn backup_suffix(mut self, backup_suffix: &str) -> Self {
+        self.backup_suffix = backup_suffix.to_string();
+        self
+    }
+
+    /// Sets the directory backups are moved to.
+    pub fn backup_dir<P: AsRef<Path>>(mut self, backup_dir: P) -> Self {
+        self.backup_dir = Some(backup_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets how copied files and directories are flushed to the disk.
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Sets the digest to verify each copied file with.
+    pub fn verify(mut self, verify: Verify) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Sets the option true for continue files whose copy was interrupted.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Sets the journal file which records the progress of the copy.
+    pub fn journal<P: AsRef<Path>>(mut self, journal: P) -> Self {
+        self.journal = Some(journal.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets how FIFOs, device nodes and sockets are handled.
+    pub fn special_files(mut self, special_files: SpecialFilePolicy) -> Self {
+        self.special_files = special_files;
+        self
+    }
+
+    /// Remove entries from the destination which do not exist in the source if true.
+    pub fn mirror_copy(mut self, mirror_copy: bool) -> Self {
+        self.mirror_copy = mirror_copy;
+        self
+    }
+
+    /// Only count the entries which `mirror_copy` would remove if true.
+    pub fn mirror_dry_run(mut self, mirror_dry_run: bool) -> Self {
+        self.mirror_dry_run = mirror_dry_run;
+        self
+    }
+
+    /// Sets paths relative to the copied directory which `mirror_copy` never removes.
+    pub fn mirror_exclude<P: AsRef<Path>>(mut self, mirror_exclude: &[P]) -> Self {
+        self.mirror_exclude = mirror_exclude
+            .iter()
+            .map(|path| path.as_ref().to_path_buf())
+            .collect();
+        self
+    }
+
+    /// Sets glob patterns of the files to copy or move.
+    pub fn include(mut self, include: &[&str]) -> Self {
+        self.include = include.iter().map(|pattern| pattern.to_string()).collect();
+        self
+    }
+
+    /// Sets glob patterns of the entries to leave out.
+    pub fn exclude(mut self, exclude: &[&str]) -> Self {
+        self.exclude = exclude.iter().map(|pattern| pattern.to_string()).collect();
+        self
+    }
+
+    /// Sets a function which decides whether an entry of the source directory is copied or
+    /// moved.
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&Path, &Metadata) -> FilterResult + Send + Sync + 'static,
+    {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Leave out the entries ignored by git if true.
+    pub fn gitignore(mut self, gitignore: bool) -> Self {
+        self.gitignore = gitignore;
+        self
+    }
+
+    /// Sets names of further ignore files read in each directory, like `.fsextraignore`.
+    pub fn ignore_files(mut self, ignore_files: &[&str]) -> Self {
+        self.ignore_files = ignore_files.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    // Options for reading the source directory.
+    pub(crate) fn dir_options(&self) -> DirOptions {
+        DirOptions {
+            depth: self.depth,
+            symlinks: self.symlinks,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            filter: self.filter.clone(),
+            gitignore: self.gitignore,
+            ignore_files: self.ignore_files.clone(),
+            ..Default::default()
+        }
+    }
+
+    // Options for `get_size2` which count the files the filters leave in, like
+    // `get_size` does without them.
+    pub(crate) fn size_options(&self) -> DirOptions {
+        DirOptions {
+            symlinks: SymlinkPolicy::Preserve,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            filter: self.filter.clone(),
+            gitignore: self.gitignore,
+            ignore_files: self.ignore_files.clone(),
+            ..Default::default()
+        }
+    }
+
+    // Whether `include`, `exclude`, `filter` or ignore files leave out entries of the
+    // directory.
+    pub(crate) fn is_filtered(&self) -> bool {
+        self.dir_options().is_filtered()
+    }
+
+    // Options for copying or moving a single file of the directory.
+    pub(crate) fn file_options(&self) -> super::file::CopyOptions {
+        super::file::CopyOptions {
+            overwrite: self.overwrite,
+            skip_exist: self.skip_exist,
+            overwrite_policy: self.overwrite_policy,
+            buffer_size: self.buffer_size,
+            preserve_times: self.preserve_times,
+            preserve_owner: self.preserve_owner,
+            strict_owner: self.strict_owner,
+            owner_map: self.owner_map.clone(),
+            preserve_xattrs: self.preserve_xattrs,
+            xattr_namespaces: self.xattr_namespaces.clone(),
+            sparse: self.sparse,
+            reflink: self.reflink,
+            atomic: self.atomic,
+            backup: self.backup,
+            backup_suffix: self.backup_suffix.clone(),
+            backup_dir: self.backup_dir.clone(),
+            durability: self.durability,
+            verify: self.verify,
+            resume: self.resume,
+        }
+    }
+
+    // Like `file_options`, for the entry at `relative` inside the directory. Its backup
+    // keeps that place below `backup_dir`.
+    pub(crate) fn file_options_at(&self, relative: &Path) -> super::file::CopyOptions {
+        let mut options = self.file_options();
+        if let (Some(dir), Some(parent)) = (&self.backup_dir, relative.parent()) {
+            options.backup_dir = Some(dir.join(parent));
+        }
+        options
+    }
 }
 
 impl Default for CopyOptions {
@@ -89,11 +456,93 @@ impl Default for CopyOptions {
     }
 }
 
+/// Available ways to handle symbolic links found inside a directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Follow the link and treat it as the file or directory it points to.
+    #[default]
+    Follow,
+    /// Keep the link itself. Copies re-create the link at the destination.
+    Preserve,
+    /// Ignore the link.
+    Skip,
+    /// Stop with an `ErrorKind::Symlink` error.
+    Error,
+}
+
+/// Available ways to handle FIFOs, device nodes and sockets found inside a directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpecialFilePolicy {
+    /// Re-create FIFOs and device nodes at the destination and ignore sockets. Creating a
+    /// device node needs privileges and fails with `ErrorKind::PermissionDenied` otherwise.
+    /// Works only on Linux.
+    #[default]
+    Recreate,
+    /// Ignore the special file.
+    Skip,
+    /// Stop with an `ErrorKind::SpecialFile` error.
+    Error,
+}
+
+/// Available ways to handle a directory which is reached again inside itself, e.g. through
+/// a symlink loop or a bind mount.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CyclePolicy {
+    /// Stop with an `ErrorKind::Cycle` error.
+    #[default]
+    Error,
+    /// Do not read the directory again and list it in `DirContent::cycles`.
+    Skip,
+}
+
+/// Decision of an entry filter, see `DirOptions::filter`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterResult {
+    /// Visit the entry.
+    Include,
+    /// Leave out the entry. The content of a directory is still read, and the directory is
+    /// kept if some of its content is.
+    Skip,
+    /// Leave out the entry together with everything below it.
+    Prune,
+}
+
+/// Function which decides how an entry of a directory is handled, given its path and
+/// metadata, see `DirOptions::filter`.
+pub type EntryFilter = Arc<dyn Fn(&Path, &Metadata) -> FilterResult + Send + Sync>;
+
 // Options and flags which can be used to configure how to read a directory.
 #[derive(Clone, Default)]
 pub struct DirOptions {
     /// Sets levels reading. Set value 0 for read all directory folder. By default 0.
     pub depth: u64,
+    /// Sets how symbolic links inside the directory are handled. By default SymlinkPolicy::Follow.
+    pub symlinks: SymlinkPolicy,
+    /// Sets how directory cycles are handled. By default CyclePolicy::Error.
+    pub cycles: CyclePolicy,
+    /// Glob patterns of the files to read, relative to the directory. If not empty, other
+    /// files are left out, but directories are still read. By default empty.
+    ///
+    /// `*` and `?` match within a path segment and `**` matches any number of segments.
+    /// Patterns with a `/` are anchored at the directory, others match the entry name at any
+    /// depth. A trailing `/` matches only directories, which includes everything inside them.
+    pub include: Vec<String>,
+    /// Glob patterns of the entries to leave out, see `include`. An excluded directory is not
+    /// read at all. Takes precedence over `include`. By default empty.
+    pub exclude: Vec<String>,
+    /// Function called for each entry which is not left out by `include` and `exclude`. The
+    /// metadata is the one of the symlink target if `symlinks` follows links. By default None.
+    pub filter: Option<EntryFilter>,
+    /// Leave out the entries ignored by git, as listed in the `.gitignore` files of each
+    /// directory and in `.git/info/exclude` of the directory, and the `.git` directories.
+    /// Patterns use the syntax of `include`, relative to the directory of their file, and a
+    /// leading `!` includes an entry again. The last matching pattern of a file wins, and
+    /// files deeper in the tree take precedence. By default false.
+    pub gitignore: bool,
+    /// Names of further ignore files read in each directory, like `.fsextraignore`, with the
+    /// syntax of `.gitignore`. They take precedence over `.gitignore` files. Applied before
+    /// `filter`. By default empty.
+    pub ignore_files: Vec<String>,
 }
 
 impl DirOptions {
@@ -101,9 +550,19 @@ impl DirOptions {
     pub fn new() -> DirOptions {
         Default::default()
     }
+
+    // Whether `include`, `exclude`, `filter` or ignore files leave out entries.
+    pub(crate) fn is_filtered(&self) -> bool {
+        !self.include.is_empty()
+            || !self.exclude.is_empty()
+            || self.filter.is_some()
+            || self.gitignore
+            || !self.ignore_files.is_empty()
+    }
 }
 
 /// A structure which include information about directory
+#[derive(Clone)]
 pub struct DirContent {
     /// Directory size in bytes.
     pub dir_size: u64,
@@ -111,6 +570,13 @@ pub struct DirContent {
     pub files: Vec<String>,
     /// List all folders and sub folders directory.
     pub directories: Vec<String>,
+    /// List all symbolic links which were not followed (see `SymlinkPolicy::Preserve`).
+    pub symlinks: Vec<String>,
+    /// List all directories which were not read because they contain themselves
+    /// (see `CyclePolicy::Skip`).
+    pub cycles: Vec<String>,
+    /// List all FIFOs, device nodes and sockets.
+    pub special_files: Vec<String>,
 }
 
 /// A structure which include information about the current status of the copy or move directory.
@@ -127,9 +593,26 @@ pub struct TransitProcess {
     pub file_name: String,
     /// Transit state
     pub state: TransitState,
+    /// Entries removed from the destination by `CopyOptions::mirror_copy` on this time.
+    pub deleted_entries: u64,
+    /// Path the current file is written to if the destination existed and it was renamed,
+    /// see `OverwritePolicy::Rename`.
+    pub renamed_to: Option<PathBuf>,
+    /// Path the overwritten destination of the current file was moved to, see
+    /// `CopyOptions::backup`.
+    pub backup: Option<PathBuf>,
 }
 
-///
+/// A structure which include the results of a mirror copy.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MirrorResult {
+    /// Copied bytes.
+    pub copied_bytes: u64,
+    /// Entries removed from the destination, or which would be removed in a dry run.
+    pub deleted_entries: u64,
+}
+
+/// Available states of the copy or move process.
 #[derive(Hash, Eq, PartialEq, Clone)]
 pub enum TransitState {
     /// Standard state.
@@ -139,6 +622,13 @@ pub enum TransitState {
     /// Pause state when current process does not have the permission to access from or to
     /// path.
     NoAccess,
+    /// Extended attributes of the current file or directory could not be copied. The copy
+    /// goes on unless the handler returns `TransitProcessResult::Abort`.
+    XattrFailed,
+    /// Pause state when the copy of the current file does not match its source, see
+    /// `CopyOptions::verify`. The copy is removed. `TransitProcessResult::Retry` copies the
+    /// file again, `Skip` and `SkipAll` leave it out, and other results return the error.
+    VerificationFailed,
 }
 
 /// Available returns codes for user decide
@@ -151,6 +641,10 @@ pub enum TransitProcessResult {
     Skip,
     /// Skip for all problems file or directory.
     SkipAll,
+    /// Write the current file to a free name next to the existing one, like `name (1).ext`.
+    Rename,
+    /// Write all files which exist already to free names.
+    RenameAll,
     /// Retry current operation.
     Retry,
     /// Abort current operation.
@@ -168,6 +662,9 @@ impl Clone for TransitProcess {
             file_total_bytes: self.file_total_bytes,
             file_name: self.file_name.clone(),
             state: self.state.clone(),
+            deleted_entries: self.deleted_entries,
+            renamed_to: self.renamed_to.clone(),
+            backup: self.backup.clone(),
         }
     }
 }
@@ -367,7 +864,7 @@ where
         item.insert(DirEntryAttr::DosPath, DirEntryValue::String(path));
     }
     if config.contains(&DirEntryAttr::Size) {
-        item.insert(DirEntryAttr::Size, DirEntryValue::U64(get_size(&path)?));
+        item.insert(DirEntryAttr::Size, DirEntryValue::U64(get_size(path)?));
     }
     if config.contains(&DirEntryAttr::FileSize) {
         item.insert(DirEntryAttr::FileSize, DirEntryValue::U64(metadata.len()));
@@ -439,6 +936,55 @@ where
 /// assert_eq!(2, ls_result.base.len());
 /// ```
 pub fn ls<P>(path: P, config: &HashSet<DirEntryAttr>) -> Result<LsResult>
+where
+    P: AsRef<Path>,
+{
+    ls_filtered(path, config, &DirOptions::new())
+}
+
+/// Returns a collection of directory entries with attributes specifying the information that
+/// should be returned, like `ls`, but only the entries which pass the filters of `options`.
+///
+/// Unlike `ls`, which lists every entry of `path`, this leaves out the entries which
+/// `options.include`, `options.exclude` or `options.filter` leave out. Skipped directories
+/// are left out too, since only the entries of `path` itself are listed. Other fields of
+/// `options` are not used.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is not limited to just
+/// these cases:
+///
+/// * This `path` directory does not exist.
+/// * Invalid `path`.
+/// * The current process does not have the permission to access `path`.
+///
+/// #Examples
+///
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::dir::{ls_filtered, DirEntryAttr, DirOptions, FilterResult};
+/// use std::collections::HashSet;
+/// use std::sync::Arc;
+///
+/// let mut config = HashSet::new();
+/// config.insert(DirEntryAttr::Name);
+///
+/// let mut options = DirOptions::new();
+/// options.filter = Some(Arc::new(|_, metadata| {
+///     if metadata.len() > 2 << 30 {
+///         FilterResult::Skip
+///     } else {
+///         FilterResult::Include
+///     }
+/// }));
+/// let result = ls_filtered("test", &config, &options)?;
+/// ```
+pub fn ls_filtered<P>(
+    path: P,
+    config: &HashSet<DirEntryAttr>,
+    options: &DirOptions,
+) -> Result<LsResult>
 where
     P: AsRef<Path>,
 {
@@ -447,16 +993,22 @@ where
     if !path.is_dir() {
         err!("Path does not directory", ErrorKind::InvalidFolder);
     }
-    for entry in read_dir(&path)? {
+    let filter = Filter::for_walk(path, options);
+    filter.enter(path)?;
+    for entry in read_dir(path)? {
         let entry = entry?;
         let path = entry.path();
         let metadata = entry.metadata()?;
-        let item = get_details_entry_with_meta(path, &config, metadata)?;
+        match filter.visit(&path, &metadata, Visit::default()) {
+            Some(visit) if !visit.skipped => {}
+            _ => continue,
+        }
+        let item = get_details_entry_with_meta(path, config, metadata)?;
         items.push(item);
     }
     let mut base = HashMap::new();
     if config.contains(&DirEntryAttr::BaseInfo) {
-        base = get_details_entry(&path, &config)?;
+        base = get_details_entry(path, config)?;
     }
     Ok(LsResult { items, base })
 }
@@ -531,8 +1083,8 @@ where
 }
 
 /// Copies the directory contents from one place to another using recursive method.
-/// This function will also copy the permission bits of the original files to
-/// destination files (not for directories).
+/// This function will also copy the permission bits of the original files and
+/// directories to the destination files and newly created directories.
 ///
 /// # Errors
 ///
@@ -562,8 +1114,46 @@ where
     P: AsRef<Path>,
     Q: AsRef<Path>,
 {
-    let from = from.as_ref();
+    Ok(_copy(from.as_ref(), to.as_ref(), options)?.copied_bytes)
+}
 
+/// Copies the directory contents like [`copy`] with `mirror_copy` set, so entries of the
+/// destination which do not exist in the source are removed. Returns the copied bytes
+/// and the number of removed entries.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is not limited to just
+/// these cases:
+///
+/// * This `from` path is not a directory.
+/// * This `from` directory does not exist.
+/// * Invalid folder name for `from` or `to`.
+/// * The current process does not have the permission to access `from` or write `to`.
+///
+/// # Example
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::dir::{mirror, CopyOptions};
+///
+/// let options = CopyOptions::new().mirror_exclude(&["cache"]);
+///
+/// // make target/dir1 a copy of source/dir1, but keep target/dir1/cache
+/// let result = mirror("source/dir1", "target", &options)?;
+/// println!("{} entries removed", result.deleted_entries);
+///
+/// ```
+pub fn mirror<P, Q>(from: P, to: Q, options: &CopyOptions) -> Result<MirrorResult>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut options = options.clone();
+    options.mirror_copy = true;
+    _copy(from.as_ref(), to.as_ref(), &options)
+}
+
+fn _copy(from: &Path, to: &Path, options: &CopyOptions) -> Result<MirrorResult> {
     if !from.exists() {
         if let Some(msg) = from.to_str() {
             let msg = format!("Path \"{}\" does not exist or you don't have access!", msg);
@@ -582,53 +1172,55 @@ where
         err!("Path is not a directory!", ErrorKind::InvalidFolder);
     }
     let dir_name;
-    if let Some(val) = from.components().last() {
+    if let Some(val) = from.components().next_back() {
         dir_name = val.as_os_str();
     } else {
         err!("Invalid folder from", ErrorKind::InvalidFolder);
     }
-    let mut to: PathBuf = to.as_ref().to_path_buf();
+    let mut to: PathBuf = to.to_path_buf();
     if (to.exists() || !options.copy_inside) && !options.content_only {
         to.push(dir_name);
     }
 
-    let mut read_options = DirOptions::new();
-    if options.depth > 0 {
-        read_options.depth = options.depth;
-    }
-
-    let dir_content = get_dir_content2(from, &read_options)?;
-    for directory in dir_content.directories {
-        let tmp_to = Path::new(&directory).strip_prefix(from)?;
-        let dir = to.join(&tmp_to);
-        if !dir.exists() {
-            if options.copy_inside {
-                create_all(dir, false)?;
-            } else {
-                create(dir, false)?;
-            }
-        }
-    }
+    let dir_content = get_dir_content2(from, &options.dir_options())?;
+    let dirs = create_dirs(dir_content.directories, from, &to, options)?;
+    copy_special_files(&dir_content.special_files, from, &to, options)?;
+    let mut hard_links = find_hard_links(&dir_content.files, options)?;
     let mut result: u64 = 0;
-    for file in dir_content.files {
+    // Renamed copies and backups, which have no entry of the same name in the source.
+    let mut created = HashSet::new();
+    let files = dir_content.files.into_iter().map(|file| (file, false));
+    let symlinks = dir_content.symlinks.into_iter().map(|link| (link, true));
+    for (file, is_symlink) in files.chain(symlinks) {
         let to = to.to_path_buf();
         let tp = Path::new(&file).strip_prefix(from)?;
-        let path = to.join(&tp);
-
-        let file_options = super::file::CopyOptions {
-            overwrite: options.overwrite,
-            skip_exist: options.skip_exist,
-            buffer_size: options.buffer_size,
-        };
+        let file_options = options.file_options_at(tp);
+        let path = super::file::destination(&to.join(tp), &file_options);
+        if path != to.join(tp) {
+            created.insert(path.clone());
+        }
         let mut result_copy: Result<u64>;
         let mut work = true;
 
         while work {
-            result_copy = super::file::copy(&file, &path, &file_options);
+            let first_name = hard_links.is_first(&file)
+                && writes_destination(Path::new(&file), &path, &file_options);
+            let mut backup = None;
+            result_copy = if is_symlink {
+                copy_symlink(Path::new(&file), &path, &file_options, &mut backup)
+            } else if let Some(target) = hard_links.target(&file) {
+                copy_hard_link(Path::new(&file), target, &path, &file_options, &mut backup)
+            } else {
+                super::file::_copy(Path::new(&file), &path, &file_options, &mut backup)
+            };
             match result_copy {
                 Ok(val) => {
                     result += val;
                     work = false;
+                    if first_name {
+                        hard_links.copied(&file, &path);
+                    }
+                    created.extend(backup);
                 }
                 Err(err) => {
                     let err_msg = err.to_string();
@@ -637,7 +1229,25 @@ where
             }
         }
     }
-    Ok(result)
+    let mut deleted_entries = 0;
+    if options.mirror_copy {
+        let filter = Filter::new(&to, &options.include, &options.exclude);
+        remove_extraneous(
+            Some(from),
+            &to,
+            &filter,
+            false,
+            &created,
+            options,
+            &mut |_| deleted_entries += 1,
+        )?;
+    }
+    copy_dir_attributes(&dirs, options, |_, err| Err(err))?;
+
+    Ok(MirrorResult {
+        copied_bytes: result,
+        deleted_entries,
+    })
 }
 
 /// Return DirContent which contains information about directory:
@@ -710,15 +1320,34 @@ where
     if options.depth != 0 {
         depth = options.depth + 1;
     }
-    _get_dir_content(path, depth)
+    let filter = Filter::for_walk(path.as_ref(), options);
+    _get_dir_content(
+        path.as_ref(),
+        depth,
+        options,
+        &filter,
+        Visit::default(),
+        &mut Vec::new(),
+    )
 }
 
-fn _get_dir_content<P>(path: P, mut depth: u64) -> Result<DirContent>
+// `visit` tells how `filter` handles `path`.
+fn _get_dir_content<P>(
+    path: P,
+    mut depth: u64,
+    options: &DirOptions,
+    filter: &Filter,
+    visit: Visit,
+    ancestors: &mut Vec<DirId>,
+) -> Result<DirContent>
 where
     P: AsRef<Path>,
 {
     let mut directories = Vec::new();
     let mut files = Vec::new();
+    let mut symlinks = Vec::new();
+    let mut cycles = Vec::new();
+    let mut special_files = Vec::new();
     let mut dir_size;
     let item = path.as_ref().to_str();
     if item.is_none() {
@@ -727,38 +1356,153 @@ where
     let item = item.unwrap().to_string();
 
     if path.as_ref().is_dir() {
-        dir_size = path.as_ref().metadata()?.len();
+        let metadata = path.as_ref().metadata()?;
+        let id = dir_id(path.as_ref(), &metadata)?;
+        if ancestors.contains(&id) {
+            cycle_error(path.as_ref(), options)?;
+            cycles.push(item);
+            return Ok(DirContent {
+                dir_size: 0,
+                files,
+                directories,
+                symlinks,
+                cycles,
+                special_files,
+            });
+        }
+
+        dir_size = metadata.len();
         directories.push(item);
+        ancestors.push(id);
         if depth == 0 || depth > 1 {
             if depth > 1 {
                 depth -= 1;
             }
+            filter.enter(path.as_ref())?;
             for entry in read_dir(&path)? {
                 let _path = entry?.path();
+                let metadata = _path.symlink_metadata()?;
+                let is_symlink = metadata.file_type().is_symlink();
+                let entry_visit = if is_symlink && options.symlinks == SymlinkPolicy::Follow {
+                    let target = _path.metadata().unwrap_or_else(|_| metadata.clone());
+                    filter.visit(&_path, &target, visit)
+                } else {
+                    filter.visit(&_path, &metadata, visit)
+                };
+                let entry_visit = match entry_visit {
+                    Some(entry_visit) => entry_visit,
+                    None => continue,
+                };
+                if is_symlink {
+                    match options.symlinks {
+                        SymlinkPolicy::Follow => {}
+                        SymlinkPolicy::Preserve => {
+                            let item = _path.to_str();
+                            if item.is_none() {
+                                err!("Invalid path", ErrorKind::InvalidPath);
+                            }
+                            dir_size += metadata.len();
+                            symlinks.push(item.unwrap().to_string());
+                            continue;
+                        }
+                        SymlinkPolicy::Skip => continue,
+                        SymlinkPolicy::Error => symlink_error(&_path)?,
+                    }
+                }
 
-                match _get_dir_content(_path, depth) {
+                match _get_dir_content(_path, depth, options, filter, entry_visit, ancestors) {
                     Ok(items) => {
                         let mut _files = items.files;
                         let mut _directories = items.directories;
+                        let mut _symlinks = items.symlinks;
+                        let mut _cycles = items.cycles;
+                        let mut _special_files = items.special_files;
                         dir_size += items.dir_size;
                         files.append(&mut _files);
                         directories.append(&mut _directories);
+                        symlinks.append(&mut _symlinks);
+                        cycles.append(&mut _cycles);
+                        special_files.append(&mut _special_files);
                     }
                     Err(err) => return Err(err),
                 }
             }
+            filter.leave(path.as_ref());
+        }
+        ancestors.pop();
+        // A skipped directory is kept only for its content.
+        let is_empty = directories.len() == 1
+            && files.is_empty()
+            && symlinks.is_empty()
+            && special_files.is_empty();
+        if visit.skipped && is_empty {
+            directories.clear();
+            dir_size -= metadata.len();
         }
     } else {
-        dir_size = path.as_ref().metadata()?.len();
-        files.push(item);
+        let metadata = path.as_ref().metadata()?;
+        dir_size = metadata.len();
+        if metadata.is_file() {
+            files.push(item);
+        } else {
+            special_files.push(item);
+        }
     }
     Ok(DirContent {
         dir_size,
         files,
         directories,
+        symlinks,
+        cycles,
+        special_files,
     })
 }
 
+// Identifies a directory independent of the path it was reached by.
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+#[cfg(unix)]
+fn dir_id(_path: &Path, metadata: &Metadata) -> Result<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path, _metadata: &Metadata) -> Result<DirId> {
+    Ok(path.canonicalize()?)
+}
+
+// Returns an error for a directory cycle, unless `options` allow to skip it.
+fn cycle_error(path: &Path, options: &DirOptions) -> Result<()> {
+    if options.cycles == CyclePolicy::Skip {
+        return Ok(());
+    }
+    if let Some(msg) = path.to_str() {
+        let msg = format!("Path \"{}\" is a directory cycle!", msg);
+        err!(&msg, ErrorKind::Cycle);
+    }
+    err!("Path is a directory cycle!", ErrorKind::Cycle);
+}
+
+pub(crate) fn special_file_error(path: &Path) -> Result<()> {
+    if let Some(msg) = path.to_str() {
+        let msg = format!("Path \"{}\" is a special file!", msg);
+        err!(&msg, ErrorKind::SpecialFile);
+    }
+    err!("Path is a special file!", ErrorKind::SpecialFile);
+}
+
+fn symlink_error(path: &Path) -> Result<()> {
+    if let Some(msg) = path.to_str() {
+        let msg = format!("Path \"{}\" is a symbolic link!", msg);
+        err!(&msg, ErrorKind::Symlink);
+    }
+    err!("Path is a symbolic link!", ErrorKind::Symlink);
+}
+
 /// Returns the size of the file or directory in bytes.(!important: folders size not count)
 ///
 /// If used on a directory, this function will recursively iterate over every file and every
@@ -787,37 +1531,141 @@ pub fn get_size<P>(path: P) -> Result<u64>
 where
     P: AsRef<Path>,
 {
+    let options = DirOptions {
+        symlinks: SymlinkPolicy::Preserve,
+        ..Default::default()
+    };
+    get_size2(path, &options)
+}
+
+/// Returns the size of the file or directory in bytes.(!important: folders size not count)
+///
+/// Works like `get_size`, but reads only `options.depth` levels of the directory and
+/// handles symbolic links inside the directory with `options.symlinks`. A preserved symlink
+/// counts with the size of the link itself, not its target.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is not limited to just
+/// these cases:
+///
+/// * This `path` directory does not exist.
+/// * Invalid `path`.
+/// * The current process does not have the permission to access `path`.
+/// * The directory contains a symlink and `options.symlinks` is `SymlinkPolicy::Error`.
+///
+/// # Examples
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::dir::{get_size2, DirOptions, SymlinkPolicy};
+///
+/// let mut options = DirOptions::new();
+/// options.symlinks = SymlinkPolicy::Skip;
+/// let folder_size = get_size2("dir", &options)?;
+/// println!("{}", folder_size); // print directory size in bytes
+/// ```
+pub fn get_size2<P>(path: P, options: &DirOptions) -> Result<u64>
+where
+    P: AsRef<Path>,
+{
+    let mut depth = 0;
+    if options.depth != 0 {
+        depth = options.depth + 1;
+    }
     // Using `fs::symlink_metadata` since we don't want to follow symlinks,
     // as we're calculating the exact size of the requested path itself.
-    let path_metadata = path.as_ref().symlink_metadata()?;
+    let path = path.as_ref();
+    let filter = Filter::for_walk(path, options);
+    _get_size(
+        path,
+        path.symlink_metadata()?,
+        depth,
+        options,
+        &filter,
+        Visit::default(),
+        &mut Vec::new(),
+    )
+}
 
-    let mut size_in_bytes = 0;
+// `visit` tells how `filter` handles `path`.
+fn _get_size(
+    path: &Path,
+    metadata: Metadata,
+    mut depth: u64,
+    options: &DirOptions,
+    filter: &Filter,
+    visit: Visit,
+    ancestors: &mut Vec<DirId>,
+) -> Result<u64> {
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    if depth == 1 {
+        return Ok(0);
+    }
+    if depth > 1 {
+        depth -= 1;
+    }
+    let id = dir_id(path, &metadata)?;
+    if ancestors.contains(&id) {
+        cycle_error(path, options)?;
+        return Ok(0);
+    }
+    ancestors.push(id);
 
-    if path_metadata.is_dir() {
-        for entry in read_dir(&path)? {
-            let entry = entry?;
-            // `DirEntry::metadata` does not follow symlinks (unlike `fs::metadata`), so in the
-            // case of symlinks, this is the size of the symlink itself, not its target.
-            let entry_metadata = entry.metadata()?;
-
-            if entry_metadata.is_dir() {
-                // The size of the directory entry itself will be counted inside the `get_size()` call,
-                // so we intentionally don't also add `entry_metadata.len()` to the total here.
-                size_in_bytes += get_size(entry.path())?;
-            } else {
-                size_in_bytes += entry_metadata.len();
+    let mut size_in_bytes = 0;
+    filter.enter(path)?;
+    for entry in read_dir(path)? {
+        let entry = entry?;
+        // `DirEntry::metadata` does not follow symlinks (unlike `fs::metadata`), so in the
+        // case of symlinks, this is the size of the symlink itself, not its target.
+        let mut entry_metadata = entry.metadata()?;
+        let is_symlink = entry_metadata.file_type().is_symlink();
+        let entry_visit = if is_symlink && options.symlinks == SymlinkPolicy::Follow {
+            let target = entry.path().metadata();
+            filter.visit(
+                &entry.path(),
+                target.as_ref().unwrap_or(&entry_metadata),
+                visit,
+            )
+        } else {
+            filter.visit(&entry.path(), &entry_metadata, visit)
+        };
+        let entry_visit = match entry_visit {
+            Some(entry_visit) => entry_visit,
+            None => continue,
+        };
+        if is_symlink {
+            match options.symlinks {
+                SymlinkPolicy::Follow => entry_metadata = entry.path().metadata()?,
+                SymlinkPolicy::Preserve => {}
+                SymlinkPolicy::Skip => continue,
+                SymlinkPolicy::Error => symlink_error(&entry.path())?,
             }
         }
-    } else {
-        size_in_bytes = path_metadata.len();
+
+        // The size of a directory entry itself is not counted, `_get_size()` only sums up
+        // its content.
+        size_in_bytes += _get_size(
+            &entry.path(),
+            entry_metadata,
+            depth,
+            options,
+            filter,
+            entry_visit,
+            ancestors,
+        )?;
     }
+    filter.leave(path);
+    ancestors.pop();
 
     Ok(size_in_bytes)
 }
 
 /// Copies the directory contents from one place to another using recursive method,
-/// with information about progress. This function will also copy the
-/// permission bits of the original files to destination files (not for directories).
+/// with information about progress. This function will also copy the permission bits
+/// of the original files and directories to the destination files and newly created
+/// directories.
 ///
 /// # Errors
 ///
@@ -847,15 +1695,35 @@ pub fn copy_with_progress<P, Q, F>(
     from: P,
     to: Q,
     options: &CopyOptions,
-    mut progress_handler: F,
+    progress_handler: F,
 ) -> Result<u64>
 where
     P: AsRef<Path>,
     Q: AsRef<Path>,
     F: FnMut(TransitProcess) -> TransitProcessResult,
 {
-    let from = from.as_ref();
+    let mut journal = open_journal(options)?;
+    _copy_with_progress(
+        from.as_ref(),
+        to.as_ref(),
+        options,
+        journal.as_mut(),
+        progress_handler,
+    )
+}
 
+// Like `copy_with_progress`, but records the progress in `journal`, which the caller opened
+// from `options.journal`.
+pub(crate) fn _copy_with_progress<F>(
+    from: &Path,
+    to: &Path,
+    options: &CopyOptions,
+    mut journal: Option<&mut Journal>,
+    mut progress_handler: F,
+) -> Result<u64>
+where
+    F: FnMut(TransitProcess) -> TransitProcessResult,
+{
     if !from.exists() {
         if let Some(msg) = from.to_str() {
             let msg = format!("Path \"{}\" does not exist or you don't have access!", msg);
@@ -867,7 +1735,7 @@ where
         );
     }
 
-    let mut to: PathBuf = to.as_ref().to_path_buf();
+    let mut to: PathBuf = to.to_path_buf();
     if !from.is_dir() {
         if let Some(msg) = from.to_str() {
             let msg = format!("Path \"{}\" is not a directory!", msg);
@@ -877,7 +1745,7 @@ where
     }
 
     let dir_name;
-    if let Some(val) = from.components().last() {
+    if let Some(val) = from.components().next_back() {
         dir_name = val.as_os_str();
     } else {
         err!("Invalid folder from", ErrorKind::InvalidFolder);
@@ -886,39 +1754,38 @@ where
         to.push(dir_name);
     }
 
-    let mut read_options = DirOptions::new();
-    if options.depth > 0 {
-        read_options.depth = options.depth;
-    }
-
-    let dir_content = get_dir_content2(from, &read_options)?;
-    for directory in dir_content.directories {
-        let tmp_to = Path::new(&directory).strip_prefix(from)?;
-        let dir = to.join(&tmp_to);
-        if !dir.exists() {
-            if options.copy_inside {
-                create_all(dir, false)?;
-            } else {
-                create(dir, false)?;
-            }
-        }
+    let dir_content = get_dir_content2(from, &options.dir_options())?;
+    if let Some(journal) = &mut journal {
+        plan_content(journal, &dir_content, from, &to)?;
     }
+    let dirs = create_dirs(dir_content.directories, from, &to, options)?;
+    copy_special_files(&dir_content.special_files, from, &to, options)?;
+    let mut hard_links = find_hard_links(&dir_content.files, options)?;
 
     let mut result: u64 = 0;
     let mut info_process = TransitProcess {
         copied_bytes: 0,
-        total_bytes: dir_content.dir_size,
+        total_bytes: dir_content.dir_size - hard_links.size,
         file_bytes_copied: 0,
         file_total_bytes: 0,
         file_name: String::new(),
         state: TransitState::Normal,
+        deleted_entries: 0,
+        renamed_to: None,
+        backup: None,
     };
 
     let mut options = options.clone();
-    for file in dir_content.files {
+    // Set once the handler skips all files which fail verification.
+    let mut skip_unverified = false;
+    // Renamed copies and backups, which have no entry of the same name in the source.
+    let mut created = HashSet::new();
+    let files = dir_content.files.into_iter().map(|file| (file, false));
+    let symlinks = dir_content.symlinks.into_iter().map(|link| (link, true));
+    for (file, is_symlink) in files.chain(symlinks) {
         let mut to = to.to_path_buf();
         let tp = Path::new(&file).strip_prefix(from)?;
-        let path = to.join(&tp);
+        let path = to.join(tp);
 
         let file_name = path.file_name();
         if file_name.is_none() {
@@ -927,11 +1794,10 @@ where
         let file_name = file_name.unwrap();
         to.push(file_name);
 
-        let mut file_options = super::file::CopyOptions {
-            overwrite: options.overwrite,
-            skip_exist: options.skip_exist,
-            buffer_size: options.buffer_size,
-        };
+        let mut file_options = options.file_options_at(tp);
+        // Failures are reported to the handler below instead of failing the copy.
+        file_options.preserve_xattrs = false;
+        let mut skipped = false;
 
         if let Some(file_name) = file_name.to_str() {
             info_process.file_name = file_name.to_string();
@@ -939,27 +1805,92 @@ where
             err!("Invalid file name", ErrorKind::InvalidFileName);
         }
 
+        let hard_link = hard_links.target(&file).cloned();
         info_process.file_bytes_copied = 0;
-        info_process.file_total_bytes = Path::new(&file).metadata()?.len();
+        info_process.file_total_bytes = if is_symlink {
+            Path::new(&file).symlink_metadata()?.len()
+        } else if hard_link.is_some() {
+            0
+        } else {
+            Path::new(&file).metadata()?.len()
+        };
+
+        if let Some(journal) = &mut journal {
+            if !journal.start(Path::new(&file), &mut file_options) {
+                continue;
+            }
+        }
 
+        let requested = path;
+        let mut path = super::file::destination(&requested, &file_options);
         let mut result_copy: Result<u64>;
         let mut work = true;
         let copied_bytes = result;
         while work {
+            info_process.renamed_to = Some(path.clone()).filter(|path| *path != requested);
+            let first_name = hard_links.is_first(&file)
+                && writes_destination(Path::new(&file), &path, &file_options);
+            let mut backup = None;
             {
-                let _progress_handler = |info: super::file::TransitProcess| {
+                let mut _progress_handler = |info: super::file::TransitProcess| {
                     info_process.copied_bytes = copied_bytes + info.copied_bytes;
                     info_process.file_bytes_copied = info.copied_bytes;
+                    info_process.backup = info.backup;
+                    if let Some(journal) = &mut journal {
+                        journal.progress(Path::new(&file), info.copied_bytes);
+                    }
                     progress_handler(info_process.clone());
                 };
 
-                result_copy =
-                    super::file::copy_with_progress(&file, &path, &file_options, _progress_handler);
+                result_copy = if is_symlink {
+                    copy_symlink(Path::new(&file), &path, &file_options, &mut backup).inspect(
+                        |&val| {
+                            _progress_handler(super::file::TransitProcess {
+                                copied_bytes: val,
+                                total_bytes: val,
+                                backup: backup.clone(),
+                                renamed_to: None,
+                            });
+                        },
+                    )
+                } else if let Some(target) = &hard_link {
+                    copy_hard_link(Path::new(&file), target, &path, &file_options, &mut backup)
+                        .inspect(|&val| {
+                            _progress_handler(super::file::TransitProcess {
+                                copied_bytes: val,
+                                total_bytes: val,
+                                backup: backup.clone(),
+                                renamed_to: None,
+                            });
+                        })
+                } else {
+                    super::file::_copy_with_progress(
+                        Path::new(&file),
+                        &path,
+                        &file_options,
+                        &mut backup,
+                        _progress_handler,
+                    )
+                    .map(|copied| {
+                        skipped = copied.is_none();
+                        copied.unwrap_or(0)
+                    })
+                };
             }
             match result_copy {
                 Ok(val) => {
                     result += val;
                     work = false;
+                    if first_name {
+                        hard_links.copied(&file, &path);
+                    }
+                    if path != requested {
+                        created.insert(path.clone());
+                    }
+                    created.extend(backup);
+                    if let Some(journal) = &mut journal {
+                        journal.done(Path::new(&file), &path)?;
+                    }
                 }
                 Err(err) => match err.kind {
                     ErrorKind::AlreadyExists => {
@@ -982,6 +1913,14 @@ where
                                 options.skip_exist = true;
                             }
                             TransitProcessResult::Retry => {}
+                            TransitProcessResult::Rename => {
+                                path = super::file::free_path(&path);
+                            }
+                            TransitProcessResult::RenameAll => {
+                                path = super::file::free_path(&path);
+                                file_options.overwrite_policy = OverwritePolicy::Rename;
+                                options.overwrite_policy = OverwritePolicy::Rename;
+                            }
                             TransitProcessResult::ContinueOrAbort => {
                                 let err_msg = err.to_string();
                                 err!(err_msg.as_str(), err.kind)
@@ -992,6 +1931,15 @@ where
                             }
                         }
                     }
+                    ErrorKind::VerificationFailed => {
+                        let mut info_process = info_process.clone();
+                        info_process.state = TransitState::VerificationFailed;
+                        let ask = || progress_handler(info_process);
+                        if skip_unverified_file(err, &mut skip_unverified, ask)? {
+                            skipped = true;
+                            work = false;
+                        }
+                    }
                     ErrorKind::PermissionDenied => {
                         let mut info_process = info_process.clone();
                         info_process.state = TransitState::Exists;
@@ -1011,6 +1959,12 @@ where
                                 options.skip_exist = true;
                             }
                             TransitProcessResult::Retry => {}
+                            TransitProcessResult::Rename => {
+                                err!("Rename denied for this situation!", ErrorKind::Other);
+                            }
+                            TransitProcessResult::RenameAll => {
+                                err!("Rename denied for this situation!", ErrorKind::Other);
+                            }
                             TransitProcessResult::ContinueOrAbort => {
                                 let err_msg = err.to_string();
                                 err!(err_msg.as_str(), err.kind)
@@ -1028,14 +1982,45 @@ where
                 },
             }
         }
+
+        if options.preserve_xattrs && !is_symlink && hard_link.is_none() && !skipped {
+            if let Err(err) = super::file::copy_xattrs(Path::new(&file), &path, &file_options) {
+                report_xattr_error(err, info_process.clone(), &mut progress_handler)?;
+            }
+        }
     }
 
+    if options.mirror_copy {
+        let filter = Filter::new(&to, &options.include, &options.exclude);
+        remove_extraneous(
+            Some(from),
+            &to,
+            &filter,
+            false,
+            &created,
+            &options,
+            &mut |path| {
+                info_process.deleted_entries += 1;
+                info_process.file_name = path.to_string_lossy().into_owned();
+                progress_handler(info_process.clone());
+            },
+        )?;
+    }
+    copy_dir_attributes(&dirs, &options, |dir, err| {
+        report_dir_xattr_error(dir, err, &info_process, &mut progress_handler)
+    })?;
+
     Ok(result)
 }
 
 /// Moves the directory contents from one place to another.
-/// This function will also copy the permission bits of the original files to
-/// destination files (not for directories).
+/// This function will also copy the permission bits of the original files and
+/// directories to the destination files and newly created directories.
+///
+/// If the destination directory does not exist yet and `content_only` is not set, the
+/// directory is moved with a single rename. The content is copied and the source removed
+/// only when `to` is on another file system or has to be merged with existing content.
+/// The returned size is still read from the whole moved tree after the rename.
 ///
 /// # Errors
 ///
@@ -1063,10 +2048,7 @@ where
     P: AsRef<Path>,
     Q: AsRef<Path>,
 {
-    let mut is_remove = true;
-    if options.skip_exist && to.as_ref().exists() && !options.overwrite {
-        is_remove = false;
-    }
+    let is_remove = !(may_keep_files(options) && to.as_ref().exists());
     let from = from.as_ref();
 
     if !from.exists() {
@@ -1095,7 +2077,7 @@ where
         );
     }
     let dir_name;
-    if let Some(val) = from.components().last() {
+    if let Some(val) = from.components().next_back() {
         dir_name = val.as_os_str();
     } else {
         err!("Invalid folder from", ErrorKind::InvalidFolder);
@@ -1104,39 +2086,55 @@ where
     if (to.exists() || !options.copy_inside) && !options.content_only {
         to.push(dir_name);
     }
-    let dir_content = get_dir_content(from)?;
-    for directory in dir_content.directories {
-        let tmp_to = Path::new(&directory).strip_prefix(from)?;
-        let dir = to.join(&tmp_to);
-        if !dir.exists() {
-            if options.copy_inside {
-                create_all(dir, false)?;
-            } else {
-                create(dir, false)?;
-            }
-        }
+    if let Some(result) = move_dir_by_rename(from, &to, options)? {
+        return Ok(result);
     }
+
+    // `depth` works only for copy operations.
+    let read_options = DirOptions {
+        depth: 0,
+        ..options.dir_options()
+    };
+    let dir_content = get_dir_content2(from, &read_options)?;
+    // Ignore files are moved too, so the entries to remove are listed before.
+    let moved = if read_options.is_filtered() {
+        Some(dir_content.clone())
+    } else {
+        None
+    };
+    let dirs = create_dirs(dir_content.directories, from, &to, options)?;
+    copy_special_files(&dir_content.special_files, from, &to, options)?;
+    let mut hard_links = find_hard_links(&dir_content.files, options)?;
     let mut result: u64 = 0;
-    for file in dir_content.files {
+    let files = dir_content.files.into_iter().map(|file| (file, false));
+    let symlinks = dir_content.symlinks.into_iter().map(|link| (link, true));
+    for (file, is_symlink) in files.chain(symlinks) {
         let to = to.to_path_buf();
         let tp = Path::new(&file).strip_prefix(from)?;
-        let path = to.join(&tp);
-
-        let file_options = super::file::CopyOptions {
-            overwrite: options.overwrite,
-            skip_exist: options.skip_exist,
-            buffer_size: options.buffer_size,
-        };
+        let file_options = options.file_options_at(tp);
+        let path = super::file::destination(&to.join(tp), &file_options);
 
         let mut result_copy: Result<u64>;
         let mut work = true;
         while work {
             {
-                result_copy = super::file::move_file(&file, &path, &file_options);
+                let first_name = hard_links.is_first(&file)
+                    && writes_destination(Path::new(&file), &path, &file_options);
+                // Symlinks and hard links are removed from the source together with the directory.
+                result_copy = if is_symlink {
+                    copy_symlink(Path::new(&file), &path, &file_options, &mut None)
+                } else if let Some(target) = hard_links.target(&file) {
+                    copy_hard_link(Path::new(&file), target, &path, &file_options, &mut None)
+                } else {
+                    super::file::move_file(&file, &path, &file_options)
+                };
                 match result_copy {
                     Ok(val) => {
                         result += val;
                         work = false;
+                        if first_name {
+                            hard_links.copied(&file, &path);
+                        }
                     }
                     Err(err) => {
                         let err_msg = err.to_string();
@@ -1146,16 +2144,20 @@ where
             }
         }
     }
+    copy_dir_attributes(&dirs, options, |_, err| Err(err))?;
     if is_remove {
-        remove(from)?;
+        remove_moved(from, moved.as_ref())?;
     }
 
     Ok(result)
 }
 
 /// Moves the directory contents from one place to another with information about progress.
-/// This function will also copy the permission bits of the original files to
-/// destination files (not for directories).
+/// This function will also copy the permission bits of the original files and
+/// directories to the destination files and newly created directories.
+///
+/// Like [`move_dir`], this tries a single rename first. In that case the progress handler
+/// is called only once, after the whole directory has been moved and its size was read.
 ///
 /// # Errors
 ///
@@ -1193,10 +2195,7 @@ where
     Q: AsRef<Path>,
     F: FnMut(TransitProcess) -> TransitProcessResult,
 {
-    let mut is_remove = true;
-    if options.skip_exist && to.as_ref().exists() && !options.overwrite {
-        is_remove = false;
-    }
+    let mut is_remove = !(may_keep_files(options) && to.as_ref().exists());
     let from = from.as_ref();
 
     if !from.exists() {
@@ -1219,7 +2218,7 @@ where
         err!("Path is not a directory!", ErrorKind::InvalidFolder);
     }
     let dir_name;
-    if let Some(val) = from.components().last() {
+    if let Some(val) = from.components().next_back() {
         dir_name = val.as_os_str();
     } else {
         err!("Invalid folder from", ErrorKind::InvalidFolder);
@@ -1228,34 +2227,61 @@ where
         to.push(dir_name);
     }
 
-    let dir_content = get_dir_content(from)?;
-    for directory in dir_content.directories {
-        let tmp_to = Path::new(&directory).strip_prefix(from)?;
-        let dir = to.join(&tmp_to);
-        if !dir.exists() {
-            if options.copy_inside {
-                create_all(dir, false)?;
-            } else {
-                create(dir, false)?;
-            }
-        }
+    if let Some(result) = move_dir_by_rename(from, &to, options)? {
+        progress_handler(TransitProcess {
+            copied_bytes: result,
+            total_bytes: result,
+            file_bytes_copied: 0,
+            file_total_bytes: 0,
+            file_name: String::new(),
+            state: TransitState::Normal,
+            deleted_entries: 0,
+            renamed_to: None,
+            backup: None,
+        });
+        return Ok(result);
     }
 
+    // `depth` works only for copy operations.
+    let read_options = DirOptions {
+        depth: 0,
+        ..options.dir_options()
+    };
+    let dir_content = get_dir_content2(from, &read_options)?;
+    // Ignore files are moved too, so the entries to remove are listed before. Files which
+    // fail verification may be skipped, and are not removed then.
+    let mut moved = if read_options.is_filtered() || options.verify != Verify::None {
+        Some(dir_content.clone())
+    } else {
+        None
+    };
+    let dirs = create_dirs(dir_content.directories, from, &to, options)?;
+    copy_special_files(&dir_content.special_files, from, &to, options)?;
+    let mut hard_links = find_hard_links(&dir_content.files, options)?;
+
     let mut result: u64 = 0;
     let mut info_process = TransitProcess {
         copied_bytes: 0,
-        total_bytes: dir_content.dir_size,
+        total_bytes: dir_content.dir_size - hard_links.size,
         file_bytes_copied: 0,
         file_total_bytes: 0,
         file_name: String::new(),
         state: TransitState::Normal,
+        deleted_entries: 0,
+        renamed_to: None,
+        backup: None,
     };
 
     let mut options = options.clone();
-    for file in dir_content.files {
+    // Set once the handler skips all files which fail verification.
+    let mut skip_unverified = false;
+    let mut unverified = Vec::new();
+    let files = dir_content.files.into_iter().map(|file| (file, false));
+    let symlinks = dir_content.symlinks.into_iter().map(|link| (link, true));
+    for (file, is_symlink) in files.chain(symlinks) {
         let mut to = to.to_path_buf();
         let tp = Path::new(&file).strip_prefix(from)?;
-        let path = to.join(&tp);
+        let path = to.join(tp);
 
         let file_name = path.file_name();
         if file_name.is_none() {
@@ -1264,11 +2290,7 @@ where
         let file_name = file_name.unwrap();
         to.push(file_name);
 
-        let mut file_options = super::file::CopyOptions {
-            overwrite: options.overwrite,
-            skip_exist: options.skip_exist,
-            buffer_size: options.buffer_size,
-        };
+        let mut file_options = options.file_options_at(tp);
 
         if let Some(file_name) = file_name.to_str() {
             info_process.file_name = file_name.to_string();
@@ -1276,31 +2298,81 @@ where
             err!("Invalid file name", ErrorKind::InvalidFileName);
         }
 
+        let hard_link = hard_links.target(&file).cloned();
         info_process.file_bytes_copied = 0;
-        info_process.file_total_bytes = Path::new(&file).metadata()?.len();
+        info_process.file_total_bytes = if is_symlink {
+            Path::new(&file).symlink_metadata()?.len()
+        } else if hard_link.is_some() {
+            0
+        } else {
+            Path::new(&file).metadata()?.len()
+        };
 
+        let requested = path;
+        let mut path = super::file::destination(&requested, &file_options);
         let mut result_copy: Result<u64>;
         let mut work = true;
         let copied_bytes = result;
         while work {
+            info_process.renamed_to = Some(path.clone()).filter(|path| *path != requested);
+            let first_name = hard_links.is_first(&file)
+                && writes_destination(Path::new(&file), &path, &file_options);
+            // Failures are reported to the handler below instead of failing the move.
+            let mut xattr_error = None;
             {
-                let _progress_handler = |info: super::file::TransitProcess| {
+                let mut _progress_handler = |info: super::file::TransitProcess| {
                     info_process.copied_bytes = copied_bytes + info.copied_bytes;
                     info_process.file_bytes_copied = info.copied_bytes;
+                    info_process.backup = info.backup;
                     progress_handler(info_process.clone());
                 };
-
-                result_copy = super::file::move_file_with_progress(
-                    &file,
-                    &path,
-                    &file_options,
-                    _progress_handler,
-                );
+                let mut backup = None;
+
+                // Symlinks and hard links are removed from the source together with the directory.
+                result_copy = if is_symlink {
+                    copy_symlink(Path::new(&file), &path, &file_options, &mut backup).inspect(
+                        |&val| {
+                            _progress_handler(super::file::TransitProcess {
+                                copied_bytes: val,
+                                total_bytes: val,
+                                backup: backup.clone(),
+                                renamed_to: None,
+                            });
+                        },
+                    )
+                } else if let Some(target) = &hard_link {
+                    copy_hard_link(Path::new(&file), target, &path, &file_options, &mut backup)
+                        .inspect(|&val| {
+                            _progress_handler(super::file::TransitProcess {
+                                copied_bytes: val,
+                                total_bytes: val,
+                                backup: backup.clone(),
+                                renamed_to: None,
+                            });
+                        })
+                } else {
+                    super::file::_move_file_with_progress(
+                        Path::new(&file),
+                        &path,
+                        &file_options,
+                        |err| {
+                            xattr_error = Some(err);
+                            Ok(())
+                        },
+                        _progress_handler,
+                    )
+                };
             }
             match result_copy {
                 Ok(val) => {
                     result += val;
                     work = false;
+                    if first_name {
+                        hard_links.copied(&file, &path);
+                    }
+                    if let Some(err) = xattr_error {
+                        report_xattr_error(err, info_process.clone(), &mut progress_handler)?;
+                    }
                 }
                 Err(err) => match err.kind {
                     ErrorKind::AlreadyExists => {
@@ -1325,6 +2397,14 @@ where
                                 options.skip_exist = true;
                             }
                             TransitProcessResult::Retry => {}
+                            TransitProcessResult::Rename => {
+                                path = super::file::free_path(&path);
+                            }
+                            TransitProcessResult::RenameAll => {
+                                path = super::file::free_path(&path);
+                                file_options.overwrite_policy = OverwritePolicy::Rename;
+                                options.overwrite_policy = OverwritePolicy::Rename;
+                            }
                             TransitProcessResult::ContinueOrAbort => {
                                 let err_msg = err.to_string();
                                 err!(err_msg.as_str(), err.kind)
@@ -1335,6 +2415,15 @@ where
                             }
                         }
                     }
+                    ErrorKind::VerificationFailed => {
+                        let mut info_process = info_process.clone();
+                        info_process.state = TransitState::VerificationFailed;
+                        let ask = || progress_handler(info_process);
+                        if skip_unverified_file(err, &mut skip_unverified, ask)? {
+                            unverified.push(file.clone());
+                            work = false;
+                        }
+                    }
                     ErrorKind::PermissionDenied => {
                         let mut info_process = info_process.clone();
                         info_process.state = TransitState::Exists;
@@ -1355,6 +2444,12 @@ where
                                 options.skip_exist = true;
                             }
                             TransitProcessResult::Retry => {}
+                            TransitProcessResult::Rename => {
+                                err!("Rename denied for this situation!", ErrorKind::Other);
+                            }
+                            TransitProcessResult::RenameAll => {
+                                err!("Rename denied for this situation!", ErrorKind::Other);
+                            }
                             TransitProcessResult::ContinueOrAbort => {
                                 let err_msg = err.to_string();
                                 err!(err_msg.as_str(), err.kind)
@@ -1373,13 +2468,566 @@ where
             }
         }
     }
+    copy_dir_attributes(&dirs, &options, |dir, err| {
+        report_dir_xattr_error(dir, err, &info_process, &mut progress_handler)
+    })?;
     if is_remove {
-        remove(from)?;
+        // Files left out after failing verification stay in the source.
+        if let Some(moved) = &mut moved {
+            moved.files.retain(|file| !unverified.contains(file));
+        }
+        remove_moved(from, moved.as_ref())?;
     }
 
     Ok(result)
 }
 
+// Whether the overwrite policy of `options` may keep existing files at the destination.
+// Files which are kept at the destination are left in the source, so a move into an
+// existing directory does not remove its source.
+pub(crate) fn may_keep_files(options: &CopyOptions) -> bool {
+    let policy = super::file::effective_overwrite_policy(
+        options.overwrite,
+        options.skip_exist,
+        options.overwrite_policy,
+    );
+    !matches!(
+        policy,
+        OverwritePolicy::Never | OverwritePolicy::Always | OverwritePolicy::Rename
+    )
+}
+
+// Handles a file which failed verification. Returns true if it is skipped, which `ask`
+// decides unless all such files are skipped already, and false if it is copied again.
+// Other answers return `err`.
+pub(crate) fn skip_unverified_file<F>(err: Error, skip_all: &mut bool, ask: F) -> Result<bool>
+where
+    F: FnOnce() -> TransitProcessResult,
+{
+    if *skip_all {
+        return Ok(true);
+    }
+    match ask() {
+        TransitProcessResult::Retry => Ok(false),
+        TransitProcessResult::Skip => Ok(true),
+        TransitProcessResult::SkipAll => {
+            *skip_all = true;
+            Ok(true)
+        }
+        _ => {
+            let err_msg = err.to_string();
+            err!(err_msg.as_str(), err.kind)
+        }
+    }
+}
+
+// Opens the journal of `options`, if any.
+pub(crate) fn open_journal(options: &CopyOptions) -> Result<Option<Journal>> {
+    match &options.journal {
+        Some(path) => Ok(Some(Journal::open(
+            path,
+            options.durability != Durability::None,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+// Records the files which `copy_with_progress(from, to, options)` copies in `journal`,
+// before a copy of several items starts.
+pub(crate) fn plan_journal(
+    journal: &mut Journal,
+    from: &Path,
+    to: &Path,
+    options: &CopyOptions,
+) -> Result<()> {
+    let mut to = to.to_path_buf();
+    if (to.exists() || !options.copy_inside) && !options.content_only {
+        if let Some(dir_name) = from.components().next_back() {
+            to.push(dir_name.as_os_str());
+        }
+    }
+    let dir_content = get_dir_content2(from, &options.dir_options())?;
+    plan_content(journal, &dir_content, from, &to)
+}
+
+// Records the files and symlinks of `dir_content`, copied from `from` to `to`, in `journal`.
+fn plan_content(
+    journal: &mut Journal,
+    dir_content: &DirContent,
+    from: &Path,
+    to: &Path,
+) -> Result<()> {
+    for file in dir_content.files.iter().chain(&dir_content.symlinks) {
+        let file = Path::new(file);
+        journal.plan(file, &to.join(file.strip_prefix(from)?))?;
+    }
+    Ok(())
+}
+
+// A destination directory together with the metadata of its source directory.
+struct DirAttributes {
+    source: PathBuf,
+    path: PathBuf,
+    metadata: Metadata,
+    created: bool,
+}
+
+// Creates the destination directories for `directories` read from `from`. Returns the
+// destination directories with the metadata of their source, see `copy_dir_attributes`.
+fn create_dirs(
+    directories: Vec<String>,
+    from: &Path,
+    to: &Path,
+    options: &CopyOptions,
+) -> Result<Vec<DirAttributes>> {
+    let mut dirs = Vec::new();
+    for directory in directories {
+        let tmp_to = Path::new(&directory).strip_prefix(from)?;
+        let dir = to.join(tmp_to);
+        let created = !dir.exists();
+        if created {
+            if options.copy_inside {
+                create_all(&dir, false)?;
+            } else {
+                create(&dir, false)?;
+            }
+            // Moved files must not lose their directory on a crash.
+            if options.durability == Durability::FileAndDirectory {
+                super::file::sync_parent(&dir)?;
+            }
+        }
+        dirs.push(DirAttributes {
+            source: PathBuf::from(&directory),
+            path: dir,
+            metadata: Path::new(&directory).metadata()?,
+            created,
+        });
+    }
+    Ok(dirs)
+}
+
+// Removes the entries of `to` which have no counterpart in `from`, see
+// `CopyOptions::mirror_copy`, and calls `removed` with the path of each of them relative
+// to the root of `filter`, the copied directory. Entries left out by the filter are kept,
+// `included` tells whether `to` matches its include patterns. `from` is `None` inside a
+// directory which does not exist in the source. Entries in `created`, which the copy wrote
+// under other names than the ones of the source like renamed copies and backups, are kept
+// too. Returns true if all entries of `to` were removed.
+pub(crate) fn remove_extraneous(
+    from: Option<&Path>,
+    to: &Path,
+    filter: &Filter,
+    included: bool,
+    created: &HashSet<PathBuf>,
+    options: &CopyOptions,
+    removed: &mut dyn FnMut(&Path),
+) -> Result<bool> {
+    let mut emptied = true;
+    for entry in read_dir(to)? {
+        let entry = entry?;
+        let path = entry.path();
+        let relative = path.strip_prefix(filter.root)?;
+        let is_dir = entry.file_type()?.is_dir();
+        let included = match filter.check(&path, is_dir, included) {
+            Some(included) if included || is_dir => included,
+            _ => {
+                emptied = false;
+                continue;
+            }
+        };
+        let excluded = options
+            .mirror_exclude
+            .iter()
+            .any(|exclude| relative.starts_with(exclude));
+        if excluded || created.contains(&path) {
+            emptied = false;
+            continue;
+        }
+        let source = from
+            .map(|from| from.join(entry.file_name()))
+            .filter(|source| source.symlink_metadata().is_ok());
+        if is_dir && source.as_ref().is_none_or(|source| source.is_dir()) {
+            // Excluded entries may keep the directory from being removed.
+            let dir_emptied = remove_extraneous(
+                source.as_deref(),
+                &path,
+                filter,
+                included,
+                created,
+                options,
+                removed,
+            )?;
+            if source.is_some() || !dir_emptied {
+                emptied = false;
+                continue;
+            }
+        } else if source.is_some() {
+            emptied = false;
+            continue;
+        }
+
+        if !options.mirror_dry_run {
+            if is_dir {
+                std::fs::remove_dir(&path)?;
+            } else {
+                remove_file(&path)?;
+            }
+        }
+        removed(relative);
+    }
+    Ok(emptied)
+}
+
+// Tells the progress handler that extended attributes of `info_process.file_name` could
+// not be copied. Returns `err` if the handler decides to abort.
+fn report_xattr_error<F>(
+    err: Error,
+    mut info_process: TransitProcess,
+    progress_handler: &mut F,
+) -> Result<()>
+where
+    F: FnMut(TransitProcess) -> TransitProcessResult,
+{
+    info_process.state = TransitState::XattrFailed;
+    match progress_handler(info_process) {
+        TransitProcessResult::Abort => Err(err),
+        _ => Ok(()),
+    }
+}
+
+// Like `report_xattr_error` for the destination directory `dir`.
+fn report_dir_xattr_error<F>(
+    dir: &Path,
+    err: Error,
+    info_process: &TransitProcess,
+    progress_handler: &mut F,
+) -> Result<()>
+where
+    F: FnMut(TransitProcess) -> TransitProcessResult,
+{
+    let mut info_process = info_process.clone();
+    info_process.file_name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    report_xattr_error(err, info_process, progress_handler)
+}
+
+// Copies the attributes of the source directories to the directories returned by
+// `create_dirs`. This has to run after the content is written: writing into a directory
+// changes its modification time, and a read-only permission would prevent the writing.
+// Permission bits are only copied to directories which did not exist before. With
+// `Durability::FileAndDirectory` the directories are flushed afterwards, together with the
+// entries created in them.
+//
+// Errors from copying extended attributes are passed to `xattr_failed`.
+fn copy_dir_attributes<F>(
+    dirs: &[DirAttributes],
+    options: &CopyOptions,
+    mut xattr_failed: F,
+) -> Result<()>
+where
+    F: FnMut(&Path, Error) -> Result<()>,
+{
+    let file_options = options.file_options();
+    // Children first, so that a parent without access rights does not hide them.
+    for dir in dirs.iter().rev() {
+        super::file::copy_owner(&dir.path, &dir.metadata, &file_options)?;
+        if options.preserve_xattrs {
+            if let Err(err) = super::file::copy_xattrs(&dir.source, &dir.path, &file_options) {
+                xattr_failed(&dir.path, err)?;
+            }
+        }
+        if options.preserve_times {
+            super::file::set_file_times(&dir.path, &dir.metadata)?;
+        }
+        if dir.created {
+            set_permissions(&dir.path, dir.metadata.permissions())?;
+        }
+        if options.durability == Durability::FileAndDirectory {
+            crate::sys::sync_dir(&dir.path)?;
+        }
+    }
+    Ok(())
+}
+
+// Moves the directory with a single `rename` when nothing has to be merged into
+// the destination. Returns `None` if the caller should fall back to copying the
+// content and removing the source, e.g. when `to` is on another file system.
+fn move_dir_by_rename(from: &Path, to: &Path, options: &CopyOptions) -> Result<Option<u64>> {
+    // A rename always keeps symlinks as they are.
+    let keeps_symlinks = matches!(
+        options.symlinks,
+        SymlinkPolicy::Follow | SymlinkPolicy::Preserve
+    );
+    // A rename always keeps special files as they are.
+    let keeps_special_files = options.special_files == SpecialFilePolicy::Recreate;
+    if options.content_only
+        || to.exists()
+        || !keeps_symlinks
+        || !keeps_special_files
+        || options.is_filtered()
+    {
+        return Ok(None);
+    }
+    if options.copy_inside {
+        if let Some(parent) = to.parent() {
+            create_dir_all(parent)?;
+        }
+    }
+
+    match rename(from, to) {
+        Ok(()) => {
+            super::file::sync_renamed(from, to, options.durability)?;
+            // The rename itself does not read the tree, only the returned size does.
+            Ok(Some(get_size(to)?))
+        }
+        Err(ref err) if err.kind() == IoErrorKind::CrossesDevices => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// Removes the source of a moved directory. If the filters left out entries, only the moved
+// entries of `dir_content` are removed, and the directories which are then empty.
+fn remove_moved(from: &Path, dir_content: Option<&DirContent>) -> Result<()> {
+    let dir_content = match dir_content {
+        Some(dir_content) => dir_content,
+        None => return remove(from),
+    };
+    let entries = dir_content.files.iter().chain(&dir_content.symlinks);
+    for entry in entries.chain(&dir_content.special_files) {
+        if Path::new(entry).symlink_metadata().is_ok() {
+            remove_file(entry)?;
+        }
+    }
+    // Children first.
+    for directory in dir_content.directories.iter().rev() {
+        let result = if Path::new(directory).symlink_metadata()?.is_symlink() {
+            remove_file(directory)
+        } else {
+            std::fs::remove_dir(directory)
+        };
+        match result {
+            Err(ref err) if err.kind() == IoErrorKind::DirectoryNotEmpty => {}
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+// Removes an existing `to`, or moves it to its `backup`, before the entry `from` is
+// re-created there. Returns false if the existing entry should be kept.
+fn prepare_destination(
+    from: &Path,
+    to: &Path,
+    options: &super::file::CopyOptions,
+    backup: &mut Option<PathBuf>,
+) -> Result<bool> {
+    if let Ok(to_metadata) = to.symlink_metadata() {
+        let from_metadata = from.symlink_metadata()?;
+        if !super::file::should_overwrite(from, &from_metadata, to, &to_metadata, options)? {
+            return Ok(false);
+        }
+        *backup = super::file::backup(to, options)?;
+        if backup.is_none() {
+            remove_file(to)?;
+        }
+    }
+    Ok(true)
+}
+
+// Re-creates the symbolic link `from` at `to`. Returns the size of the link itself.
+fn copy_symlink(
+    from: &Path,
+    to: &Path,
+    options: &super::file::CopyOptions,
+    backup: &mut Option<PathBuf>,
+) -> Result<u64> {
+    if !prepare_destination(from, to, options, backup)? {
+        return Ok(0);
+    }
+
+    let target = read_link(from)?;
+    create_symlink(&target, from, to)?;
+    let metadata = from.symlink_metadata()?;
+    super::file::copy_owner(to, &metadata, options)?;
+    Ok(metadata.len())
+}
+
+// Re-creates the FIFOs and device nodes among `special_files` read from `from` in `to`,
+// following `options.special_files`. Sockets can not be re-created and are ignored.
+fn copy_special_files(
+    special_files: &[String],
+    from: &Path,
+    to: &Path,
+    options: &CopyOptions,
+) -> Result<()> {
+    for file in special_files {
+        let file = Path::new(file);
+        match options.special_files {
+            SpecialFilePolicy::Recreate => {}
+            SpecialFilePolicy::Skip => continue,
+            SpecialFilePolicy::Error => special_file_error(file)?,
+        }
+        let metadata = file.metadata()?;
+        if is_socket(&metadata) {
+            continue;
+        }
+        let relative = file.strip_prefix(from)?;
+        let file_options = options.file_options_at(relative);
+        let path = super::file::destination(&to.join(relative), &file_options);
+        if !prepare_destination(file, &path, &file_options, &mut None)? {
+            continue;
+        }
+        create_special_file(&path, &metadata)?;
+        super::file::copy_owner(&path, &metadata, &file_options)?;
+        if options.preserve_times {
+            super::file::set_file_times(&path, &metadata)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn is_socket(metadata: &Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    metadata.file_type().is_socket()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_socket(_metadata: &Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn create_special_file(path: &Path, metadata: &Metadata) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    crate::sys::mknod(path, metadata.mode(), metadata.rdev())?;
+    // The mode passed to `mknod` is reduced by the umask.
+    set_permissions(path, metadata.permissions())?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn create_special_file(path: &Path, _metadata: &Metadata) -> Result<()> {
+    special_file_error(path)
+}
+
+// Source files which are names of the same file.
+#[derive(Default)]
+pub(crate) struct HardLinks {
+    // Index into `targets` by the source path of every name of a file with several names.
+    groups: HashMap<String, usize>,
+    // Destination of the name of each file which was copied first.
+    targets: Vec<Option<PathBuf>>,
+    // Size of the data shared with the first names.
+    pub(crate) size: u64,
+}
+
+impl HardLinks {
+    // Returns the copy of another name of `file`, which `file` is linked to.
+    pub(crate) fn target(&self, file: &str) -> Option<&PathBuf> {
+        let group = *self.groups.get(file)?;
+        self.targets[group].as_ref()
+    }
+
+    // Returns true if the copy of `file` has to be recorded with `copied`.
+    pub(crate) fn is_first(&self, file: &str) -> bool {
+        self.groups.contains_key(file) && self.target(file).is_none()
+    }
+
+    // Records that `file` was copied to `to`, so that further names are linked to it. A name
+    // which is skipped is not recorded, so the next name of the file is copied instead.
+    pub(crate) fn copied(&mut self, file: &str, to: &Path) {
+        if let Some(&group) = self.groups.get(file) {
+            self.targets[group].get_or_insert_with(|| to.to_path_buf());
+        }
+    }
+}
+
+// Finds the hard links among `files` if `options.preserve_hard_links` is set.
+#[cfg(unix)]
+pub(crate) fn find_hard_links(files: &[String], options: &CopyOptions) -> Result<HardLinks> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut hard_links = HardLinks::default();
+    if !options.preserve_hard_links {
+        return Ok(hard_links);
+    }
+    let mut groups = HashMap::new();
+    for file in files {
+        let metadata = Path::new(file).symlink_metadata()?;
+        // Followed symlinks are copied as independent files.
+        if metadata.file_type().is_symlink() || metadata.nlink() < 2 {
+            continue;
+        }
+        let next = hard_links.targets.len();
+        let group = *groups
+            .entry((metadata.dev(), metadata.ino()))
+            .or_insert(next);
+        if group == next {
+            hard_links.targets.push(None);
+        } else {
+            hard_links.size += metadata.len();
+        }
+        hard_links.groups.insert(file.clone(), group);
+    }
+    Ok(hard_links)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn find_hard_links(_files: &[String], _options: &CopyOptions) -> Result<HardLinks> {
+    Ok(HardLinks::default())
+}
+
+// Returns true if copying `from` to `to` writes `to` instead of keeping an existing file.
+fn writes_destination(from: &Path, to: &Path, options: &super::file::CopyOptions) -> bool {
+    match (from.metadata(), to.metadata()) {
+        (Ok(from_metadata), Ok(to_metadata)) => {
+            super::file::should_overwrite(from, &from_metadata, to, &to_metadata, options)
+                .unwrap_or(false)
+        }
+        _ => true,
+    }
+}
+
+// Creates `to` as a hard link to `target`, the copy of another name of the source file
+// `from`. The data is counted for `target` already, so this returns 0.
+fn copy_hard_link(
+    from: &Path,
+    target: &Path,
+    to: &Path,
+    options: &super::file::CopyOptions,
+    backup: &mut Option<PathBuf>,
+) -> Result<u64> {
+    if prepare_destination(from, to, options, backup)? {
+        std::fs::hard_link(target, to)?;
+    }
+    Ok(0)
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, _from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, to)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(target, to)
+    } else {
+        std::os::windows::fs::symlink_file(target, to)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn create_symlink(_target: &Path, _from: &Path, _to: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "Symbolic links are not supported on this platform",
+    ))
+}
+
 /// Removes directory.
 ///
 /// # Example
@@ -24,6 +24,16 @@ pub enum ErrorKind {
     InvalidFileName,
     /// Invalid path.
     InvalidPath,
+    /// Path is a symbolic link which is not allowed by the `SymlinkPolicy`.
+    Symlink,
+    /// Directory contains itself, e.g. through a symlink loop or a bind mount.
+    Cycle,
+    /// Path is a FIFO, device node or socket which is not allowed by the `SpecialFilePolicy`.
+    SpecialFile,
+    /// The file system changed after a `Plan` was made.
+    PlanOutdated,
+    /// The copy of a file does not match its source, see `file::CopyOptions::verify`.
+    VerificationFailed,
     /// Any I/O error.
     Io(IoError),
     /// Any StripPrefix error.
@@ -46,6 +56,11 @@ impl ErrorKind {
             ErrorKind::InvalidFile => "invalid file error",
             ErrorKind::InvalidFileName => "invalid file name error",
             ErrorKind::InvalidPath => "invalid path error",
+            ErrorKind::Symlink => "symbolic link error",
+            ErrorKind::Cycle => "directory cycle error",
+            ErrorKind::SpecialFile => "special file error",
+            ErrorKind::PlanOutdated => "outdated plan error",
+            ErrorKind::VerificationFailed => "verification error",
             ErrorKind::Io(_) => "Io error",
             ErrorKind::StripPrefix(_) => "Strip prefix error",
             ErrorKind::OsString(_) => "OsString error",
@@ -141,18 +156,19 @@ impl From<OsString> for Error {
 
 impl From<IoError> for Error {
     fn from(err: IoError) -> Error {
-        let err_kind: ErrorKind;
-        match err.kind() {
-            IoErrorKind::NotFound => err_kind = ErrorKind::NotFound,
-            IoErrorKind::PermissionDenied => err_kind = ErrorKind::PermissionDenied,
-            IoErrorKind::AlreadyExists => err_kind = ErrorKind::AlreadyExists,
-            IoErrorKind::Interrupted => err_kind = ErrorKind::Interrupted,
-            IoErrorKind::Other => err_kind = ErrorKind::Other,
+        let err_kind = match err.kind() {
+            IoErrorKind::NotFound => ErrorKind::NotFound,
+            IoErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            IoErrorKind::AlreadyExists => ErrorKind::AlreadyExists,
+            IoErrorKind::Interrupted => ErrorKind::Interrupted,
+            IoErrorKind::Other => ErrorKind::Other,
             _ => {
-                err_kind = ErrorKind::Io(err);
-                return Error::new(err_kind, "Io error. Look inside err_kind for more details.");
+                return Error::new(
+                    ErrorKind::Io(err),
+                    "Io error. Look inside err_kind for more details.",
+                );
             }
-        }
+        };
         Error::new(err_kind, &err.to_string())
     }
 }
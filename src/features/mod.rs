@@ -0,0 +1,4 @@
+// Optional features which depend on external crates, each behind its own cargo feature.
+
+#[cfg(feature = "sha256")]
+pub(crate) mod sha256;
@@ -0,0 +1,19 @@
+// SHA-256 digests of file contents, see `file::Verify::Sha256`.
+
+use sha2::{Digest, Sha256 as Sha256Hasher};
+
+pub(crate) struct Sha256(Sha256Hasher);
+
+impl Sha256 {
+    pub(crate) fn new() -> Sha256 {
+        Sha256(Sha256Hasher::new())
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.0.finalize().to_vec()
+    }
+}
@@ -1,17 +1,149 @@
 use crate::error::{Error, ErrorKind, Result};
 use std;
-use std::fs::{remove_file, File};
-use std::io::{Read, Write};
-use std::path::Path;
+use std::ffi::{OsStr, OsString};
+use std::fs::{remove_file, rename, set_permissions, File, FileTimes, Metadata, OpenOptions};
+use std::io::{ErrorKind as IoErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Function which maps the uid and gid of a source file to the ones set on its copy.
+pub type OwnerMap = Arc<dyn Fn(u32, u32) -> (u32, u32) + Send + Sync>;
+
+/// Available ways to handle a destination which exists already.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    /// Stop with an `ErrorKind::AlreadyExists` error.
+    #[default]
+    Never,
+    /// Overwrite the destination.
+    Always,
+    /// Keep the destination.
+    Skip,
+    /// Overwrite the destination if the source was modified later.
+    IfNewer,
+    /// Overwrite the destination if its size differs from the source.
+    IfSizeDiffers,
+    /// Overwrite the destination if its content differs from the source.
+    IfContentDiffers,
+    /// Overwrite the destination if the source was modified later or the sizes differ.
+    IfNewerOrSizeDiffers,
+    /// Keep the destination and write to the first free name like `name (1).ext` next to it.
+    Rename,
+}
+
+/// Available ways to use reflinks, copies which share the data with the source until one of
+/// them is changed. Reflinks are supported on Linux by file systems like Btrfs and XFS.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reflink {
+    /// Try a reflink and copy the data if that is not possible.
+    #[default]
+    Auto,
+    /// Return an error if the file can not be reflinked.
+    Always,
+    /// Always copy the data. This disables `copy_file_range` too, which may reflink on its own.
+    Never,
+}
+
+/// Available ways to keep an existing destination before it is overwritten, like
+/// `cp --backup`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backup {
+    /// Replace the destination without a backup.
+    #[default]
+    None,
+    /// Rename the destination to its name followed by `backup_suffix`, like `name~`. An
+    /// older backup of that name is replaced.
+    Simple,
+    /// Rename the destination to its name with the next free number, like `name.~3~`.
+    Numbered,
+    /// Make a numbered backup if the destination has numbered backups already, otherwise a
+    /// simple one.
+    Existing,
+}
+
+/// Available levels of flushing copies to the disk with `fsync`, so that they survive a
+/// crash or a power loss. Moves flush the destination before the source is removed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Durability {
+    /// Leave flushing to the operating system.
+    #[default]
+    None,
+    /// Flush the data and metadata of every written file.
+    FileData,
+    /// Flush the written files and the directories whose entries were created or renamed.
+    FileAndDirectory,
+}
+
+/// Available digests to verify copies with, see `CopyOptions::verify`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Verify {
+    /// Do not verify copies.
+    #[default]
+    None,
+    /// Compare XXH64 digests, a fast non-cryptographic hash.
+    Fast,
+    /// Compare SHA-256 digests. Requires the `sha256` feature.
+    #[cfg(feature = "sha256")]
+    Sha256,
+}
 
 // Options and flags which can be used to configure how a file will be  copied  or moved.
+#[derive(Clone)]
 pub struct CopyOptions {
-    /// Sets the option true for overwrite existing files.
+    /// Sets the option true for overwrite existing files. Shorthand for
+    /// `OverwritePolicy::Always`, which takes precedence over `overwrite_policy`.
     pub overwrite: bool,
-    /// Sets the option true for skip existing files.
+    /// Sets the option true for skip existing files. Shorthand for `OverwritePolicy::Skip`,
+    /// used if `overwrite_policy` is `OverwritePolicy::Never`.
     pub skip_exist: bool,
+    /// Sets how existing files are handled.
+    pub overwrite_policy: OverwritePolicy,
     /// Sets buffer size for copy/move work only with receipt information about process work.
     pub buffer_size: usize,
+    /// Sets the option true for copy access and modification times of the file.
+    pub preserve_times: bool,
+    /// Sets the option true for copy the owner (uid and gid) of the file. Works only on Unix.
+    pub preserve_owner: bool,
+    /// Sets the option true for return an error if the owner can not be copied because the
+    /// process lacks the privilege. Otherwise such an error is ignored.
+    pub strict_owner: bool,
+    /// Sets a function which maps the owner of the source to the owner of the copy.
+    pub owner_map: Option<OwnerMap>,
+    /// Sets the option true for copy extended attributes of the file. Works only on Linux.
+    pub preserve_xattrs: bool,
+    /// Namespaces of the extended attributes which are copied, e.g. `user` or `system`.
+    /// POSIX ACLs are stored in the `system` namespace.
+    pub xattr_namespaces: Vec<String>,
+    /// Sets the option true for keep holes of sparse files. Only the data is read and written,
+    /// and the progress reports only data bytes. Works only on Linux, other platforms copy
+    /// the whole file.
+    pub sparse: bool,
+    /// Sets how reflinks are used to copy the file.
+    pub reflink: Reflink,
+    /// Sets the option true for write the copy to a temporary file in the directory of the
+    /// destination, which replaces the destination once it is complete and flushed to the
+    /// disk. Readers never see a partially written destination.
+    pub atomic: bool,
+    /// Sets how an existing destination is kept before it is overwritten.
+    pub backup: Backup,
+    /// Suffix of simple backups.
+    pub backup_suffix: String,
+    /// Sets the directory backups are moved to, instead of the directory of the destination.
+    /// It is created if needed.
+    pub backup_dir: Option<PathBuf>,
+    /// Sets how the copy is flushed to the disk.
+    pub durability: Durability,
+    /// Sets the digest to verify the copy with. Source and copy are read again once the copy
+    /// is written, and a copy which differs is removed with an
+    /// `ErrorKind::VerificationFailed` error. Files moved with a rename are not verified.
+    pub verify: Verify,
+    /// Sets the option true for continue an interrupted copy. If the destination is shorter
+    /// than the source and matches its start, checked by size and a checksum of its last
+    /// block, only the rest of the source is copied. Such a destination is not subject to
+    /// `overwrite_policy` and `backup`. Not used in atomic mode, which never leaves a
+    /// partial destination.
+    pub resume: bool,
 }
 
 impl CopyOptions {
@@ -23,13 +155,61 @@ impl CopyOptions {
     ///
     /// skip_exist: false
     ///
+    /// overwrite_policy: OverwritePolicy::Never
+    ///
     /// buffer_size: 64000 //64kb
+    ///
+    /// preserve_times: false
+    ///
+    /// preserve_owner: false
+    ///
+    /// strict_owner: false
+    ///
+    /// owner_map: None
+    ///
+    /// preserve_xattrs: false
+    ///
+    /// xattr_namespaces: ["user"]
+    ///
+    /// sparse: false
+    ///
+    /// reflink: Reflink::Auto
+    ///
+    /// atomic: false
+    ///
+    /// backup: Backup::None
+    ///
+    /// backup_suffix: "~"
+    ///
+    /// backup_dir: None
+    ///
+    /// durability: Durability::None
+    ///
+    /// verify: Verify::None
+    ///
+    /// resume: false
     /// ```
     pub fn new() -> CopyOptions {
         CopyOptions {
             overwrite: false,
             skip_exist: false,
+            overwrite_policy: OverwritePolicy::Never,
             buffer_size: 64000, //64kb
+            preserve_times: false,
+            preserve_owner: false,
+            strict_owner: false,
+            owner_map: None,
+            preserve_xattrs: false,
+            xattr_namespaces: vec!["user".to_string()],
+            sparse: false,
+            reflink: Reflink::Auto,
+            atomic: false,
+            backup: Backup::None,
+            backup_suffix: "~".to_string(),
+            backup_dir: None,
+            durability: Durability::None,
+            verify: Verify::None,
+            resume: false,
         }
     }
 
@@ -45,11 +225,115 @@ impl CopyOptions {
         self
     }
 
+    /// Sets how existing files are handled.
+    pub fn overwrite_policy(mut self, overwrite_policy: OverwritePolicy) -> Self {
+        self.overwrite_policy = overwrite_policy;
+        self
+    }
+
+    pub(crate) fn effective_overwrite_policy(&self) -> OverwritePolicy {
+        effective_overwrite_policy(self.overwrite, self.skip_exist, self.overwrite_policy)
+    }
+
     /// Sets buffer size for copy/move work only with receipt information about process work.
     pub fn buffer_size(mut self, buffer_size: usize) -> Self {
         self.buffer_size = buffer_size;
         self
     }
+
+    /// Sets the option true for copy access and modification times of the file.
+    pub fn preserve_times(mut self, preserve_times: bool) -> Self {
+        self.preserve_times = preserve_times;
+        self
+    }
+
+    /// Sets the option true for copy the owner (uid and gid) of the file. Works only on Unix.
+    pub fn preserve_owner(mut self, preserve_owner: bool) -> Self {
+        self.preserve_owner = preserve_owner;
+        self
+    }
+
+    /// Sets the option true for return an error if the owner can not be copied because the
+    /// process lacks the privilege.
+    pub fn strict_owner(mut self, strict_owner: bool) -> Self {
+        self.strict_owner = strict_owner;
+        self
+    }
+
+    /// Sets a function which maps the owner of the source to the owner of the copy.
+    pub fn owner_map<F>(mut self, owner_map: F) -> Self
+    where
+        F: Fn(u32, u32) -> (u32, u32) + Send + Sync + 'static,
+    {
+        self.owner_map = Some(Arc::new(owner_map));
+        self
+    }
+
+    /// Sets the option true for copy extended attributes of the file. Works only on Linux.
+    pub fn preserve_xattrs(mut self, preserve_xattrs: bool) -> Self {
+        self.preserve_xattrs = preserve_xattrs;
+        self
+    }
+
+    /// Sets namespaces of the extended attributes which are copied.
+    pub fn xattr_namespaces(mut self, xattr_namespaces: &[&str]) -> Self {
+        self.xattr_namespaces = xattr_namespaces.iter().map(|ns| ns.to_string()).collect();
+        self
+    }
+
+    /// Sets the option true for keep holes of sparse files. Works only on Linux.
+    pub fn sparse(mut self, sparse: bool) -> Self {
+        self.sparse = sparse;
+        self
+    }
+
+    /// Sets how reflinks are used to copy the file.
+    pub fn reflink(mut self, reflink: Reflink) -> Self {
+        self.reflink = reflink;
+        self
+    }
+
+    /// Sets the option true for replace the destination only once the copy is complete.
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Sets how an existing destination is kept before it is overwritten.
+    pub fn backup(mut self, backup: Backup) -> Self {
+        self.backup = backup;
+        self
+    }
+
+    /// Sets the suffix of simple backups.
+    pub fn backup_suffix(mut self, backup_suffix: &str) -> Self {
+        self.backup_suffix = backup_suffix.to_string();
+        self
+    }
+
+    /// Sets the directory backups are moved to.
+    pub fn backup_dir<P: AsRef<Path>>(mut self, backup_dir: P) -> Self {
+        self.backup_dir = Some(backup_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets how the copy is flushed to the disk.
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Sets the digest to verify the copy with.
+    pub fn verify(mut self, verify: Verify) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Sets the option true for continue an interrupted copy.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
 }
 
 impl Default for CopyOptions {
@@ -64,6 +348,11 @@ pub struct TransitProcess {
     pub copied_bytes: u64,
     /// All the bytes which should to copy or move.
     pub total_bytes: u64,
+    /// Path the overwritten destination was moved to, see `CopyOptions::backup`.
+    pub backup: Option<PathBuf>,
+    /// Path the file is written to if the destination existed and it was renamed,
+    /// see `OverwritePolicy::Rename`.
+    pub renamed_to: Option<PathBuf>,
 }
 
 /// Copies the contents of one file to another. This function will also copy the permission
@@ -93,7 +382,16 @@ where
     P: AsRef<Path>,
     Q: AsRef<Path>,
 {
-    let from = from.as_ref();
+    _copy(from.as_ref(), to.as_ref(), options, &mut None)
+}
+
+// Like `copy`, but sets `backup_path` to the path of the backup of the overwritten `to`.
+pub(crate) fn _copy(
+    from: &Path,
+    to: &Path,
+    options: &CopyOptions,
+    backup_path: &mut Option<PathBuf>,
+) -> Result<u64> {
     if !from.exists() {
         if let Some(msg) = from.to_str() {
             let msg = format!("Path \"{}\" does not exist or you don't have access!", msg);
@@ -113,24 +411,55 @@ where
         err!("Path is not a file!", ErrorKind::InvalidFile);
     }
 
-    if !options.overwrite && to.as_ref().exists() {
-        if options.skip_exist {
+    // Reading the file may update its access time, so take the times beforehand.
+    let metadata = from.metadata()?;
+    let offset = resume_offset(from, &metadata, to, options)?;
+    let to = match offset {
+        Some(_) => to.to_path_buf(),
+        None => destination(to, options),
+    };
+    if offset.is_none() {
+        if !may_write(from, &to, options)? {
             return Ok(0);
         }
+        *backup_path = backup(&to, options)?;
+    }
 
-        if let Some(msg) = to.as_ref().to_str() {
-            let msg = format!("Path \"{}\" exists", msg);
-            err!(&msg, ErrorKind::AlreadyExists);
-        }
+    // `std::fs::copy` already uses `copy_file_range`, which may reflink on its own.
+    let fast = !options.sparse && options.reflink == Reflink::Auto && !options.atomic;
+    if fast && offset.is_none() {
+        let result = std::fs::copy(from, &to)?;
+        verify_copy(from, &to, options)?;
+        copy_metadata(from, &to, &metadata, options)?;
+        sync_written(&to, options.durability)?;
+        return Ok(result);
     }
+    let mut file_from = File::open(from)?;
+    let mut output = Output::open(&to, offset, options)?;
+    copy_data(
+        &mut file_from,
+        &mut output.file,
+        metadata.len(),
+        offset.unwrap_or(0),
+        options,
+        |_| {},
+    )?;
+    verify_copy(from, &output.path, options)?;
+    set_permissions(&output.path, metadata.permissions())?;
+    copy_metadata(from, &output.path, &metadata, options)?;
+    output.finish()?;
 
-    Ok(std::fs::copy(from, to)?)
+    Ok(metadata.len())
 }
 
 /// Copies the contents of one file to another file with information about progress.
 /// This function will also copy the permission bits of the original file to the
 /// destination file.
 ///
+/// On Linux the file is reflinked if `options.reflink` allows it, otherwise the kernel copies
+/// it with `copy_file_range` in chunks of `buffer_size`. Other platforms, and file systems
+/// which do not support these calls, copy it through a buffer of `buffer_size`.
+///
 /// # Errors
 ///
 /// This function will return an error in the following situations, but is not limited to just
@@ -156,14 +485,35 @@ pub fn copy_with_progress<P, Q, F>(
     from: P,
     to: Q,
     options: &CopyOptions,
-    mut progress_handler: F,
+    progress_handler: F,
 ) -> Result<u64>
 where
     P: AsRef<Path>,
     Q: AsRef<Path>,
     F: FnMut(TransitProcess),
 {
-    let from = from.as_ref();
+    let copied = _copy_with_progress(
+        from.as_ref(),
+        to.as_ref(),
+        options,
+        &mut None,
+        progress_handler,
+    )?;
+    Ok(copied.unwrap_or(0))
+}
+
+// Like `copy_with_progress`, but returns `None` if the existing `to` was kept, and sets
+// `backup_path` to the path of the backup of the overwritten `to`.
+pub(crate) fn _copy_with_progress<F>(
+    from: &Path,
+    to: &Path,
+    options: &CopyOptions,
+    backup_path: &mut Option<PathBuf>,
+    mut progress_handler: F,
+) -> Result<Option<u64>>
+where
+    F: FnMut(TransitProcess),
+{
     if !from.exists() {
         if let Some(msg) = from.to_str() {
             let msg = format!("Path \"{}\" does not exist or you don't have access!", msg);
@@ -183,47 +533,215 @@ where
         err!("Path is not a file!", ErrorKind::InvalidFile);
     }
 
-    if !options.overwrite && to.as_ref().exists() {
-        if options.skip_exist {
-            return Ok(0);
+    let metadata = from.metadata()?;
+    let file_size = metadata.len();
+    let offset = resume_offset(from, &metadata, to, options)?;
+    let requested = to;
+    let to = &match offset {
+        Some(_) => to.to_path_buf(),
+        None => destination(to, options),
+    };
+    let renamed_to = Some(to.clone()).filter(|to| to != requested);
+    if offset.is_none() {
+        if !may_write(from, to, options)? {
+            return Ok(None);
         }
+        *backup_path = backup(to, options)?;
+    }
+    let backup_path = backup_path.clone();
+    let mut progress_handler = |mut info: TransitProcess| {
+        info.backup = backup_path.clone();
+        info.renamed_to = renamed_to.clone();
+        progress_handler(info);
+    };
+    let mut file_from = File::open(from)?;
 
-        if let Some(msg) = to.as_ref().to_str() {
-            let msg = format!("Path \"{}\" exists", msg);
-            err!(&msg, ErrorKind::AlreadyExists);
+    let mut output = Output::open(to, offset, options)?;
+    copy_data(
+        &mut file_from,
+        &mut output.file,
+        file_size,
+        offset.unwrap_or(0),
+        options,
+        &mut progress_handler,
+    )?;
+    verify_copy(from, &output.path, options)?;
+    set_permissions(&output.path, metadata.permissions())?;
+    copy_metadata(from, &output.path, &metadata, options)?;
+    output.finish()?;
+    Ok(Some(file_size))
+}
+
+// Size of the last block of a partial destination which is compared with the source.
+const RESUME_BLOCK_SIZE: u64 = 64 * 1024;
+
+// Returns the size of `to` if the copy of `from` continues there, see `CopyOptions::resume`.
+// `metadata` is the one of `from`.
+fn resume_offset(
+    from: &Path,
+    metadata: &Metadata,
+    to: &Path,
+    options: &CopyOptions,
+) -> Result<Option<u64>> {
+    if !options.resume || options.atomic {
+        return Ok(None);
+    }
+    let len = match to.metadata() {
+        Ok(to_metadata) if to_metadata.is_file() && to_metadata.len() < metadata.len() => {
+            to_metadata.len()
         }
+        _ => return Ok(None),
+    };
+    if len == 0 {
+        return Ok(None);
     }
-    let mut file_from = File::open(from)?;
-    let mut buf = vec![0; options.buffer_size];
-    let file_size = file_from.metadata()?.len();
-    let mut copied_bytes: u64 = 0;
+    let block = len.min(RESUME_BLOCK_SIZE);
+    let tail = |path: &Path| -> Result<Option<Vec<u8>>> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(len - block))?;
+        crate::digest::digest(&mut file, block, Verify::Fast)
+    };
+    if tail(from)? == tail(to)? {
+        Ok(Some(len))
+    } else {
+        Ok(None)
+    }
+}
 
-    let mut file_to = File::create(to)?;
-    while !buf.is_empty() {
-        match file_from.read(&mut buf) {
-            Ok(0) => break,
-            Ok(n) => {
-                let written_bytes = file_to.write(&buf[..n])?;
-                if written_bytes != n {
-                    err!("Couldn't write the whole buffer to file", ErrorKind::Other);
+// Compares the digests of `from` and its copy `to` if `options.verify` asks for it. A copy
+// which differs is removed.
+fn verify_copy(from: &Path, to: &Path, options: &CopyOptions) -> Result<()> {
+    if options.verify == Verify::None {
+        return Ok(());
+    }
+    let digest =
+        |path: &Path| crate::digest::digest(&mut File::open(path)?, u64::MAX, options.verify);
+    if digest(from)? == digest(to)? {
+        return Ok(());
+    }
+    remove_file(to)?;
+    if let Some(msg) = to.to_str() {
+        let msg = format!("Path \"{}\" does not match its source", msg);
+        err!(&msg, ErrorKind::VerificationFailed);
+    }
+    err!(
+        "Path does not match its source",
+        ErrorKind::VerificationFailed
+    );
+}
+
+// Copies the owner, extended attributes and times of `from` to its copy `to`, as far as
+// `options` asks for them. `metadata` is the one of `from` before it was read.
+fn copy_metadata(from: &Path, to: &Path, metadata: &Metadata, options: &CopyOptions) -> Result<()> {
+    copy_owner(to, metadata, options)?;
+    if options.preserve_xattrs {
+        copy_xattrs(from, to, options)?;
+    }
+    if options.preserve_times {
+        set_file_times(to, metadata)?;
+    }
+    Ok(())
+}
+
+// The file a copy is written to. In atomic mode this is a temporary file in the directory of
+// the destination, which replaces the destination on `finish`, so that readers never see it
+// partially written. The temporary file is removed if the copy fails before.
+pub(crate) struct Output {
+    pub(crate) file: File,
+    // The path of `file`.
+    pub(crate) path: PathBuf,
+    to: PathBuf,
+    atomic: bool,
+    durability: Durability,
+    finished: bool,
+}
+
+impl Output {
+    // Like `create`, but continues writing the existing `to` if the copy resumes at `offset`.
+    fn open(to: &Path, offset: Option<u64>, options: &CopyOptions) -> Result<Output> {
+        if offset.is_none() {
+            return Output::create(to, options);
+        }
+        Ok(Output {
+            file: OpenOptions::new().write(true).open(to)?,
+            path: to.to_path_buf(),
+            to: to.to_path_buf(),
+            atomic: false,
+            durability: options.durability,
+            finished: false,
+        })
+    }
+
+    pub(crate) fn create(to: &Path, options: &CopyOptions) -> Result<Output> {
+        if !options.atomic {
+            return Ok(Output {
+                file: File::create(to)?,
+                path: to.to_path_buf(),
+                to: to.to_path_buf(),
+                atomic: false,
+                durability: options.durability,
+                finished: false,
+            });
+        }
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = match to.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => err!("Invalid file name", ErrorKind::InvalidFileName),
+        };
+        loop {
+            let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+            let temp_name = format!(".{}.{}-{}.tmp", name, std::process::id(), count);
+            let path = to.with_file_name(temp_name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    return Ok(Output {
+                        file,
+                        path,
+                        to: to.to_path_buf(),
+                        atomic: true,
+                        durability: options.durability,
+                        finished: false,
+                    })
                 }
-                copied_bytes += n as u64;
-                let data = TransitProcess {
-                    copied_bytes,
-                    total_bytes: file_size,
-                };
-                progress_handler(data);
+                Err(ref err) if err.kind() == IoErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err.into()),
             }
-            Err(ref e) if e.kind() == ::std::io::ErrorKind::Interrupted => {}
-            Err(e) => return Err(::std::convert::From::from(e)),
         }
     }
-    Ok(file_size)
+
+    // Completes the copy. In atomic mode the data is flushed to the disk, the temporary
+    // file renamed to the destination and the rename flushed too. Otherwise the file and its
+    // directory are flushed as far as `durability` asks for it.
+    pub(crate) fn finish(mut self) -> Result<()> {
+        if self.atomic || self.durability >= Durability::FileData {
+            self.file.sync_all()?;
+        }
+        if self.atomic {
+            rename(&self.path, &self.to)?;
+        }
+        if self.atomic || self.durability == Durability::FileAndDirectory {
+            sync_parent(&self.to)?;
+        }
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        if self.atomic && !self.finished {
+            let _ = remove_file(&self.path);
+        }
+    }
 }
 
 /// Moves a file from one place to another. This function will also copy the permission
 /// bits of the original file to the destination file.
 ///
+/// The file is moved with a single rename when `from` and `to` are on the same file
+/// system. Otherwise it is copied together with its permission bits and access and
+/// modification times, and the source file is removed afterwards.
+///
 /// # Errors
 ///
 /// This function will return an error in the following situations, but is not limited to just
@@ -247,15 +765,23 @@ where
     P: AsRef<Path>,
     Q: AsRef<Path>,
 {
-    let mut is_remove = true;
-    if options.skip_exist && to.as_ref().exists() && !options.overwrite {
-        is_remove = false;
-    }
-    let result = copy(&from, to, options)?;
-    if is_remove {
-        remove(from)?;
+    let from = from.as_ref();
+    let to = &destination(to.as_ref(), options);
+    let options = match move_options(from, to, options)? {
+        Some((options, _)) => options,
+        None => return Ok(0),
+    };
+    if let Some(result) = move_file_by_rename(from, to, &options)? {
+        return Ok(result);
     }
 
+    // The destination is flushed once it is complete, before the source is removed.
+    let copy_options = options.clone().durability(Durability::None);
+    let result = copy(from, to, &copy_options)?;
+    set_file_times(to, &from.metadata()?)?;
+    sync_written(to, options.durability)?;
+    remove(from)?;
+
     Ok(result)
 }
 
@@ -263,6 +789,9 @@ where
 /// This function will also copy the permission bits of the original file to the
 /// destination file.
 ///
+/// Like [`move_file`], this tries a single rename first. In that case the progress handler
+/// is called only once, after the file has been moved.
+///
 /// # Errors
 ///
 /// This function will return an error in the following situations, but is not limited to just
@@ -295,18 +824,601 @@ where
     Q: AsRef<Path>,
     F: FnMut(TransitProcess),
 {
-    let mut is_remove = true;
-    if options.skip_exist && to.as_ref().exists() && !options.overwrite {
-        is_remove = false;
+    _move_file_with_progress(from.as_ref(), to.as_ref(), options, Err, progress_handler)
+}
+
+// Like `move_file_with_progress`, but passes an error from copying the extended attributes
+// of a file which is not renamed to `xattr_failed`. The source is removed only if
+// `xattr_failed` returns `Ok`.
+pub(crate) fn _move_file_with_progress<X, F>(
+    from: &Path,
+    to: &Path,
+    options: &CopyOptions,
+    xattr_failed: X,
+    mut progress_handler: F,
+) -> Result<u64>
+where
+    X: FnOnce(Error) -> Result<()>,
+    F: FnMut(TransitProcess),
+{
+    let requested = to;
+    let to = &destination(requested, options);
+    let renamed_to = Some(to.clone()).filter(|to| to != requested);
+    let (options, backup) = match move_options(from, to, options)? {
+        Some(result) => result,
+        None => return Ok(0),
+    };
+    if let Some(result) = move_file_by_rename(from, to, &options)? {
+        progress_handler(TransitProcess {
+            copied_bytes: result,
+            total_bytes: result,
+            backup,
+            renamed_to,
+        });
+        return Ok(result);
     }
-    let result = copy_with_progress(&from, to, options, progress_handler)?;
-    if is_remove {
-        remove(from)?;
+
+    let mut copy_options = options.clone().durability(Durability::None);
+    copy_options.preserve_xattrs = false;
+    let result = copy_with_progress(from, to, &copy_options, |mut info: TransitProcess| {
+        info.backup = backup.clone();
+        info.renamed_to = renamed_to.clone();
+        progress_handler(info);
+    })?;
+    if options.preserve_xattrs {
+        if let Err(err) = copy_xattrs(from, to, &options) {
+            xattr_failed(err)?;
+        }
     }
+    let metadata = from.metadata()?;
+    set_file_times(to, &metadata)?;
+    set_permissions(to, metadata.permissions())?;
+    sync_written(to, options.durability)?;
+    remove(from)?;
 
     Ok(result)
 }
 
+// Copies the content of `file_from` to `file_to`, calling `progress_handler` after every
+// chunk. The data is reflinked if possible, then copied by the kernel with
+// `copy_file_range`, and finally through a buffer of `options.buffer_size`.
+// With `options.sparse` only the data ranges are copied, so holes stay holes.
+// The data before `offset` is already in `file_to`, and counted as copied.
+fn copy_data<F>(
+    file_from: &mut File,
+    file_to: &mut File,
+    file_size: u64,
+    offset: u64,
+    options: &CopyOptions,
+    mut progress_handler: F,
+) -> Result<()>
+where
+    F: FnMut(TransitProcess),
+{
+    if options.reflink != Reflink::Never && offset == 0 {
+        match crate::sys::reflink(file_from, file_to) {
+            Ok(()) => {
+                progress_handler(TransitProcess {
+                    copied_bytes: file_size,
+                    total_bytes: file_size,
+                    backup: None,
+                    renamed_to: None,
+                });
+                return Ok(());
+            }
+            Err(err) if options.reflink == Reflink::Always => {
+                return Err(Error::new(ErrorKind::Io(err), "Couldn't reflink the file"));
+            }
+            Err(_) => {}
+        }
+    }
+
+    let ranges = if options.sparse {
+        crate::sys::data_ranges(file_from)?
+    } else {
+        None
+    };
+    let mut copier = RangeCopier {
+        file_from,
+        file_to,
+        buffer_size: options.buffer_size,
+        buf: Vec::new(),
+        kernel_copy: options.reflink != Reflink::Never,
+    };
+    let mut copied_bytes: u64 = 0;
+    match ranges {
+        Some(ranges) => {
+            let total_bytes = ranges.iter().map(|&(start, end)| end - start).sum();
+            for (start, end) in ranges {
+                let resumed = end.min(offset).saturating_sub(start);
+                copied_bytes += resumed;
+                copier.copy(start + resumed, end - start - resumed, &mut |n| {
+                    copied_bytes += n;
+                    progress_handler(TransitProcess {
+                        copied_bytes,
+                        total_bytes,
+                        backup: None,
+                        renamed_to: None,
+                    });
+                })?;
+            }
+            // Creates the hole at the end of the file, if any.
+            copier.file_to.set_len(file_size)?;
+        }
+        None => {
+            copied_bytes = offset;
+            copier.copy(offset, u64::MAX, &mut |n| {
+                copied_bytes += n;
+                progress_handler(TransitProcess {
+                    copied_bytes,
+                    total_bytes: file_size,
+                    backup: None,
+                    renamed_to: None,
+                });
+            })?;
+        }
+    }
+    Ok(())
+}
+
+// Copies ranges of a file to the same offsets of another file.
+struct RangeCopier<'a> {
+    file_from: &'a mut File,
+    file_to: &'a mut File,
+    buffer_size: usize,
+    // Allocated once the buffered copy is needed.
+    buf: Vec<u8>,
+    // Cleared for good once `copy_file_range` is not supported for these files.
+    kernel_copy: bool,
+}
+
+impl RangeCopier<'_> {
+    // Copies `len` bytes at `offset`, or up to the end of the file, in chunks of
+    // `buffer_size`, calling `written` with the size of every chunk.
+    fn copy(&mut self, mut offset: u64, mut len: u64, written: &mut dyn FnMut(u64)) -> Result<()> {
+        while self.kernel_copy && len > 0 && self.buffer_size > 0 {
+            let chunk = len.min(self.buffer_size as u64);
+            match crate::sys::copy_file_range(self.file_from, self.file_to, offset, chunk)? {
+                // Either the end of the file, or a file in procfs, sysfs or FUSE which the
+                // kernel can not copy. The buffered copy reads the rest, if there is any.
+                Some(0) => self.kernel_copy = false,
+                Some(n) => {
+                    offset += n;
+                    len -= n;
+                    written(n);
+                }
+                None => self.kernel_copy = false,
+            }
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        self.file_from.seek(SeekFrom::Start(offset))?;
+        self.file_to.seek(SeekFrom::Start(offset))?;
+        if self.buf.is_empty() {
+            self.buf = vec![0; self.buffer_size];
+        }
+        copy_buffered(self.file_from, self.file_to, &mut self.buf, len, written)
+    }
+}
+
+// Copies up to `len` bytes from the current position of `file_from` to `file_to`, calling
+// `written` with the size of every written chunk.
+fn copy_buffered(
+    file_from: &mut File,
+    file_to: &mut File,
+    buf: &mut [u8],
+    mut len: u64,
+    written: &mut dyn FnMut(u64),
+) -> Result<()> {
+    while len > 0 && !buf.is_empty() {
+        let chunk = if len < buf.len() as u64 {
+            len as usize
+        } else {
+            buf.len()
+        };
+        match file_from.read(&mut buf[..chunk]) {
+            Ok(0) => break,
+            Ok(n) => {
+                let written_bytes = file_to.write(&buf[..n])?;
+                if written_bytes != n {
+                    err!("Couldn't write the whole buffer to file", ErrorKind::Other);
+                }
+                len -= n as u64;
+                written(n as u64);
+            }
+            Err(ref e) if e.kind() == IoErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+// Returns `overwrite_policy` with the `overwrite` and `skip_exist` shorthands applied.
+pub(crate) fn effective_overwrite_policy(
+    overwrite: bool,
+    skip_exist: bool,
+    overwrite_policy: OverwritePolicy,
+) -> OverwritePolicy {
+    if overwrite {
+        OverwritePolicy::Always
+    } else if skip_exist && overwrite_policy == OverwritePolicy::Never {
+        OverwritePolicy::Skip
+    } else {
+        overwrite_policy
+    }
+}
+
+// Applies the overwrite policy and makes the backup before a move. Returns `None` if `from`
+// should stay where it is, otherwise the options for moving it and the path of the backup.
+fn move_options(
+    from: &Path,
+    to: &Path,
+    options: &CopyOptions,
+) -> Result<Option<(CopyOptions, Option<PathBuf>)>> {
+    let mut options = options.clone();
+    let mut backup_path = None;
+    if from.is_file() && to.exists() {
+        if !may_write(from, to, &options)? {
+            return Ok(None);
+        }
+        backup_path = backup(to, &options)?;
+        options.overwrite = true;
+    }
+    Ok(Some((options, backup_path)))
+}
+
+// Returns true if `to` does not exist or may be overwritten with `from`.
+fn may_write(from: &Path, to: &Path, options: &CopyOptions) -> Result<bool> {
+    match to.metadata() {
+        Ok(to_metadata) => should_overwrite(from, &from.metadata()?, to, &to_metadata, options),
+        Err(_) => Ok(true),
+    }
+}
+
+// Decides whether the existing `to` is replaced with `from` following the overwrite policy
+// of `options`. Returns an `ErrorKind::AlreadyExists` error for `OverwritePolicy::Never`.
+pub(crate) fn should_overwrite(
+    from: &Path,
+    from_metadata: &Metadata,
+    to: &Path,
+    to_metadata: &Metadata,
+    options: &CopyOptions,
+) -> Result<bool> {
+    let newer = || -> Result<bool> { Ok(from_metadata.modified()? > to_metadata.modified()?) };
+    let size_differs = from_metadata.len() != to_metadata.len();
+    match options.effective_overwrite_policy() {
+        OverwritePolicy::Never => {
+            if let Some(msg) = to.to_str() {
+                let msg = format!("Path \"{}\" exists", msg);
+                err!(&msg, ErrorKind::AlreadyExists);
+            }
+            err!("Path exists", ErrorKind::AlreadyExists);
+        }
+        OverwritePolicy::Always => Ok(true),
+        OverwritePolicy::Skip => Ok(false),
+        OverwritePolicy::IfNewer => newer(),
+        OverwritePolicy::IfSizeDiffers => Ok(size_differs),
+        OverwritePolicy::IfContentDiffers => {
+            Ok(size_differs || content_differs(from, from_metadata, to, to_metadata)?)
+        }
+        OverwritePolicy::IfNewerOrSizeDiffers => Ok(size_differs || newer()?),
+        // The destination is replaced by a free name beforehand, see `destination`.
+        OverwritePolicy::Rename => Ok(false),
+    }
+}
+
+// Returns the path `from` is written to: `to`, or a free name next to it if `to` exists and
+// the overwrite policy of `options` is `OverwritePolicy::Rename`.
+pub(crate) fn destination(to: &Path, options: &CopyOptions) -> PathBuf {
+    if options.effective_overwrite_policy() == OverwritePolicy::Rename {
+        free_path(to)
+    } else {
+        to.to_path_buf()
+    }
+}
+
+// Moves the existing `to` out of the way following `options.backup`, before it is
+// overwritten. Returns the path of the backup, or `None` if no backup is made.
+pub(crate) fn backup(to: &Path, options: &CopyOptions) -> Result<Option<PathBuf>> {
+    let path = match backup_path(to, options)? {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    if let Some(dir) = &options.backup_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+    match rename(to, &path) {
+        Ok(()) => {}
+        // `backup_dir` is on another file system.
+        Err(ref err) if err.kind() == IoErrorKind::CrossesDevices => {
+            std::fs::copy(to, &path)?;
+            remove_file(to)?;
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(Some(path))
+}
+
+// Returns the path `backup` moves the existing `to` to, without changing anything.
+pub(crate) fn backup_path(to: &Path, options: &CopyOptions) -> Result<Option<PathBuf>> {
+    if options.backup == Backup::None || to.symlink_metadata().is_err() {
+        return Ok(None);
+    }
+    let name = match to.file_name() {
+        Some(name) => name,
+        None => err!("Invalid file name", ErrorKind::InvalidFileName),
+    };
+    let dir = match &options.backup_dir {
+        Some(dir) => dir.as_path(),
+        None => to.parent().unwrap_or_else(|| Path::new("")),
+    };
+
+    let last = match options.backup {
+        Backup::Numbered | Backup::Existing => last_backup_number(dir, name)?,
+        _ => 0,
+    };
+    let mut backup_name = name.to_os_string();
+    if options.backup == Backup::Numbered || last > 0 {
+        backup_name.push(format!(".~{}~", last + 1));
+    } else {
+        backup_name.push(&options.backup_suffix);
+    }
+    Ok(Some(dir.join(backup_name)))
+}
+
+// Returns the highest number of the backups `name.~n~` in `dir`, or 0 if there is none.
+fn last_backup_number(dir: &Path, name: &OsStr) -> Result<u64> {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // `backup_dir` is created by the first backup.
+        Err(ref err) if err.kind() == IoErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err.into()),
+    };
+    let prefix = format!("{}.~", name.to_string_lossy());
+    let mut last = 0;
+    for entry in entries {
+        let entry_name = entry?.file_name();
+        let entry_name = entry_name.to_string_lossy();
+        let number = entry_name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix('~'))
+            .and_then(|number| number.parse::<u64>().ok());
+        if let Some(number) = number {
+            last = last.max(number);
+        }
+    }
+    Ok(last)
+}
+
+// Returns `path` if nothing exists there, otherwise the first free path `name (n).ext`.
+pub(crate) fn free_path(path: &Path) -> PathBuf {
+    free_path_by(path, |path| path.symlink_metadata().is_ok())
+}
+
+// Like `free_path`, with `exists` telling which paths are taken.
+pub(crate) fn free_path_by<F>(path: &Path, exists: F) -> PathBuf
+where
+    F: Fn(&Path) -> bool,
+{
+    if !exists(path) {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default();
+    let mut n: u64 = 1;
+    loop {
+        let mut name = stem.to_os_string();
+        name.push(format!(" ({})", n));
+        if let Some(extension) = path.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        let candidate = path.with_file_name(name);
+        if !exists(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+// Compares the content of two files, or the targets of two symlinks. Entries of different
+// types always differ.
+fn content_differs(
+    from: &Path,
+    from_metadata: &Metadata,
+    to: &Path,
+    to_metadata: &Metadata,
+) -> Result<bool> {
+    if from_metadata.file_type().is_symlink() && to_metadata.file_type().is_symlink() {
+        return Ok(std::fs::read_link(from)? != std::fs::read_link(to)?);
+    }
+    if !from_metadata.is_file() || !to_metadata.is_file() {
+        return Ok(true);
+    }
+    let mut file_from = File::open(from)?;
+    let mut file_to = File::open(to)?;
+    let mut buf_from = vec![0; 64000];
+    let mut buf_to = vec![0; 64000];
+    loop {
+        let n = read_full(&mut file_from, &mut buf_from)?;
+        if n != read_full(&mut file_to, &mut buf_to)? || buf_from[..n] != buf_to[..n] {
+            return Ok(true);
+        }
+        if n == 0 {
+            return Ok(false);
+        }
+    }
+}
+
+// Reads until `buf` is full or the end of the file is reached.
+fn read_full(file: &mut File, buf: &mut [u8]) -> Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(ref e) if e.kind() == IoErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(read)
+}
+
+// Moves the file with a single `rename`. Returns `None` if the caller should fall back to
+// `copy`, either because `to` is on another file system or because `copy` has to decide
+// how to handle an invalid source or an existing destination.
+fn move_file_by_rename(from: &Path, to: &Path, options: &CopyOptions) -> Result<Option<u64>> {
+    if !from.is_file() || (to.exists() && !options.overwrite) {
+        return Ok(None);
+    }
+
+    let size = from.metadata()?.len();
+    match rename(from, to) {
+        Ok(()) => {
+            sync_renamed(from, to, options.durability)?;
+            Ok(Some(size))
+        }
+        Err(ref err) if err.kind() == IoErrorKind::CrossesDevices => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// Flushes the file `path`, written by a copy or move, and its entry in the parent directory
+// as far as `durability` asks for it.
+pub(crate) fn sync_written(path: &Path, durability: Durability) -> Result<()> {
+    if durability >= Durability::FileData {
+        open_for_sync(path)?.sync_all()?;
+    }
+    if durability == Durability::FileAndDirectory {
+        sync_parent(path)?;
+    }
+    Ok(())
+}
+
+// Flushes the directories `from` was renamed out of and `to` into, if `durability` asks for
+// it.
+pub(crate) fn sync_renamed(from: &Path, to: &Path, durability: Durability) -> Result<()> {
+    if durability == Durability::FileAndDirectory {
+        sync_parent(to)?;
+        if from.parent() != to.parent() {
+            sync_parent(from)?;
+        }
+    }
+    Ok(())
+}
+
+// Flushes the entries of the directory containing `path`.
+pub(crate) fn sync_parent(path: &Path) -> Result<()> {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    crate::sys::sync_dir(parent)?;
+    Ok(())
+}
+
+// Sets the access and modification times of `path` to the ones recorded in `metadata`.
+pub(crate) fn set_file_times(path: &Path, metadata: &Metadata) -> Result<()> {
+    open_for_times(path)?.set_times(file_times(metadata)?)?;
+    Ok(())
+}
+
+fn file_times(metadata: &Metadata) -> Result<FileTimes> {
+    Ok(FileTimes::new()
+        .set_accessed(metadata.accessed()?)
+        .set_modified(metadata.modified()?))
+}
+
+// Sets the owner recorded in `metadata` on `path` without following symlinks, if
+// `options.preserve_owner` is set.
+#[cfg(unix)]
+pub(crate) fn copy_owner(path: &Path, metadata: &Metadata, options: &CopyOptions) -> Result<()> {
+    use std::os::unix::fs::{lchown, MetadataExt, PermissionsExt};
+
+    if !options.preserve_owner {
+        return Ok(());
+    }
+    let (uid, gid) = match options.owner_map {
+        Some(ref owner_map) => owner_map(metadata.uid(), metadata.gid()),
+        None => (metadata.uid(), metadata.gid()),
+    };
+    // Changing the owner clears the setuid and setgid bits, which have to be restored.
+    let path_metadata = path.symlink_metadata()?;
+    match lchown(path, Some(uid), Some(gid)) {
+        Ok(()) => {}
+        Err(ref err) if err.kind() == IoErrorKind::PermissionDenied && !options.strict_owner => {
+            return Ok(())
+        }
+        Err(err) => return Err(err.into()),
+    }
+    let permissions = path_metadata.permissions();
+    if !path_metadata.file_type().is_symlink() && permissions.mode() & 0o6000 != 0 {
+        set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn copy_owner(_path: &Path, _metadata: &Metadata, _options: &CopyOptions) -> Result<()> {
+    Ok(())
+}
+
+// Copies the extended attributes of `from` in `options.xattr_namespaces` to `to`.
+// Tries every attribute and returns the first error.
+pub(crate) fn copy_xattrs(from: &Path, to: &Path, options: &CopyOptions) -> Result<()> {
+    let mut result = Ok(());
+    for name in crate::sys::list_xattrs(from)? {
+        let namespace = name.to_str().and_then(|name| name.split('.').next());
+        let in_namespace = options
+            .xattr_namespaces
+            .iter()
+            .any(|ns| Some(ns.as_str()) == namespace);
+        if !in_namespace {
+            continue;
+        }
+        let copied = crate::sys::get_xattr(from, &name)
+            .and_then(|value| crate::sys::set_xattr(to, &name, &value));
+        if let Err(err) = copied {
+            if result.is_ok() {
+                result = Err(err.into());
+            }
+        }
+    }
+    result
+}
+
+#[cfg(windows)]
+fn open_for_times(path: &Path) -> std::io::Result<File> {
+    use std::fs::OpenOptions;
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_WRITE_ATTRIBUTES: u32 = 0x100;
+    // Required to open directories.
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    OpenOptions::new()
+        .access_mode(FILE_WRITE_ATTRIBUTES)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+}
+
+#[cfg(not(windows))]
+fn open_for_times(path: &Path) -> std::io::Result<File> {
+    File::open(path)
+}
+
+// Windows flushes only files which are open for writing.
+#[cfg(windows)]
+fn open_for_sync(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().write(true).open(path)
+}
+
+#[cfg(not(windows))]
+fn open_for_sync(path: &Path) -> std::io::Result<File> {
+    File::open(path)
+}
+
 /// Removes a file from the filesystem.
 ///
 /// # Errors
@@ -411,3 +1523,114 @@ where
 
     Ok(f.write_all(content.as_bytes())?)
 }
+
+/// Write `String` content into file atomically. The content is written to a temporary file in
+/// the same directory and flushed to the disk, which then replaces `path`. A crash leaves
+/// either the old or the new content.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is not limited to just
+/// these cases:
+///
+/// * This `path` is not a file.
+/// * The current process does not have the permission to write the directory of `path`.
+///
+/// # Example
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::file::write_all_atomic;
+///
+/// write_all_atomic("config.toml", "contents")?; // Replace config.toml as a whole
+///
+/// ```
+pub fn write_all_atomic<P>(path: P, content: &str) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if path.exists() && !path.is_file() {
+        if let Some(msg) = path.to_str() {
+            let msg = format!("Path \"{}\" is not a file!", msg);
+            err!(&msg, ErrorKind::InvalidFile);
+        }
+        err!("Path is not a file!", ErrorKind::InvalidFile);
+    }
+
+    let mut output = Output::create(path, &CopyOptions::new().atomic(true))?;
+    // Keeps the permission bits of the replaced file.
+    if let Ok(metadata) = path.metadata() {
+        set_permissions(&output.path, metadata.permissions())?;
+    }
+    output.file.write_all(content.as_bytes())?;
+    output.finish()
+}
+
+/// Returns the extended attributes of the file as pairs of name and value.
+/// Extended attributes are supported only on Linux.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is not limited to just
+/// these cases:
+///
+/// * This `path` does not exist.
+/// * The file system or platform does not support extended attributes.
+/// * The current process does not have the permission to access `path`.
+///
+/// # Example
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::file::get_xattrs;
+///
+/// for (name, value) in get_xattrs("foo.txt")? {
+///     println!("{:?} = {:?}", name, value);
+/// }
+///
+/// ```
+pub fn get_xattrs<P>(path: P) -> Result<Vec<(OsString, Vec<u8>)>>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut xattrs = Vec::new();
+    for name in crate::sys::list_xattrs(path)? {
+        let value = crate::sys::get_xattr(path, &name)?;
+        xattrs.push((name, value));
+    }
+    Ok(xattrs)
+}
+
+/// Sets extended attributes of the file. Attributes which are not listed are kept as they are.
+/// Extended attributes are supported only on Linux.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is not limited to just
+/// these cases:
+///
+/// * This `path` does not exist.
+/// * The file system or platform does not support extended attributes.
+/// * The current process does not have the permission to set an attribute.
+///
+/// # Example
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::file::set_xattrs;
+///
+/// set_xattrs("foo.txt", vec![("user.origin", "build-42")])?;
+///
+/// ```
+pub fn set_xattrs<P, I, N, V>(path: P, xattrs: I) -> Result<()>
+where
+    P: AsRef<Path>,
+    I: IntoIterator<Item = (N, V)>,
+    N: AsRef<OsStr>,
+    V: AsRef<[u8]>,
+{
+    let path = path.as_ref();
+    for (name, value) in xattrs {
+        crate::sys::set_xattr(path, name.as_ref(), value.as_ref())?;
+    }
+    Ok(())
+}
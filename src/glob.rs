@@ -0,0 +1,283 @@
+// Glob patterns matched against paths relative to the root of a directory walk.
+//
+// A pattern is split at `/` into segments. `*` matches any characters of a single segment,
+// `?` one character and `[a-z]` or `[!a-z]` one character of a class. A `**` segment matches
+// any number of segments. A pattern which contains a `/` before its end, or starts with one,
+// is anchored at the root; any other pattern matches the name of an entry at any depth.
+// A trailing `/` makes the pattern match only directories.
+
+use crate::dir::{DirOptions, EntryFilter, FilterResult};
+use crate::error::*;
+use crate::ignore::Ignore;
+use std::fs::Metadata;
+use std::path::{Component, Path};
+
+#[derive(Debug)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnyChars,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug)]
+enum Segment {
+    Name(Vec<Token>),
+    // `**`
+    AnyPath,
+}
+
+#[derive(Debug)]
+pub(crate) struct Glob {
+    segments: Vec<Segment>,
+    dir_only: bool,
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str) -> Glob {
+        let mut pattern = pattern;
+        let dir_only = pattern.len() > 1 && pattern.ends_with('/');
+        if dir_only {
+            pattern = &pattern[..pattern.len() - 1];
+        }
+        let anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+
+        let mut segments = Vec::new();
+        if !anchored {
+            segments.push(Segment::AnyPath);
+        }
+        for segment in pattern.split('/').filter(|segment| !segment.is_empty()) {
+            if segment == "**" {
+                segments.push(Segment::AnyPath);
+            } else {
+                segments.push(Segment::Name(parse_segment(segment)));
+            }
+        }
+        Glob { segments, dir_only }
+    }
+
+    // `path` is relative to the root of the walk.
+    pub(crate) fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let names: Vec<String> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        match_segments(&self.segments, &names)
+    }
+}
+
+fn parse_segment(segment: &str) -> Vec<Token> {
+    let chars: Vec<char> = segment.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => {
+                if !matches!(tokens.last(), Some(Token::AnyChars)) {
+                    tokens.push(Token::AnyChars);
+                }
+            }
+            '?' => tokens.push(Token::AnyChar),
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                tokens.push(Token::Char(chars[i]));
+            }
+            '[' => match parse_class(&chars[i + 1..]) {
+                Some((token, len)) => {
+                    tokens.push(token);
+                    i += len;
+                }
+                // An unclosed `[` matches itself.
+                None => tokens.push(Token::Char('[')),
+            },
+            c => tokens.push(Token::Char(c)),
+        }
+        i += 1;
+    }
+    tokens
+}
+
+// Parses a class after its `[`. Returns the token and the number of characters it used,
+// including the closing `]`.
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let mut i = 0;
+    let negated = matches!(chars.first(), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    let start = i;
+    while i < chars.len() {
+        // A `]` right after the `[` is part of the class.
+        if chars[i] == ']' && i > start {
+            return Some((Token::Class { negated, ranges }, i + 1));
+        }
+        let mut first = chars[i];
+        if first == '\\' && i + 1 < chars.len() {
+            i += 1;
+            first = chars[i];
+        }
+        if i + 2 < chars.len() && chars[i + 1] == '-' && chars[i + 2] != ']' {
+            ranges.push((first, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((first, first));
+            i += 1;
+        }
+    }
+    None
+}
+
+fn match_segments(segments: &[Segment], names: &[&str]) -> bool {
+    match segments.split_first() {
+        None => names.is_empty(),
+        Some((Segment::AnyPath, rest)) => {
+            // A trailing `**` matches everything inside, but not the directory itself.
+            let min = if rest.is_empty() { 1 } else { 0 };
+            (min..=names.len()).any(|skip| match_segments(rest, &names[skip..]))
+        }
+        Some((Segment::Name(tokens), rest)) => match names.split_first() {
+            Some((name, names)) => {
+                let name: Vec<char> = name.chars().collect();
+                match_name(tokens, &name) && match_segments(rest, names)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_name(tokens: &[Token], name: &[char]) -> bool {
+    match tokens.split_first() {
+        None => name.is_empty(),
+        Some((Token::AnyChars, rest)) => {
+            (0..=name.len()).any(|skip| match_name(rest, &name[skip..]))
+        }
+        Some((token, rest)) => match name.split_first() {
+            Some((c, name)) => {
+                let matched = match token {
+                    Token::Char(expected) => c == expected,
+                    Token::AnyChar => true,
+                    Token::Class { negated, ranges } => {
+                        ranges.iter().any(|&(low, high)| low <= *c && *c <= high) != *negated
+                    }
+                    Token::AnyChars => unreachable!(),
+                };
+                matched && match_name(rest, name)
+            }
+            None => false,
+        },
+    }
+}
+
+// How a directory walk handles an entry which it does not leave out, see `Filter::visit`.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Visit {
+    // The entry matches the include patterns, by itself or through its directory.
+    pub(crate) included: bool,
+    // The entry is a directory which is only listed if some of its content is.
+    pub(crate) skipped: bool,
+}
+
+// Decides which entries of a directory walk are visited, see `DirOptions::include`,
+// `DirOptions::exclude`, `DirOptions::filter` and the ignore files.
+pub(crate) struct Filter<'a> {
+    pub(crate) root: &'a Path,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    predicate: Option<EntryFilter>,
+    ignore: Option<Ignore>,
+}
+
+impl<'a> Filter<'a> {
+    // A filter with the glob patterns only.
+    pub(crate) fn new(root: &'a Path, include: &[String], exclude: &[String]) -> Filter<'a> {
+        Filter {
+            root,
+            include: include.iter().map(|pattern| Glob::new(pattern)).collect(),
+            exclude: exclude.iter().map(|pattern| Glob::new(pattern)).collect(),
+            predicate: None,
+            ignore: None,
+        }
+    }
+
+    pub(crate) fn for_walk(root: &'a Path, options: &DirOptions) -> Filter<'a> {
+        Filter {
+            predicate: options.filter.clone(),
+            ignore: Ignore::new(root, options.gitignore, &options.ignore_files),
+            ..Filter::new(root, &options.include, &options.exclude)
+        }
+    }
+
+    // Called by the walk before it visits the entries of `dir`, to read its ignore files.
+    pub(crate) fn enter(&self, dir: &Path) -> Result<()> {
+        match &self.ignore {
+            Some(ignore) => ignore.enter(dir),
+            None => Ok(()),
+        }
+    }
+
+    // Called by the walk after it visited the entries of `dir`.
+    pub(crate) fn leave(&self, dir: &Path) {
+        if let Some(ignore) = &self.ignore {
+            ignore.leave(dir);
+        }
+    }
+
+    // Applies the glob patterns and the predicate to an entry with the `metadata` the walk
+    // uses for it. Returns `None` if the entry is left out, for a directory together with
+    // everything below it. `parent` is the visit of the directory containing the entry.
+    pub(crate) fn visit(&self, path: &Path, metadata: &Metadata, parent: Visit) -> Option<Visit> {
+        let is_dir = metadata.is_dir();
+        let included = self.check(path, is_dir, parent.included)?;
+        if let Some(ignore) = &self.ignore {
+            if ignore.is_ignored(path, is_dir) {
+                return None;
+            }
+        }
+        let skipped = match &self.predicate {
+            Some(predicate) => match predicate(path, metadata) {
+                FilterResult::Include => false,
+                FilterResult::Skip => true,
+                FilterResult::Prune => return None,
+            },
+            None => false,
+        };
+        if !is_dir && (skipped || !included) {
+            return None;
+        }
+        Some(Visit { included, skipped })
+    }
+
+    // Returns `None` if `path` is excluded, together with everything below it. Otherwise
+    // returns whether `path` is included, by itself or because its directory is included
+    // (`parent_included`). Everything is included if there are no include patterns.
+    pub(crate) fn check(&self, path: &Path, is_dir: bool, parent_included: bool) -> Option<bool> {
+        let relative = path.strip_prefix(self.root).unwrap_or(path);
+        if self
+            .exclude
+            .iter()
+            .any(|glob| glob.matches(relative, is_dir))
+        {
+            return None;
+        }
+        Some(
+            self.include.is_empty()
+                || parent_included
+                || self
+                    .include
+                    .iter()
+                    .any(|glob| glob.matches(relative, is_dir)),
+        )
+    }
+}
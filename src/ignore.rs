@@ -0,0 +1,136 @@
+// Ignore files with the syntax of `.gitignore`, read at each level of a directory walk.
+//
+// The patterns of a file apply to the directory containing it, see `glob::Glob`. Patterns
+// starting with `!` re-include what an earlier pattern ignored. Within a file the last
+// matching pattern wins, and files deeper in the tree take precedence over the ones above.
+// `.git/info/exclude` of the root takes the lowest precedence.
+
+use crate::error::*;
+use crate::glob::Glob;
+use std::cell::RefCell;
+use std::fs::read_to_string;
+use std::io::ErrorKind as IoErrorKind;
+use std::path::{Path, PathBuf};
+
+struct Rule {
+    glob: Glob,
+    negated: bool,
+}
+
+// The rules of the ignore files in `base`.
+struct Level {
+    base: PathBuf,
+    rules: Vec<Rule>,
+}
+
+pub(crate) struct Ignore {
+    root: PathBuf,
+    gitignore: bool,
+    ignore_files: Vec<String>,
+    levels: RefCell<Vec<Level>>,
+}
+
+impl Ignore {
+    // Returns `None` if no ignore files are read.
+    pub(crate) fn new(root: &Path, gitignore: bool, ignore_files: &[String]) -> Option<Ignore> {
+        if !gitignore && ignore_files.is_empty() {
+            return None;
+        }
+        Some(Ignore {
+            root: root.to_path_buf(),
+            gitignore,
+            ignore_files: ignore_files.to_vec(),
+            levels: RefCell::new(Vec::new()),
+        })
+    }
+
+    // Reads the ignore files of `dir` before its entries are checked. Every call is followed
+    // by a call of `leave` once the entries of `dir` are done.
+    pub(crate) fn enter(&self, dir: &Path) -> Result<()> {
+        let mut levels = self.levels.borrow_mut();
+        if self.gitignore && dir == self.root {
+            let exclude = dir.join(".git").join("info").join("exclude");
+            levels.push(Level {
+                base: dir.to_path_buf(),
+                rules: read_rules(&exclude)?,
+            });
+        }
+        let mut rules = Vec::new();
+        if self.gitignore {
+            rules.append(&mut read_rules(&dir.join(".gitignore"))?);
+        }
+        // Custom ignore files take precedence over `.gitignore`.
+        for name in &self.ignore_files {
+            rules.append(&mut read_rules(&dir.join(name))?);
+        }
+        levels.push(Level {
+            base: dir.to_path_buf(),
+            rules,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn leave(&self, dir: &Path) {
+        let mut levels = self.levels.borrow_mut();
+        levels.pop();
+        if self.gitignore && dir == self.root {
+            levels.pop();
+        }
+    }
+
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        // Git does not look into its own directory.
+        if self.gitignore && is_dir && path.file_name() == Some(".git".as_ref()) {
+            return true;
+        }
+        for level in self.levels.borrow().iter().rev() {
+            let relative = match path.strip_prefix(&level.base) {
+                Ok(relative) => relative,
+                Err(_) => continue,
+            };
+            let rule = level
+                .rules
+                .iter()
+                .rev()
+                .find(|rule| rule.glob.matches(relative, is_dir));
+            if let Some(rule) = rule {
+                return !rule.negated;
+            }
+        }
+        false
+    }
+}
+
+// Reads the rules of an ignore file. A missing file has no rules.
+fn read_rules(path: &Path) -> Result<Vec<Rule>> {
+    let content = match read_to_string(path) {
+        Ok(content) => content,
+        Err(ref err) if err.kind() == IoErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(content.lines().filter_map(parse_rule).collect())
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    // Trailing spaces are removed unless they are escaped.
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut trimmed = line.trim_end_matches(' ');
+    if trimmed.ends_with('\\') && trimmed.len() < line.len() {
+        trimmed = &line[..trimmed.len() + 1];
+    }
+    let mut line = trimmed;
+    let negated = line.starts_with('!');
+    if negated {
+        line = &line[1..];
+    }
+    if line.is_empty() {
+        return None;
+    }
+    Some(Rule {
+        glob: Glob::new(line),
+        negated,
+    })
+}
@@ -0,0 +1,328 @@
+use crate::error::*;
+use crate::file;
+use std::collections::HashMap;
+use std::fs::{File, Metadata, OpenOptions};
+use std::io::{ErrorKind as IoErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+// Copied bytes between two progress records of the same file.
+const PROGRESS_INTERVAL: u64 = 16 * 1024 * 1024;
+
+/// A file of a copy job which is not done yet, see [`pending`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pending {
+    /// Path of the source file.
+    pub from: PathBuf,
+    /// Path of the destination file.
+    pub to: PathBuf,
+    /// Size of the source file when the job was started.
+    pub total_bytes: u64,
+    /// Bytes which were copied when the progress was recorded last. 0 if the copy of the
+    /// file did not start.
+    pub copied_bytes: u64,
+}
+
+/// Returns the files of the copy job recorded in the journal `path` which are not done yet,
+/// in the order the job copies them. The job is complete if the list is empty.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is not limited to just
+/// these cases:
+///
+/// * The journal `path` does not exist.
+/// * The current process does not have the permission to read `path`.
+///
+/// # Example
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::journal;
+///
+/// for file in journal::pending("copy.journal")? {
+///     println!("{}: {} of {} bytes", file.from.display(), file.copied_bytes, file.total_bytes);
+/// }
+/// ```
+pub fn pending<P: AsRef<Path>>(path: P) -> Result<Vec<Pending>> {
+    let mut content = String::new();
+    File::open(path)?.read_to_string(&mut content)?;
+    let records = Records::parse(&content);
+    Ok(records
+        .order
+        .iter()
+        .filter(|key| !records.done.contains_key(*key))
+        .map(|key| {
+            let mut pending = records.planned[key].clone();
+            pending.copied_bytes = records.offsets.get(key).copied().unwrap_or(0);
+            pending
+        })
+        .collect())
+}
+
+// A completed file.
+struct Done {
+    to: PathBuf,
+    // Size and modification time of the source when it was copied.
+    len: u64,
+    modified: u128,
+}
+
+// The records of a journal, keyed by the source path.
+#[derive(Default)]
+struct Records {
+    order: Vec<String>,
+    planned: HashMap<String, Pending>,
+    offsets: HashMap<String, u64>,
+    done: HashMap<String, Done>,
+}
+
+impl Records {
+    // Lines which can not be parsed, like a line cut off by a crash, are ignored.
+    fn parse(content: &str) -> Records {
+        let mut records = Records::default();
+        for line in content.lines() {
+            let fields: Vec<String> = line.split('\t').map(unescape).collect();
+            match (fields[0].as_str(), fields.len()) {
+                ("todo", 4) => {
+                    if let Ok(total_bytes) = fields[3].parse() {
+                        records.plan(&fields[1], Path::new(&fields[2]), total_bytes);
+                    }
+                }
+                ("offset", 3) => {
+                    if let Ok(offset) = fields[2].parse() {
+                        records.offsets.insert(fields[1].clone(), offset);
+                    }
+                }
+                ("done", 5) => {
+                    if let (Ok(len), Ok(modified)) = (fields[3].parse(), fields[4].parse()) {
+                        let done = Done {
+                            to: PathBuf::from(&fields[2]),
+                            len,
+                            modified,
+                        };
+                        records.done.insert(fields[1].clone(), done);
+                    }
+                }
+                _ => {}
+            }
+        }
+        records
+    }
+
+    // Returns false if `key` is planned already.
+    fn plan(&mut self, key: &str, to: &Path, total_bytes: u64) -> bool {
+        if self.planned.contains_key(key) {
+            return false;
+        }
+        self.order.push(key.to_string());
+        let pending = Pending {
+            from: PathBuf::from(key),
+            to: to.to_path_buf(),
+            total_bytes,
+            copied_bytes: 0,
+        };
+        self.planned.insert(key.to_string(), pending);
+        true
+    }
+}
+
+// An open journal of a copy job, see `dir::CopyOptions::journal`. Records are appended as
+// lines, so the journal of a job which was interrupted keeps everything recorded before.
+pub(crate) struct Journal {
+    file: File,
+    records: Records,
+    // Flush every completed file to the disk.
+    sync: bool,
+    // The last recorded progress of the current file.
+    offset: u64,
+    // The first error from recording the progress, which can not be returned right away.
+    error: Option<Error>,
+}
+
+impl Journal {
+    // Opens the journal at `path`, which is created if it does not exist.
+    pub(crate) fn open(path: &Path, sync: bool) -> Result<Journal> {
+        let mut content = String::new();
+        match File::open(path) {
+            Ok(mut file) => {
+                file.read_to_string(&mut content)?;
+            }
+            Err(ref err) if err.kind() == IoErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        // Ends a line cut off by a crash, so that it does not merge with the next record.
+        if !content.is_empty() && !content.ends_with('\n') {
+            file.write_all(b"\n")?;
+        }
+        Ok(Journal {
+            file,
+            records: Records::parse(&content),
+            sync,
+            offset: 0,
+            error: None,
+        })
+    }
+
+    // Records that the job copies `from` to `to`, unless it is recorded already.
+    pub(crate) fn plan(&mut self, from: &Path, to: &Path) -> Result<()> {
+        let name = key(from);
+        let total_bytes = source_metadata(from)?.len();
+        if self.records.plan(&name, to, total_bytes) {
+            let line = format!(
+                "todo\t{}\t{}\t{}",
+                escape(&name),
+                escape(&key(to)),
+                total_bytes
+            );
+            self.write(&line)?;
+        }
+        Ok(())
+    }
+
+    // Starts the copy of `from`. Returns false if it is done already. A destination which
+    // holds a part of `from` copied by this job is continued, see `file::CopyOptions::resume`.
+    // The overwrite policy of `options` still applies to any other destination.
+    pub(crate) fn start(&mut self, from: &Path, options: &mut file::CopyOptions) -> bool {
+        if self.is_done(from) {
+            return false;
+        }
+        if self.is_partial(from) {
+            options.resume = true;
+        }
+        self.offset = 0;
+        true
+    }
+
+    // Returns true if `from` was copied completely and neither it nor its copy changed since.
+    fn is_done(&self, from: &Path) -> bool {
+        let done = match self.records.done.get(&key(from)) {
+            Some(done) => done,
+            None => return false,
+        };
+        let metadata = match source_metadata(from) {
+            Ok(metadata) => metadata,
+            Err(_) => return false,
+        };
+        let copied = match done.to.symlink_metadata() {
+            Ok(to_metadata) => to_metadata.is_symlink() || to_metadata.len() == done.len,
+            Err(_) => false,
+        };
+        copied && metadata.len() == done.len && modified(&metadata) == done.modified
+    }
+
+    // Returns true if the copy of `from` wrote to its destination but did not complete, so
+    // the destination holds a part of it. Progress is only recorded once the copy wrote
+    // some bytes, so a destination the copy never reached is not taken for a part of it.
+    fn is_partial(&self, from: &Path) -> bool {
+        let key = key(from);
+        let offset = self.records.offsets.get(&key).copied().unwrap_or(0);
+        offset > 0 && !self.records.done.contains_key(&key)
+    }
+
+    // Records the progress of the copy of `from` with the first copied bytes and then every
+    // `PROGRESS_INTERVAL` bytes. An error is returned by the next call of `done`.
+    pub(crate) fn progress(&mut self, from: &Path, copied_bytes: u64) {
+        let due = if self.offset == 0 {
+            copied_bytes > 0
+        } else {
+            copied_bytes >= self.offset + PROGRESS_INTERVAL
+        };
+        if !due || self.error.is_some() {
+            return;
+        }
+        self.offset = copied_bytes;
+        if let Err(err) = self.record_offset(from, copied_bytes) {
+            self.error = Some(err);
+        }
+    }
+
+    // Records that `from` was copied completely to `to`.
+    pub(crate) fn done(&mut self, from: &Path, to: &Path) -> Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        let name = key(from);
+        let metadata = source_metadata(from)?;
+        let done = Done {
+            to: to.to_path_buf(),
+            len: metadata.len(),
+            modified: modified(&metadata),
+        };
+        let line = format!(
+            "done\t{}\t{}\t{}\t{}",
+            escape(&name),
+            escape(&key(to)),
+            done.len,
+            done.modified
+        );
+        self.write(&line)?;
+        if self.sync {
+            self.file.sync_data()?;
+        }
+        self.records.done.insert(name, done);
+        Ok(())
+    }
+
+    fn record_offset(&mut self, from: &Path, offset: u64) -> Result<()> {
+        let key = key(from);
+        self.write(&format!("offset\t{}\t{}", escape(&key), offset))?;
+        self.records.offsets.insert(key, offset);
+        Ok(())
+    }
+
+    // Appends `line` with a single write, so that records are not interleaved.
+    fn write(&mut self, line: &str) -> Result<()> {
+        self.file.write_all(format!("{}\n", line).as_bytes())?;
+        Ok(())
+    }
+}
+
+fn key(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+// The metadata of the file a source entry stands for. Preserved symlinks which point
+// nowhere stand for themselves.
+fn source_metadata(path: &Path) -> Result<Metadata> {
+    match path.metadata() {
+        Ok(metadata) => Ok(metadata),
+        Err(_) => Ok(path.symlink_metadata()?),
+    }
+}
+
+fn modified(metadata: &Metadata) -> u128 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0)
+}
+
+fn escape(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+    result
+}
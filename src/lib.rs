@@ -153,12 +153,63 @@ pub mod file;
 ///
 pub mod dir;
 
+/// This module plans copies, moves and removals without changing anything.
+///
+/// A plan lists the operations in order and can be executed later. The execution fails
+/// with `ErrorKind::PlanOutdated` if the file system changed after the plan was made.
+///
+/// # Example
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::dir::CopyOptions;
+/// use fs_extra::plan::{self, Operation};
+///
+/// let options = CopyOptions::new().overwrite(true);
+/// let plan = plan::copy_items(&["source/dir1", "source/file.txt"], "target", &options)?;
+/// for operation in plan.operations() {
+///     if let Operation::Overwrite { to, .. } = operation {
+///         println!("{} will be overwritten", to.display());
+///     }
+/// }
+/// plan.execute()?;
+/// ```
+pub mod plan;
+
+/// This module lists the remaining work of copy jobs which keep a journal.
+///
+/// With `dir::CopyOptions::journal` set, `dir::copy_with_progress` and
+/// `copy_items_with_progress` record the files they are going to copy, the progress of the
+/// current file and every completed file. A job which runs again with the same journal
+/// skips the completed files whose source did not change since, and continues the file
+/// it was interrupted in.
+///
+/// # Example
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::dir::CopyOptions;
+/// use fs_extra::journal;
+///
+/// let options = CopyOptions::new().journal("copy.journal");
+/// if let Err(err) = fs_extra::copy_items_with_progress(&["dir1"], "target", &options, handle) {
+///     for file in journal::pending("copy.journal")? {
+///         println!("{} still has to be copied", file.from.display());
+///     }
+/// }
+/// ```
+pub mod journal;
+
+mod digest;
+mod features;
+mod glob;
+mod ignore;
+mod sys;
+
 use crate::error::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Copies a list of directories and files to another place recursively. This function will
-/// also copy the permission bits of the original files to destination files (not for
-/// directories).
+/// also copy the permission bits of the original files and directories to destination
+/// files and newly created directories.
 ///
 /// # Errors
 ///
@@ -170,7 +221,7 @@ use std::path::Path;
 /// * List `from` contains  file or directory with invalid name.
 ///
 /// * The current process does not have the permission to access to file from `lists from` or
-/// `to`.
+///   `to`.
 ///
 /// # Example
 ///
@@ -205,11 +256,7 @@ where
             result += dir::copy(item, &to, options)?;
         } else if let Some(file_name) = item.file_name() {
             if let Some(file_name) = file_name.to_str() {
-                let file_options = file::CopyOptions {
-                    overwrite: options.overwrite,
-                    skip_exist: options.skip_exist,
-                    ..Default::default()
-                };
+                let file_options = options.file_options();
                 result += file::copy(item, to.as_ref().join(file_name), &file_options)?;
             }
         } else {
@@ -236,6 +283,12 @@ pub struct TransitProcess {
     pub dir_name: String,
     /// Transit state
     pub state: dir::TransitState,
+    /// Path the current file is written to if the destination existed and it was renamed,
+    /// see `file::OverwritePolicy::Rename`.
+    pub renamed_to: Option<PathBuf>,
+    /// Path the overwritten destination of the current file was moved to, see
+    /// `dir::CopyOptions::backup`.
+    pub backup: Option<PathBuf>,
 }
 
 impl Clone for TransitProcess {
@@ -248,13 +301,15 @@ impl Clone for TransitProcess {
             file_name: self.file_name.clone(),
             dir_name: self.dir_name.clone(),
             state: self.state.clone(),
+            renamed_to: self.renamed_to.clone(),
+            backup: self.backup.clone(),
         }
     }
 }
 
 /// Copies a list of directories and files to another place recursively, with
 /// information about progress. This function will also copy the permission bits of the
-/// original files to destination files (not for directories).
+/// original files and directories to destination files and newly created directories.
 ///
 /// # Errors
 ///
@@ -266,7 +321,7 @@ impl Clone for TransitProcess {
 /// * List `from` contains  file or directory with invalid name.
 ///
 /// * The current process does not have the permission to access to file from `lists from` or
-/// `to`.
+///   `to`.
 ///
 /// # Example
 /// ```rust,ignore
@@ -307,9 +362,20 @@ where
     let mut list_paths = Vec::new();
     for item in from {
         let item = item.as_ref();
-        total_size += dir::get_size(item)?;
+        total_size += dir::get_size2(item, &options.size_options())?;
         list_paths.push(item);
     }
+    // Every item is recorded before the copy starts, so that the journal lists all the work.
+    let mut journal = dir::open_journal(options)?;
+    if let Some(journal) = &mut journal {
+        for item in &list_paths {
+            if item.is_dir() {
+                dir::plan_journal(journal, item, to.as_ref(), options)?;
+            } else if let Some(file_name) = item.file_name() {
+                journal.plan(item, &to.as_ref().join(file_name))?;
+            }
+        }
+    }
 
     let mut result: u64 = 0;
     let mut info_process = TransitProcess {
@@ -320,12 +386,16 @@ where
         file_name: String::new(),
         dir_name: String::new(),
         state: dir::TransitState::Normal,
+        renamed_to: None,
+        backup: None,
     };
 
     let mut options = options.clone();
+    // Set once the handler skips all files which fail verification.
+    let mut skip_unverified = false;
     for item in list_paths {
         if item.is_dir() {
-            if let Some(dir_name) = item.components().last() {
+            if let Some(dir_name) = item.components().next_back() {
                 if let Ok(dir_name) = dir_name.as_os_str().to_os_string().into_string() {
                     info_process.dir_name = dir_name;
                 } else {
@@ -336,25 +406,44 @@ where
             }
 
             let copied_bytes = result;
-            let dir_options = options.clone();
+            // The journal which is open already records the directory too.
+            let mut dir_options = options.clone();
+            dir_options.journal = None;
             let handler = |info: dir::TransitProcess| {
                 info_process.copied_bytes = copied_bytes + info.copied_bytes;
                 info_process.state = info.state;
+                info_process.renamed_to = info.renamed_to;
+                info_process.backup = info.backup;
+                let verification_failed =
+                    info_process.state == dir::TransitState::VerificationFailed;
+                if verification_failed && skip_unverified {
+                    return dir::TransitProcessResult::Skip;
+                }
                 let result = progress_handler(info_process.clone());
                 match result {
+                    dir::TransitProcessResult::SkipAll if verification_failed => {
+                        skip_unverified = true
+                    }
                     dir::TransitProcessResult::OverwriteAll => options.overwrite = true,
                     dir::TransitProcessResult::SkipAll => options.skip_exist = true,
+                    dir::TransitProcessResult::RenameAll => {
+                        options.overwrite_policy = file::OverwritePolicy::Rename
+                    }
                     _ => {}
                 }
                 result
             };
-            result += dir::copy_with_progress(item, &to, &dir_options, handler)?;
+            result += dir::_copy_with_progress(
+                item,
+                to.as_ref(),
+                &dir_options,
+                journal.as_mut(),
+                handler,
+            )?;
         } else {
-            let mut file_options = file::CopyOptions {
-                overwrite: options.overwrite,
-                skip_exist: options.skip_exist,
-                buffer_size: options.buffer_size,
-            };
+            let mut file_options = options.file_options();
+            // Failures are reported to the handler below instead of failing the copy.
+            file_options.preserve_xattrs = false;
 
             if let Some(file_name) = item.file_name() {
                 if let Some(file_name) = file_name.to_str() {
@@ -369,25 +458,50 @@ where
             info_process.file_bytes_copied = 0;
             info_process.file_total_bytes = item.metadata()?.len();
 
+            if let Some(journal) = &mut journal {
+                if !journal.start(item, &mut file_options) {
+                    continue;
+                }
+            }
+
             let copied_bytes = result;
-            let file_name = to.as_ref().join(info_process.file_name.clone());
+            let requested = to.as_ref().join(info_process.file_name.clone());
+            let mut file_name = file::destination(&requested, &file_options);
             let mut work = true;
 
-            let mut result_copy: Result<u64>;
+            let mut result_copy: Result<Option<u64>>;
             while work {
+                info_process.renamed_to = Some(file_name.clone()).filter(|path| *path != requested);
                 {
                     let handler = |info: file::TransitProcess| {
                         info_process.copied_bytes = copied_bytes + info.copied_bytes;
                         info_process.file_bytes_copied = info.copied_bytes;
+                        info_process.backup = info.backup;
+                        if let Some(journal) = &mut journal {
+                            journal.progress(item, info.copied_bytes);
+                        }
                         progress_handler(info_process.clone());
                     };
-                    result_copy =
-                        file::copy_with_progress(item, &file_name, &file_options, handler);
+                    result_copy = file::_copy_with_progress(
+                        item,
+                        &file_name,
+                        &file_options,
+                        &mut None,
+                        handler,
+                    );
                 }
                 match result_copy {
-                    Ok(val) => {
-                        result += val;
+                    Ok(copied) => {
+                        result += copied.unwrap_or(0);
                         work = false;
+                        if copied.is_some() && options.preserve_xattrs {
+                            if let Err(err) = file::copy_xattrs(item, &file_name, &file_options) {
+                                report_xattr_error(err, &info_process, &mut progress_handler)?;
+                            }
+                        }
+                        if let Some(journal) = &mut journal {
+                            journal.done(item, &file_name)?;
+                        }
                     }
                     Err(err) => match err.kind {
                         ErrorKind::AlreadyExists => {
@@ -410,6 +524,14 @@ where
                                     options.skip_exist = true;
                                 }
                                 dir::TransitProcessResult::Retry => {}
+                                dir::TransitProcessResult::Rename => {
+                                    file_name = file::free_path(&file_name);
+                                }
+                                dir::TransitProcessResult::RenameAll => {
+                                    file_name = file::free_path(&file_name);
+                                    file_options.overwrite_policy = file::OverwritePolicy::Rename;
+                                    options.overwrite_policy = file::OverwritePolicy::Rename;
+                                }
                                 dir::TransitProcessResult::ContinueOrAbort => {
                                     let err_msg = err.to_string();
                                     err!(err_msg.as_str(), err.kind)
@@ -420,6 +542,14 @@ where
                                 }
                             }
                         }
+                        ErrorKind::VerificationFailed => {
+                            let mut info_process = info_process.clone();
+                            info_process.state = dir::TransitState::VerificationFailed;
+                            let ask = || progress_handler(info_process);
+                            if dir::skip_unverified_file(err, &mut skip_unverified, ask)? {
+                                work = false;
+                            }
+                        }
                         ErrorKind::PermissionDenied => {
                             let mut info_process = info_process.clone();
                             info_process.state = dir::TransitState::Exists;
@@ -439,6 +569,12 @@ where
                                     options.skip_exist = true;
                                 }
                                 dir::TransitProcessResult::Retry => {}
+                                dir::TransitProcessResult::Rename => {
+                                    err!("Rename denied for this situation!", ErrorKind::Other);
+                                }
+                                dir::TransitProcessResult::RenameAll => {
+                                    err!("Rename denied for this situation!", ErrorKind::Other);
+                                }
                                 dir::TransitProcessResult::ContinueOrAbort => {
                                     let err_msg = err.to_string();
                                     err!(err_msg.as_str(), err.kind)
@@ -463,8 +599,8 @@ where
 }
 
 /// Moves a list of directories and files to another place recursively. This function will
-/// also copy the permission bits of the original files to destination files (not for
-/// directories).
+/// also copy the permission bits of the original files and directories to destination
+/// files and newly created directories.
 ///
 /// # Errors
 ///
@@ -476,7 +612,7 @@ where
 /// * List `from` contains  file or directory with invalid name.
 ///
 /// * The current process does not have the permission to access to file from `lists from` or
-/// `to`.
+///   `to`.
 ///
 /// # Example
 ///
@@ -508,7 +644,7 @@ where
     let mut list_paths = Vec::new();
     for item in from_items {
         let item = item.as_ref();
-        total_size += dir::get_size(item)?;
+        total_size += dir::get_size2(item, &options.size_options())?;
         list_paths.push(item);
     }
 
@@ -521,11 +657,13 @@ where
         file_name: String::new(),
         dir_name: String::new(),
         state: dir::TransitState::Normal,
+        renamed_to: None,
+        backup: None,
     };
 
     for item in list_paths {
         if item.is_dir() {
-            if let Some(dir_name) = item.components().last() {
+            if let Some(dir_name) = item.components().next_back() {
                 if let Ok(dir_name) = dir_name.as_os_str().to_os_string().into_string() {
                     info_process.dir_name = dir_name;
                 } else {
@@ -537,11 +675,7 @@ where
 
             result += dir::move_dir(item, &to, options)?;
         } else {
-            let file_options = file::CopyOptions {
-                overwrite: options.overwrite,
-                skip_exist: options.skip_exist,
-                buffer_size: options.buffer_size,
-            };
+            let file_options = options.file_options();
 
             if let Some(file_name) = item.file_name() {
                 if let Some(file_name) = file_name.to_str() {
@@ -566,7 +700,7 @@ where
 
 /// Moves a list of directories and files to another place recursively, with
 /// information about progress. This function will also copy the permission bits of the
-/// original files to destination files (not for directories).
+/// original files and directories to destination files and newly created directories.
 ///
 /// # Errors
 ///
@@ -578,7 +712,7 @@ where
 /// * List `from` contains  file or directory with invalid name.
 ///
 /// * The current process does not have the permission to access to file from `lists from` or
-/// `to`.
+///   `to`.
 ///
 /// # Example
 ///
@@ -619,7 +753,7 @@ where
     let mut list_paths = Vec::new();
     for item in from_items {
         let item = item.as_ref();
-        total_size += dir::get_size(item)?;
+        total_size += dir::get_size2(item, &options.size_options())?;
         list_paths.push(item);
     }
 
@@ -632,12 +766,16 @@ where
         file_name: String::new(),
         dir_name: String::new(),
         state: dir::TransitState::Normal,
+        renamed_to: None,
+        backup: None,
     };
     let mut options = options.clone();
 
+    // Set once the handler skips all files which fail verification.
+    let mut skip_unverified = false;
     for item in list_paths {
         if item.is_dir() {
-            if let Some(dir_name) = item.components().last() {
+            if let Some(dir_name) = item.components().next_back() {
                 if let Ok(dir_name) = dir_name.as_os_str().to_os_string().into_string() {
                     info_process.dir_name = dir_name;
                 } else {
@@ -652,21 +790,30 @@ where
             let handler = |info: dir::TransitProcess| {
                 info_process.copied_bytes = copied_bytes + info.copied_bytes;
                 info_process.state = info.state;
+                info_process.renamed_to = info.renamed_to;
+                info_process.backup = info.backup;
+                let verification_failed =
+                    info_process.state == dir::TransitState::VerificationFailed;
+                if verification_failed && skip_unverified {
+                    return dir::TransitProcessResult::Skip;
+                }
                 let result = progress_handler(info_process.clone());
                 match result {
+                    dir::TransitProcessResult::SkipAll if verification_failed => {
+                        skip_unverified = true
+                    }
                     dir::TransitProcessResult::OverwriteAll => options.overwrite = true,
                     dir::TransitProcessResult::SkipAll => options.skip_exist = true,
+                    dir::TransitProcessResult::RenameAll => {
+                        options.overwrite_policy = file::OverwritePolicy::Rename
+                    }
                     _ => {}
                 }
                 result
             };
             result += dir::move_dir_with_progress(item, &to, &dir_options, handler)?;
         } else {
-            let mut file_options = file::CopyOptions {
-                overwrite: options.overwrite,
-                skip_exist: options.skip_exist,
-                buffer_size: options.buffer_size,
-            };
+            let mut file_options = options.file_options();
 
             if let Some(file_name) = item.file_name() {
                 if let Some(file_name) = file_name.to_str() {
@@ -682,24 +829,41 @@ where
             info_process.file_total_bytes = item.metadata()?.len();
 
             let copied_bytes = result;
-            let file_name = to.as_ref().join(info_process.file_name.clone());
+            let requested = to.as_ref().join(info_process.file_name.clone());
+            let mut file_name = file::destination(&requested, &file_options);
             let mut work = true;
 
             let mut result_copy: Result<u64>;
             while work {
+                info_process.renamed_to = Some(file_name.clone()).filter(|path| *path != requested);
+                // Failures are reported to the handler below instead of failing the move.
+                let mut xattr_error = None;
                 {
                     let handler = |info: file::TransitProcess| {
                         info_process.copied_bytes = copied_bytes + info.copied_bytes;
                         info_process.file_bytes_copied = info.copied_bytes;
+                        info_process.backup = info.backup;
                         progress_handler(info_process.clone());
                     };
-                    result_copy =
-                        file::move_file_with_progress(item, &file_name, &file_options, handler);
+                    let xattr_failed = |err| {
+                        xattr_error = Some(err);
+                        Ok(())
+                    };
+                    result_copy = file::_move_file_with_progress(
+                        item,
+                        &file_name,
+                        &file_options,
+                        xattr_failed,
+                        handler,
+                    );
                 }
                 match result_copy {
                     Ok(val) => {
                         result += val;
                         work = false;
+                        if let Some(err) = xattr_error {
+                            report_xattr_error(err, &info_process, &mut progress_handler)?;
+                        }
                     }
                     Err(err) => match err.kind {
                         ErrorKind::AlreadyExists => {
@@ -722,6 +886,14 @@ where
                                     options.skip_exist = true;
                                 }
                                 dir::TransitProcessResult::Retry => {}
+                                dir::TransitProcessResult::Rename => {
+                                    file_name = file::free_path(&file_name);
+                                }
+                                dir::TransitProcessResult::RenameAll => {
+                                    file_name = file::free_path(&file_name);
+                                    file_options.overwrite_policy = file::OverwritePolicy::Rename;
+                                    options.overwrite_policy = file::OverwritePolicy::Rename;
+                                }
                                 dir::TransitProcessResult::ContinueOrAbort => {
                                     let err_msg = err.to_string();
                                     err!(err_msg.as_str(), err.kind)
@@ -732,6 +904,14 @@ where
                                 }
                             }
                         }
+                        ErrorKind::VerificationFailed => {
+                            let mut info_process = info_process.clone();
+                            info_process.state = dir::TransitState::VerificationFailed;
+                            let ask = || progress_handler(info_process);
+                            if dir::skip_unverified_file(err, &mut skip_unverified, ask)? {
+                                work = false;
+                            }
+                        }
                         ErrorKind::PermissionDenied => {
                             let mut info_process = info_process.clone();
                             info_process.state = dir::TransitState::Exists;
@@ -751,6 +931,12 @@ where
                                     options.skip_exist = true;
                                 }
                                 dir::TransitProcessResult::Retry => {}
+                                dir::TransitProcessResult::Rename => {
+                                    err!("Rename denied for this situation!", ErrorKind::Other);
+                                }
+                                dir::TransitProcessResult::RenameAll => {
+                                    err!("Rename denied for this situation!", ErrorKind::Other);
+                                }
                                 dir::TransitProcessResult::ContinueOrAbort => {
                                     let err_msg = err.to_string();
                                     err!(err_msg.as_str(), err.kind)
@@ -773,6 +959,24 @@ where
     Ok(result)
 }
 
+// Tells the progress handler that extended attributes of `info_process.file_name` could
+// not be copied. Returns `err` if the handler decides to abort.
+fn report_xattr_error<F>(
+    err: Error,
+    info_process: &TransitProcess,
+    progress_handler: &mut F,
+) -> Result<()>
+where
+    F: FnMut(TransitProcess) -> dir::TransitProcessResult,
+{
+    let mut info_process = info_process.clone();
+    info_process.state = dir::TransitState::XattrFailed;
+    match progress_handler(info_process) {
+        dir::TransitProcessResult::Abort => Err(err),
+        _ => Ok(()),
+    }
+}
+
 /// Removes a list of files or directories.
 ///
 /// # Example
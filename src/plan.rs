@@ -0,0 +1,761 @@
+use crate::dir::{self, DirContent, DirOptions, SpecialFilePolicy, SymlinkPolicy};
+use crate::error::*;
+use crate::file::{self, OverwritePolicy};
+use crate::glob::Filter;
+use std::collections::HashSet;
+use std::fs::{read_dir, FileType, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A single change to the file system listed by a [`Plan`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Create the directory `to`.
+    CreateDir {
+        /// Path of the new directory.
+        to: PathBuf,
+    },
+    /// Copy `from` to `to`, which does not exist yet.
+    CopyFile {
+        /// Source path.
+        from: PathBuf,
+        /// Destination path.
+        to: PathBuf,
+        /// Bytes to copy. Further names of a hard linked file are linked and count 0 bytes.
+        size: u64,
+    },
+    /// Replace the existing `to` with a copy of `from`.
+    Overwrite {
+        /// Source path.
+        from: PathBuf,
+        /// Destination path.
+        to: PathBuf,
+        /// Bytes to copy.
+        size: u64,
+    },
+    /// Move the existing `path` to `backup` before it is overwritten.
+    Backup {
+        /// Path which is overwritten.
+        path: PathBuf,
+        /// Path of the backup.
+        backup: PathBuf,
+    },
+    /// Keep the existing `to` and leave `from` where it is.
+    Skip {
+        /// Source path.
+        from: PathBuf,
+        /// Destination path.
+        to: PathBuf,
+    },
+    /// Remove `path` together with its content.
+    Remove {
+        /// Path to remove.
+        path: PathBuf,
+    },
+    /// Rename `from` to `to` in a single step.
+    Rename {
+        /// Source path.
+        from: PathBuf,
+        /// Destination path.
+        to: PathBuf,
+    },
+}
+
+/// The operations a copy, move or remove would perform, made without changing anything.
+///
+/// A plan remembers the state of every path it depends on. [`Plan::execute`] runs the
+/// operation it was made for with the same options, but only if none of these paths
+/// changed in the meantime.
+pub struct Plan {
+    operations: Vec<Operation>,
+    request: Request,
+    options: dir::CopyOptions,
+    snapshot: Vec<(PathBuf, PathState)>,
+}
+
+impl Plan {
+    /// Returns the operations in the order they would be performed.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Returns an `ErrorKind::PlanOutdated` error if a path the plan depends on changed
+    /// after the plan was made.
+    pub fn check(&self) -> Result<()> {
+        for (path, state) in &self.snapshot {
+            if PathState::of(path) != *state {
+                if let Some(msg) = path.to_str() {
+                    let msg = format!("Path \"{}\" changed since the plan was made", msg);
+                    err!(&msg, ErrorKind::PlanOutdated);
+                }
+                err!(
+                    "Path changed since the plan was made",
+                    ErrorKind::PlanOutdated
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the planned operation after [`Plan::check`]. Returns the result of the function
+    /// the plan was made for, or 0 for removals.
+    pub fn execute(&self) -> Result<u64> {
+        self.check()?;
+        match &self.request {
+            Request::Copy { from, to } => dir::copy(from, to, &self.options),
+            Request::MoveDir { from, to } => dir::move_dir(from, to, &self.options),
+            Request::CopyItems { from, to } => crate::copy_items(from, to, &self.options),
+            Request::MoveItems { from, to } => crate::move_items(from, to, &self.options),
+            Request::RemoveItems { items } => crate::remove_items(items).map(|_| 0),
+        }
+    }
+}
+
+// The call a plan was made for.
+enum Request {
+    Copy { from: PathBuf, to: PathBuf },
+    MoveDir { from: PathBuf, to: PathBuf },
+    CopyItems { from: Vec<PathBuf>, to: PathBuf },
+    MoveItems { from: Vec<PathBuf>, to: PathBuf },
+    RemoveItems { items: Vec<PathBuf> },
+}
+
+#[derive(Debug, PartialEq)]
+struct EntryState {
+    file_type: FileType,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl EntryState {
+    fn of(metadata: std::io::Result<Metadata>) -> Option<EntryState> {
+        metadata.ok().map(|metadata| EntryState {
+            file_type: metadata.file_type(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+// State of a path and, for a symlink, of its target. The modification time of a directory
+// changes when entries are added or removed.
+#[derive(Debug, PartialEq)]
+struct PathState {
+    entry: Option<EntryState>,
+    target: Option<EntryState>,
+}
+
+impl PathState {
+    fn of(path: &Path) -> PathState {
+        PathState {
+            entry: EntryState::of(path.symlink_metadata()),
+            target: EntryState::of(path.metadata()),
+        }
+    }
+}
+
+/// Plans [`dir::copy`] with the same arguments.
+///
+/// # Errors
+///
+/// Returns the errors `dir::copy` would return before changing anything, e.g. if `from`
+/// does not exist or an existing destination may not be overwritten.
+///
+/// # Example
+/// ```rust,ignore
+/// extern crate fs_extra;
+/// use fs_extra::dir::CopyOptions;
+/// use fs_extra::plan;
+///
+/// let options = CopyOptions::new();
+/// let plan = plan::copy("source/dir1", "target", &options)?;
+/// for operation in plan.operations() {
+///     println!("{:?}", operation);
+/// }
+/// plan.execute()?;
+/// ```
+pub fn copy<P, Q>(from: P, to: Q, options: &dir::CopyOptions) -> Result<Plan>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let mut planner = Planner::new(options);
+    planner.copy_dir(from, to)?;
+    Ok(planner.finish(Request::Copy {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+    }))
+}
+
+/// Plans [`dir::move_dir`] with the same arguments.
+///
+/// Entries are listed as a copy followed by the removal of the source, unless a single
+/// rename moves them to a free destination on the same file system.
+///
+/// # Errors
+///
+/// Returns the errors `dir::move_dir` would return before changing anything.
+pub fn move_dir<P, Q>(from: P, to: Q, options: &dir::CopyOptions) -> Result<Plan>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let mut planner = Planner::new(options);
+    planner.move_dir(from, to)?;
+    Ok(planner.finish(Request::MoveDir {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+    }))
+}
+
+/// Plans [`copy_items`](crate::copy_items) with the same arguments.
+///
+/// # Errors
+///
+/// Returns the errors `copy_items` would return before changing anything.
+pub fn copy_items<P, Q>(from: &[P], to: Q, options: &dir::CopyOptions) -> Result<Plan>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    if options.content_only {
+        err!(
+            "Options 'content_only' not acccess for copy_items function",
+            ErrorKind::Other
+        );
+    }
+    let to = to.as_ref();
+    let mut planner = Planner::new(options);
+    for item in from {
+        let item = item.as_ref();
+        if item.is_dir() {
+            planner.copy_dir(item, to)?;
+        } else if let Some(file_name) = item.file_name() {
+            planner.copy_file(item, &to.join(file_name), &options.file_options())?;
+        } else {
+            err!("Invalid file name", ErrorKind::InvalidFileName);
+        }
+    }
+    Ok(planner.finish(Request::CopyItems {
+        from: from
+            .iter()
+            .map(|item| item.as_ref().to_path_buf())
+            .collect(),
+        to: to.to_path_buf(),
+    }))
+}
+
+/// Plans [`move_items`](crate::move_items) with the same arguments.
+///
+/// # Errors
+///
+/// Returns the errors `move_items` would return before changing anything.
+pub fn move_items<P, Q>(from: &[P], to: Q, options: &dir::CopyOptions) -> Result<Plan>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    if options.content_only {
+        err!(
+            "Options 'content_only' not access for move_items function",
+            ErrorKind::Other
+        );
+    }
+    let to = to.as_ref();
+    for item in from {
+        dir::get_size2(item, &options.size_options())?;
+    }
+    let mut planner = Planner::new(options);
+    for item in from {
+        let item = item.as_ref();
+        if item.is_dir() {
+            planner.move_dir(item, to)?;
+        } else if let Some(file_name) = item.file_name() {
+            planner.move_file(item, &to.join(file_name), &options.file_options())?;
+        } else {
+            err!("Invalid file name", ErrorKind::InvalidFileName);
+        }
+    }
+    Ok(planner.finish(Request::MoveItems {
+        from: from
+            .iter()
+            .map(|item| item.as_ref().to_path_buf())
+            .collect(),
+        to: to.to_path_buf(),
+    }))
+}
+
+/// Plans [`remove_items`](crate::remove_items) with the same arguments.
+///
+/// # Errors
+///
+/// Returns an error if the content of a directory in `from_items` can not be read.
+pub fn remove_items<P>(from_items: &[P]) -> Result<Plan>
+where
+    P: AsRef<Path>,
+{
+    let options = dir::CopyOptions::new();
+    let mut planner = Planner::new(&options);
+    for item in from_items {
+        let item = item.as_ref();
+        planner.record_tree(item)?;
+        if item.exists() {
+            planner.remove(item);
+        }
+    }
+    Ok(planner.finish(Request::RemoveItems {
+        items: from_items
+            .iter()
+            .map(|item| item.as_ref().to_path_buf())
+            .collect(),
+    }))
+}
+
+// Follows the decisions of the copy and move functions without changing anything. Paths
+// created or removed by earlier operations are tracked, because later items depend on them.
+struct Planner<'a> {
+    options: &'a dir::CopyOptions,
+    operations: Vec<Operation>,
+    snapshot: Vec<(PathBuf, PathState)>,
+    recorded: HashSet<PathBuf>,
+    created: HashSet<PathBuf>,
+    removed: Vec<PathBuf>,
+}
+
+impl Planner<'_> {
+    fn new(options: &dir::CopyOptions) -> Planner<'_> {
+        Planner {
+            options,
+            operations: Vec::new(),
+            snapshot: Vec::new(),
+            recorded: HashSet::new(),
+            created: HashSet::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    fn finish(self, request: Request) -> Plan {
+        Plan {
+            operations: self.operations,
+            request,
+            options: self.options.clone(),
+            snapshot: self.snapshot,
+        }
+    }
+
+    // Remembers the current state of `path` for `Plan::check`.
+    fn record(&mut self, path: &Path) {
+        if self.recorded.insert(path.to_path_buf()) {
+            self.snapshot
+                .push((path.to_path_buf(), PathState::of(path)));
+        }
+    }
+
+    // Records `path` and everything below it, without following symlinks.
+    fn record_tree(&mut self, path: &Path) -> Result<()> {
+        self.record(path);
+        if path.symlink_metadata().map(|m| m.is_dir()).unwrap_or(false) {
+            for entry in read_dir(path)? {
+                self.record_tree(&entry?.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    // Whether `path` exists once the planned operations are done.
+    fn exists(&self, path: &Path) -> bool {
+        self.created.contains(path)
+            || (path.exists() && !self.removed.iter().any(|removed| path.starts_with(removed)))
+    }
+
+    fn remove(&mut self, path: &Path) {
+        self.removed.push(path.to_path_buf());
+        self.operations.push(Operation::Remove {
+            path: path.to_path_buf(),
+        });
+    }
+
+    fn create_dir(&mut self, path: &Path) {
+        self.created.insert(path.to_path_buf());
+        self.operations.push(Operation::CreateDir {
+            to: path.to_path_buf(),
+        });
+    }
+
+    // Plans the directories which `create_all` would create for `path`.
+    fn create_all(&mut self, path: &Path) {
+        if self.exists(path) {
+            return;
+        }
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                self.record(parent);
+                self.create_all(parent);
+            }
+        }
+        self.create_dir(path);
+    }
+
+    // Checks `from` and returns the directory it is copied or moved to.
+    fn destination(&mut self, from: &Path, to: &Path) -> Result<PathBuf> {
+        if !from.exists() {
+            if let Some(msg) = from.to_str() {
+                let msg = format!("Path \"{}\" does not exist or you don't have access!", msg);
+                err!(&msg, ErrorKind::NotFound);
+            }
+            err!(
+                "Path does not exist or you don't have access!",
+                ErrorKind::NotFound
+            );
+        }
+        if !from.is_dir() {
+            if let Some(msg) = from.to_str() {
+                let msg = format!("Path \"{}\" is not a directory!", msg);
+                err!(&msg, ErrorKind::InvalidFolder);
+            }
+            err!("Path is not a directory!", ErrorKind::InvalidFolder);
+        }
+        let dir_name = match from.components().next_back() {
+            Some(val) => val.as_os_str(),
+            None => err!("Invalid folder from", ErrorKind::InvalidFolder),
+        };
+        self.record(to);
+        let mut to = to.to_path_buf();
+        if (self.exists(&to) || !self.options.copy_inside) && !self.options.content_only {
+            to.push(dir_name);
+            self.record(&to);
+        }
+        Ok(to)
+    }
+
+    fn copy_dir(&mut self, from: &Path, to: &Path) -> Result<()> {
+        let to = self.destination(from, to)?;
+        let existed = to.is_dir();
+        self.copy_content(from, &to, &self.options.dir_options(), false)?;
+
+        if self.options.mirror_copy && existed && !self.options.mirror_dry_run {
+            let mut extraneous = Vec::new();
+            let mut options = self.options.clone();
+            options.mirror_dry_run = true;
+            let filter = Filter::new(&to, &options.include, &options.exclude);
+            let created = self.created.clone();
+            dir::remove_extraneous(
+                Some(from),
+                &to,
+                &filter,
+                false,
+                &created,
+                &options,
+                &mut |path| extraneous.push(to.join(path)),
+            )?;
+            for path in extraneous {
+                self.record(&path);
+                self.remove(&path);
+            }
+        }
+        Ok(())
+    }
+
+    fn move_dir(&mut self, from: &Path, to: &Path) -> Result<()> {
+        let is_remove = !(dir::may_keep_files(self.options) && self.exists(to));
+        let to = self.destination(from, to)?;
+
+        // See `dir::move_dir_by_rename`.
+        let keeps_symlinks = matches!(
+            self.options.symlinks,
+            SymlinkPolicy::Follow | SymlinkPolicy::Preserve
+        );
+        let keeps_special_files = self.options.special_files == SpecialFilePolicy::Recreate;
+        if !self.options.content_only
+            && !self.exists(&to)
+            && keeps_symlinks
+            && keeps_special_files
+            && !self.options.is_filtered()
+            && same_device(from, &to)
+        {
+            self.record_tree(from)?;
+            if self.options.copy_inside {
+                if let Some(parent) = to.parent() {
+                    self.create_all(parent);
+                }
+            }
+            self.operations.push(Operation::Rename {
+                from: from.to_path_buf(),
+                to: to.clone(),
+            });
+            self.removed.push(from.to_path_buf());
+            self.created.insert(to);
+            return Ok(());
+        }
+
+        // `depth` works only for copy operations.
+        let read_options = DirOptions {
+            depth: 0,
+            ..self.options.dir_options()
+        };
+        let dir_content = self.copy_content(from, &to, &read_options, true)?;
+        if is_remove {
+            if self.options.is_filtered() {
+                self.remove_moved(&dir_content)?;
+            } else {
+                self.remove(from);
+            }
+        }
+        Ok(())
+    }
+
+    // Plans the removal of the moved entries like `dir::remove_moved`. Directories which
+    // keep entries left out by the filter are not removed.
+    fn remove_moved(&mut self, dir_content: &DirContent) -> Result<()> {
+        let entries = dir_content.files.iter().chain(&dir_content.symlinks);
+        let entries: Vec<&Path> = entries
+            .chain(&dir_content.special_files)
+            .map(Path::new)
+            .collect();
+        for entry in &entries {
+            if !self
+                .removed
+                .iter()
+                .any(|removed| entry.starts_with(removed))
+            {
+                self.remove(entry);
+            }
+        }
+
+        let mut listed: HashSet<&Path> = entries.into_iter().collect();
+        // Children first.
+        for directory in dir_content.directories.iter().rev() {
+            let directory = Path::new(directory);
+            let mut emptied = true;
+            for entry in read_dir(directory)? {
+                emptied &= listed.contains(entry?.path().as_path());
+            }
+            if emptied {
+                self.remove(directory);
+                listed.insert(directory);
+            }
+        }
+        Ok(())
+    }
+
+    // Plans the content of `from` like the copy loop of `dir::copy` and `dir::move_dir`.
+    fn copy_content(
+        &mut self,
+        from: &Path,
+        to: &Path,
+        read_options: &DirOptions,
+        is_move: bool,
+    ) -> Result<DirContent> {
+        let dir_content = dir::get_dir_content2(from, read_options)?;
+        for directory in &dir_content.directories {
+            let directory = Path::new(directory);
+            let path = to.join(directory.strip_prefix(from)?);
+            self.record(directory);
+            self.record(&path);
+            if self.exists(&path) {
+                continue;
+            }
+            if self.options.copy_inside {
+                self.create_all(&path);
+            } else {
+                if let Some(parent) = path.parent() {
+                    if !parent.as_os_str().is_empty() && !self.exists(parent) {
+                        if let Some(msg) = parent.to_str() {
+                            let msg = format!("Path \"{}\" does not exist", msg);
+                            err!(&msg, ErrorKind::NotFound);
+                        }
+                        err!("Path does not exist", ErrorKind::NotFound);
+                    }
+                }
+                self.create_dir(&path);
+            }
+        }
+
+        for file in &dir_content.special_files {
+            let file = Path::new(file);
+            match self.options.special_files {
+                SpecialFilePolicy::Recreate => {}
+                SpecialFilePolicy::Skip => continue,
+                SpecialFilePolicy::Error => dir::special_file_error(file)?,
+            }
+            if dir::is_socket(&file.metadata()?) {
+                continue;
+            }
+            let relative = file.strip_prefix(from)?;
+            let file_options = self.options.file_options_at(relative);
+            self.copy_entry(file, &to.join(relative), 0, false, &file_options)?;
+        }
+
+        let mut hard_links = dir::find_hard_links(&dir_content.files, self.options)?;
+        let files = dir_content.files.iter().map(|file| (file, false));
+        let symlinks = dir_content.symlinks.iter().map(|link| (link, true));
+        for (name, is_symlink) in files.chain(symlinks) {
+            let file = Path::new(name);
+            let relative = file.strip_prefix(from)?;
+            let path = to.join(relative);
+            let file_options = self.options.file_options_at(relative);
+            if is_symlink {
+                let size = file.symlink_metadata()?.len();
+                self.copy_entry(file, &path, size, false, &file_options)?;
+            } else if hard_links.target(name).is_some() {
+                self.copy_entry(file, &path, 0, false, &file_options)?;
+            } else {
+                let written = if is_move {
+                    self.move_file(file, &path, &file_options)?
+                } else {
+                    self.copy_file(file, &path, &file_options)?
+                };
+                if written {
+                    hard_links.copied(name, &path);
+                }
+            }
+        }
+        Ok(dir_content)
+    }
+
+    // See `file::destination`.
+    fn file_destination(&self, to: &Path, options: &file::CopyOptions) -> PathBuf {
+        if options.effective_overwrite_policy() == OverwritePolicy::Rename {
+            file::free_path_by(to, |path| self.exists(path))
+        } else {
+            to.to_path_buf()
+        }
+    }
+
+    // Plans `file::copy`. Returns false if the destination is kept.
+    fn copy_file(&mut self, from: &Path, to: &Path, options: &file::CopyOptions) -> Result<bool> {
+        check_file(from)?;
+        let size = from.metadata()?.len();
+        self.copy_entry(from, to, size, true, options)
+    }
+
+    // Plans `file::move_file`. Returns false if the source is kept.
+    fn move_file(&mut self, from: &Path, to: &Path, options: &file::CopyOptions) -> Result<bool> {
+        check_file(from)?;
+        let to = &self.file_destination(to, options);
+        self.record(from);
+        self.record(to);
+        if !self.exists(to) && same_device(from, to) {
+            self.operations.push(Operation::Rename {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+            });
+            self.removed.push(from.to_path_buf());
+            self.created.insert(to.to_path_buf());
+            return Ok(true);
+        }
+        let size = from.metadata()?.len();
+        let moved = self.copy_entry(from, to, size, true, options)?;
+        if moved {
+            self.remove(from);
+        }
+        Ok(moved)
+    }
+
+    // Plans the copy of one entry following the overwrite policy. `follow` tells whether
+    // symlinks are followed when an existing destination is compared. Returns false if the
+    // destination is kept.
+    fn copy_entry(
+        &mut self,
+        from: &Path,
+        to: &Path,
+        size: u64,
+        follow: bool,
+        options: &file::CopyOptions,
+    ) -> Result<bool> {
+        let to = &self.file_destination(to, options);
+        self.record(from);
+        self.record(to);
+        let metadata = |path: &Path| {
+            if follow {
+                path.metadata()
+            } else {
+                path.symlink_metadata()
+            }
+        };
+        let to_metadata = match metadata(to) {
+            Ok(to_metadata) if self.exists(to) => to_metadata,
+            _ => {
+                self.operations.push(Operation::CopyFile {
+                    from: from.to_path_buf(),
+                    to: to.to_path_buf(),
+                    size,
+                });
+                return Ok(true);
+            }
+        };
+        let overwrite = file::should_overwrite(from, &metadata(from)?, to, &to_metadata, options)?;
+        if overwrite {
+            if let Some(backup) = file::backup_path(to, options)? {
+                self.record(&backup);
+                self.created.insert(backup.clone());
+                self.operations.push(Operation::Backup {
+                    path: to.to_path_buf(),
+                    backup,
+                });
+            }
+        }
+        self.operations.push(if overwrite {
+            Operation::Overwrite {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+                size,
+            }
+        } else {
+            Operation::Skip {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+            }
+        });
+        Ok(overwrite)
+    }
+}
+
+fn check_file(from: &Path) -> Result<()> {
+    if !from.exists() {
+        if let Some(msg) = from.to_str() {
+            let msg = format!("Path \"{}\" does not exist or you don't have access!", msg);
+            err!(&msg, ErrorKind::NotFound);
+        }
+        err!(
+            "Path does not exist or you don't have access!",
+            ErrorKind::NotFound
+        );
+    }
+    if !from.is_file() {
+        if let Some(msg) = from.to_str() {
+            let msg = format!("Path \"{}\" is not a file!", msg);
+            err!(&msg, ErrorKind::InvalidFile);
+        }
+        err!("Path is not a file!", ErrorKind::InvalidFile);
+    }
+    Ok(())
+}
+
+// Whether a rename of `from` to `to` stays on one file system. `to` may not exist yet.
+#[cfg(unix)]
+fn same_device(from: &Path, to: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let from = match from.symlink_metadata() {
+        Ok(metadata) => metadata.dev(),
+        Err(_) => return false,
+    };
+    to.ancestors()
+        .find_map(|path| {
+            let path = if path.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                path
+            };
+            path.metadata().ok()
+        })
+        .map(|metadata| metadata.dev() == from)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn same_device(_from: &Path, _to: &Path) -> bool {
+    true
+}
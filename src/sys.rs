@@ -0,0 +1,310 @@
+// Thin wrappers around platform calls which are not exposed by std.
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::{CString, OsStr, OsString};
+    use std::io;
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::path::Path;
+
+    const ERANGE: i32 = 34;
+
+    extern "C" {
+        fn listxattr(path: *const c_char, list: *mut c_char, size: usize) -> isize;
+        fn getxattr(
+            path: *const c_char,
+            name: *const c_char,
+            value: *mut c_void,
+            size: usize,
+        ) -> isize;
+        fn setxattr(
+            path: *const c_char,
+            name: *const c_char,
+            value: *const c_void,
+            size: usize,
+            flags: c_int,
+        ) -> c_int;
+    }
+
+    fn c_string(value: &OsStr) -> io::Result<CString> {
+        CString::new(value.as_bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+    }
+
+    // Calls `f` with a growing buffer until the value fits. `f` follows the
+    // convention of the *xattr calls: with a size of 0 it returns the needed size.
+    fn read_sized<F>(mut f: F) -> io::Result<Vec<u8>>
+    where
+        F: FnMut(*mut u8, usize) -> isize,
+    {
+        loop {
+            let size = f(std::ptr::null_mut(), 0);
+            if size < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut buf = vec![0u8; size as usize];
+            let read = f(buf.as_mut_ptr(), buf.len());
+            if read >= 0 {
+                buf.truncate(read as usize);
+                return Ok(buf);
+            }
+            let err = io::Error::last_os_error();
+            // The value grew between the two calls.
+            if err.raw_os_error() != Some(ERANGE) {
+                return Err(err);
+            }
+        }
+    }
+
+    pub fn list_xattrs(path: &Path) -> io::Result<Vec<OsString>> {
+        let path = c_string(path.as_os_str())?;
+        let list =
+            read_sized(|buf, size| unsafe { listxattr(path.as_ptr(), buf as *mut c_char, size) })?;
+        Ok(list
+            .split(|&byte| byte == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| OsString::from_vec(name.to_vec()))
+            .collect())
+    }
+
+    pub fn get_xattr(path: &Path, name: &OsStr) -> io::Result<Vec<u8>> {
+        let path = c_string(path.as_os_str())?;
+        let name = c_string(name)?;
+        read_sized(|buf, size| unsafe {
+            getxattr(path.as_ptr(), name.as_ptr(), buf as *mut c_void, size)
+        })
+    }
+
+    pub fn set_xattr(path: &Path, name: &OsStr, value: &[u8]) -> io::Result<()> {
+        let path = c_string(path.as_os_str())?;
+        let name = c_string(name)?;
+        let result = unsafe {
+            setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr() as *const c_void,
+                value.len(),
+                0,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) use self::linux::*;
+
+#[cfg(not(target_os = "linux"))]
+mod other {
+    use std::ffi::{OsStr, OsString};
+    use std::io;
+    use std::path::Path;
+
+    fn xattrs_unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "extended attributes are not supported on this platform",
+        )
+    }
+
+    pub fn list_xattrs(_path: &Path) -> io::Result<Vec<OsString>> {
+        Err(xattrs_unsupported())
+    }
+
+    pub fn get_xattr(_path: &Path, _name: &OsStr) -> io::Result<Vec<u8>> {
+        Err(xattrs_unsupported())
+    }
+
+    pub fn set_xattr(_path: &Path, _name: &OsStr, _value: &[u8]) -> io::Result<()> {
+        Err(xattrs_unsupported())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) use self::other::*;
+
+// Data ranges of sparse files. `lseek` takes a 64 bit offset only on 64 bit targets.
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+pub(crate) fn data_ranges(file: &std::fs::File) -> std::io::Result<Option<Vec<(u64, u64)>>> {
+    use std::io;
+    use std::os::raw::c_int;
+    use std::os::unix::io::AsRawFd;
+
+    const SEEK_DATA: c_int = 3;
+    const SEEK_HOLE: c_int = 4;
+    const ENXIO: i32 = 6;
+    const EINVAL: i32 = 22;
+
+    extern "C" {
+        fn lseek(fd: c_int, offset: i64, whence: c_int) -> i64;
+    }
+
+    let len = file.metadata()?.len();
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    while offset < len {
+        let data = unsafe { lseek(file.as_raw_fd(), offset as i64, SEEK_DATA) };
+        if data < 0 {
+            let err = io::Error::last_os_error();
+            return match err.raw_os_error() {
+                // No data after `offset`.
+                Some(ENXIO) => break,
+                // The file system does not know about holes.
+                Some(EINVAL) if ranges.is_empty() => Ok(None),
+                _ => Err(err),
+            };
+        }
+        let hole = unsafe { lseek(file.as_raw_fd(), data, SEEK_HOLE) };
+        if hole < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let end = (hole as u64).min(len);
+        ranges.push((data as u64, end));
+        offset = end;
+    }
+    Ok(Some(ranges))
+}
+
+#[cfg(not(all(target_os = "linux", target_pointer_width = "64")))]
+pub(crate) fn data_ranges(_file: &std::fs::File) -> std::io::Result<Option<Vec<(u64, u64)>>> {
+    Ok(None)
+}
+
+// Reflinks and kernel copies.
+#[cfg(target_os = "linux")]
+mod copy {
+    use std::fs::File;
+    use std::io;
+    use std::os::raw::{c_int, c_uint, c_ulong};
+    use std::os::unix::io::AsRawFd;
+
+    const FICLONE: c_ulong = 0x4004_9409;
+    const EPERM: i32 = 1;
+    const EXDEV: i32 = 18;
+    const EINVAL: i32 = 22;
+    const ENOSYS: i32 = 38;
+    const EOPNOTSUPP: i32 = 95;
+
+    extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+        #[link_name = "copy_file_range"]
+        fn sys_copy_file_range(
+            fd_in: c_int,
+            off_in: *mut i64,
+            fd_out: c_int,
+            off_out: *mut i64,
+            len: usize,
+            flags: c_uint,
+        ) -> isize;
+    }
+
+    pub fn reflink(file_from: &File, file_to: &File) -> io::Result<()> {
+        let result = unsafe { ioctl(file_to.as_raw_fd(), FICLONE, file_from.as_raw_fd()) };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Returns `None` if the kernel can not copy between these files.
+    pub fn copy_file_range(
+        file_from: &File,
+        file_to: &File,
+        offset: u64,
+        len: u64,
+    ) -> io::Result<Option<u64>> {
+        let mut off_in = offset as i64;
+        let mut off_out = offset as i64;
+        let result = unsafe {
+            sys_copy_file_range(
+                file_from.as_raw_fd(),
+                &mut off_in,
+                file_to.as_raw_fd(),
+                &mut off_out,
+                len as usize,
+                0,
+            )
+        };
+        if result < 0 {
+            let err = io::Error::last_os_error();
+            return match err.raw_os_error() {
+                Some(EPERM) | Some(EXDEV) | Some(EINVAL) | Some(ENOSYS) | Some(EOPNOTSUPP) => {
+                    Ok(None)
+                }
+                _ => Err(err),
+            };
+        }
+        Ok(Some(result as u64))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) use self::copy::*;
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn reflink(_file_from: &std::fs::File, _file_to: &std::fs::File) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "reflinks are not supported on this platform",
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn copy_file_range(
+    _file_from: &std::fs::File,
+    _file_to: &std::fs::File,
+    _offset: u64,
+    _len: u64,
+) -> std::io::Result<Option<u64>> {
+    Ok(None)
+}
+
+// Creates a FIFO or device node. `mode` includes the file type bits.
+#[cfg(target_os = "linux")]
+pub(crate) fn mknod(path: &std::path::Path, mode: u32, dev: u64) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::raw::{c_char, c_int};
+    use std::os::unix::ffi::OsStrExt;
+
+    extern "C" {
+        #[link_name = "mknod"]
+        fn sys_mknod(path: *const c_char, mode: u32, dev: u64) -> c_int;
+    }
+
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    if unsafe { sys_mknod(path.as_ptr(), mode, dev) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub(crate) fn mknod(_path: &std::path::Path, _mode: u32, _dev: u64) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "special files can not be created on this platform",
+    ))
+}
+
+// Flushes the entries of a directory, like created or renamed files, to the disk.
+#[cfg(unix)]
+pub(crate) fn sync_dir(path: &std::path::Path) -> std::io::Result<()> {
+    let path = if path.as_os_str().is_empty() {
+        std::path::Path::new(".")
+    } else {
+        path
+    };
+    std::fs::File::open(path)?.sync_all()
+}
+
+// Directories can not be opened as files, and their entries are flushed with the files.
+#[cfg(not(unix))]
+pub(crate) fn sync_dir(_path: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
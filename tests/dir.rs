@@ -24,7 +24,7 @@ where
     Q: AsRef<Path>,
 {
     let mut path_to = path_to.as_ref().to_path_buf();
-    match path_from.as_ref().components().last() {
+    match path_from.as_ref().components().next_back() {
         None => panic!("Invalid folder from"),
         Some(dir_name) => {
             path_to.push(dir_name.as_os_str());
@@ -47,9 +47,7 @@ where
                 None => panic!("No file name"),
                 Some(file_name) => {
                     path_to.push(file_name);
-                    if !path_to.exists() {
-                        return false;
-                    } else if !files_eq(&path, path_to.clone()) {
+                    if !path_to.exists() || !files_eq(&path, path_to.clone()) {
                         return false;
                     }
                 }
@@ -86,7 +84,7 @@ fn create_file_symlink<P: AsRef<Path>, Q: AsRef<Path>>(
     std::os::windows::fs::symlink_file(original.as_ref(), link.as_ref())
 }
 
-const TEST_FOLDER: &'static str = "./tests/temp/dir";
+const TEST_FOLDER: &str = "./tests/temp/dir";
 
 #[test]
 fn it_create_all_work() {
@@ -132,7 +130,7 @@ fn it_create_exist_folder() {
     file_path.push("test.txt");
     assert!(!file_path.exists());
     let content = "test_content";
-    fs_extra::file::write_all(&file_path, &content).unwrap();
+    fs_extra::file::write_all(&file_path, content).unwrap();
     assert!(file_path.exists());
 
     match create(&test_dir, false) {
@@ -186,7 +184,7 @@ fn it_create_all_exist_folder() {
     file_path.push("test.txt");
     assert!(!file_path.exists());
     let content = "test_content";
-    fs_extra::file::write_all(&file_path, &content).unwrap();
+    fs_extra::file::write_all(&file_path, content).unwrap();
     assert!(file_path.exists());
 
     create_all(&test_dir, false).unwrap();
@@ -242,7 +240,7 @@ fn it_remove_not_exist() {
         Ok(_) => {
             assert!(!test_dir.exists());
         }
-        Err(err) => panic!(err.to_string()),
+        Err(err) => panic!("{}", err.to_string()),
     }
 }
 
@@ -253,7 +251,7 @@ fn it_copy_work() {
     path_from.push("it_copy_work");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -263,7 +261,7 @@ fn it_copy_work() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -272,7 +270,7 @@ fn it_copy_work() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let options = CopyOptions::new();
@@ -344,7 +342,7 @@ fn it_copy_source_not_exist() {
                 assert_eq!(wrong_path, err.to_string());
             }
             _ => {
-                panic!(format!("wrong error {}", err.to_string()));
+                panic!("wrong error {}", err);
             }
         },
         Ok(_) => {
@@ -360,7 +358,7 @@ fn it_copy_exist_overwrite() {
     path_from.push("it_copy_exist_overwrite");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
     let same_file = "test.txt";
 
     create_all(&path_from, true).unwrap();
@@ -371,7 +369,7 @@ fn it_copy_exist_overwrite() {
     let mut file1_path = path_from.clone();
     file1_path.push(same_file);
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -380,11 +378,11 @@ fn it_copy_exist_overwrite() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut exist_path = path_to.clone();
-    exist_path.push(&test_name);
+    exist_path.push(test_name);
     create(&exist_path, true).unwrap();
     assert!(exist_path.exists());
     exist_path.push(same_file);
@@ -421,11 +419,11 @@ fn it_copy_exist_not_overwrite() {
     let mut file1_path = path_from.clone();
     file1_path.push(same_file);
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut exist_path = path_to.clone();
-    exist_path.push(&test_name);
+    exist_path.push(test_name);
     create(&exist_path, true).unwrap();
     assert!(exist_path.exists());
     exist_path.push(same_file);
@@ -442,7 +440,7 @@ fn it_copy_exist_not_overwrite() {
                 assert_eq!(wrong_path, err.to_string());
             }
             _ => {
-                panic!(format!("wrong error {}", err.to_string()));
+                panic!("wrong error {}", err);
             }
         },
         Ok(_) => {
@@ -458,7 +456,7 @@ fn it_copy_exist_skip_exist() {
     path_from.push("it_copy_exist_skip_exist");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
     let same_file = "test.txt";
 
     create_all(&path_from, true).unwrap();
@@ -469,7 +467,7 @@ fn it_copy_exist_skip_exist() {
     let mut file1_path = path_from.clone();
     file1_path.push(same_file);
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -478,11 +476,11 @@ fn it_copy_exist_skip_exist() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut exist_path = path_to.clone();
-    exist_path.push(&test_name);
+    exist_path.push(test_name);
     create(&exist_path, true).unwrap();
     assert!(exist_path.exists());
     exist_path.push(same_file);
@@ -513,7 +511,7 @@ fn it_copy_exist_overwrite_and_skip_exist() {
     path_from.push("it_copy_exist_overwrite_and_skip_exist");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
     let same_file = "test.txt";
 
     create_all(&path_from, true).unwrap();
@@ -524,7 +522,7 @@ fn it_copy_exist_overwrite_and_skip_exist() {
     let mut file1_path = path_from.clone();
     file1_path.push(same_file);
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -533,11 +531,11 @@ fn it_copy_exist_overwrite_and_skip_exist() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut exist_path = path_to.clone();
-    exist_path.push(&test_name);
+    exist_path.push(test_name);
     create(&exist_path, true).unwrap();
     assert!(exist_path.exists());
     exist_path.push(same_file);
@@ -803,7 +801,7 @@ fn it_copy_progress_work() {
     path_from.push("it_copy_progress_work");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -813,7 +811,7 @@ fn it_copy_progress_work() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -822,7 +820,7 @@ fn it_copy_progress_work() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -864,7 +862,7 @@ fn it_copy_progress_work() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -917,7 +915,7 @@ fn it_copy_with_progress_work_dif_buf_size() {
     path_from.push("it_copy_with_progress_work_dif_buf_size");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -927,7 +925,7 @@ fn it_copy_with_progress_work_dif_buf_size() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -936,7 +934,7 @@ fn it_copy_with_progress_work_dif_buf_size() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -989,7 +987,7 @@ fn it_copy_with_progress_work_dif_buf_size() {
 
         match result {
             Ok(_) => {}
-            Err(err) => panic!(err),
+            Err(err) => std::panic::resume_unwind(err),
         }
     })
     .join();
@@ -1011,7 +1009,7 @@ fn it_copy_with_progress_work_dif_buf_size() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 #[test]
@@ -1045,7 +1043,7 @@ fn it_copy_with_progress_source_not_exist() {
                     assert_eq!(wrong_path, err.to_string());
                 }
                 _ => {
-                    panic!(format!("wrong error {}", err.to_string()));
+                    panic!("wrong error {}", err);
                 }
             },
             Ok(_) => {
@@ -1056,7 +1054,7 @@ fn it_copy_with_progress_source_not_exist() {
     .join();
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
     match rx.recv() {
@@ -1072,7 +1070,7 @@ fn it_copy_with_progress_exist_overwrite() {
     path_from.push("it_copy_with_progress_exist_overwrite");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -1082,7 +1080,7 @@ fn it_copy_with_progress_exist_overwrite() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -1091,7 +1089,7 @@ fn it_copy_with_progress_exist_overwrite() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -1117,12 +1115,11 @@ fn it_copy_with_progress_exist_overwrite() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -1133,7 +1130,7 @@ fn it_copy_with_progress_exist_not_overwrite() {
     path_from.push("it_copy_with_progress_exist_not_overwrite");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -1143,7 +1140,7 @@ fn it_copy_with_progress_exist_not_overwrite() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -1152,7 +1149,7 @@ fn it_copy_with_progress_exist_not_overwrite() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -1183,7 +1180,7 @@ fn it_copy_with_progress_exist_skip_exist() {
     path_from.push("it_copy_with_progress_exist_skip_exist");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -1193,7 +1190,7 @@ fn it_copy_with_progress_exist_skip_exist() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -1202,7 +1199,7 @@ fn it_copy_with_progress_exist_skip_exist() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -1227,7 +1224,7 @@ fn it_copy_with_progress_exist_skip_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
     match rx.recv() {
@@ -1243,7 +1240,7 @@ fn it_copy_with_progress_exist_overwrite_and_skip_exist() {
     path_from.push("it_copy_with_progress_exist_overwrite_and_skip_exist");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -1253,7 +1250,7 @@ fn it_copy_with_progress_exist_overwrite_and_skip_exist() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -1262,7 +1259,7 @@ fn it_copy_with_progress_exist_overwrite_and_skip_exist() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -1289,7 +1286,7 @@ fn it_copy_with_progress_exist_overwrite_and_skip_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.recv().unwrap();
 }
@@ -1390,12 +1387,11 @@ fn it_copy_with_progress_using_first_levels() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -1498,12 +1494,11 @@ fn it_copy_with_progress_using_four_levels() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 #[test]
@@ -1579,12 +1574,11 @@ fn it_copy_with_progress_content_only_option() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -1715,7 +1709,7 @@ fn it_copy_inside_work_target_dir_exist_with_source_dir_exist() {
                 assert_eq!(1, 1);
             }
             _ => {
-                panic!(format!("wrong error {}", err.to_string()));
+                panic!("wrong error {}", err);
             }
         },
         Ok(_) => {
@@ -1749,7 +1743,7 @@ where
         let entry = entry.unwrap();
         let path = entry.path();
         if path.is_dir() {
-            match path.components().last() {
+            match path.components().next_back() {
                 None => panic!("Invalid folder from"),
                 Some(dir_name) => {
                     let mut target_dir = path_to.to_path_buf();
@@ -1765,9 +1759,7 @@ where
                 None => panic!("No file name"),
                 Some(file_name) => {
                     target_file.push(file_name);
-                    if !target_file.exists() {
-                        return false;
-                    } else if !files_eq(&path, target_file.clone()) {
+                    if !target_file.exists() || !files_eq(&path, target_file.clone()) {
                         return false;
                     }
                 }
@@ -1785,7 +1777,7 @@ fn it_move_work() {
     path_from.push("it_move_work");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -1795,7 +1787,7 @@ fn it_move_work() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -1804,7 +1796,7 @@ fn it_move_work() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let options = CopyOptions::new();
@@ -1815,6 +1807,101 @@ fn it_move_work() {
     assert!(!path_from.exists());
 }
 
+#[cfg(unix)]
+#[test]
+fn it_move_rename_keep_inode() {
+    use std::os::unix::fs::MetadataExt;
+
+    let path_from = Path::new(TEST_FOLDER).join("it_move_rename_keep_inode");
+    let path_to = path_from.join("out");
+    let path_from = path_from.join("sub");
+    let file1 = path_from.join("test1.txt");
+
+    create_all(&path_from, true).unwrap();
+    create_all(&path_to, true).unwrap();
+    fs_extra::file::write_all(&file1, "content1").unwrap();
+    let ino = fs::metadata(&file1).unwrap().ino();
+
+    let options = CopyOptions::new();
+    let result = move_dir(&path_from, &path_to, &options).unwrap();
+
+    assert_eq!(8, result);
+    assert!(!path_from.exists());
+    let moved = path_to.join("sub").join("test1.txt");
+    assert_eq!(ino, fs::metadata(&moved).unwrap().ino());
+}
+
+#[test]
+fn it_move_with_progress_rename_work() {
+    let path_from = Path::new(TEST_FOLDER).join("it_move_with_progress_rename_work");
+    let path_to = path_from.join("out");
+    let path_from = path_from.join("sub");
+    let sub = path_from.join("sub");
+
+    create_all(&sub, true).unwrap();
+    create_all(&path_to, true).unwrap();
+    fs_extra::file::write_all(path_from.join("test1.txt"), "content1").unwrap();
+    fs_extra::file::write_all(sub.join("test2.txt"), "content22").unwrap();
+
+    let mut options = CopyOptions::new();
+    options.buffer_size = 1;
+    let mut infos = Vec::new();
+    let result = move_dir_with_progress(&path_from, &path_to, &options, |info| {
+        infos.push(info);
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+
+    assert_eq!(17, result);
+    assert_eq!(1, infos.len());
+    assert_eq!(17, infos[0].copied_bytes);
+    assert_eq!(17, infos[0].total_bytes);
+    assert!(!path_from.exists());
+    assert!(path_to.join("sub").join("sub").join("test2.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_move_with_progress_cross_device_work() {
+    use std::os::unix::fs::MetadataExt;
+
+    let path_from = Path::new(TEST_FOLDER)
+        .join("it_move_with_progress_cross_device_work")
+        .join("sub");
+    let sub = path_from.join("sub");
+    create_all(&sub, true).unwrap();
+    // A move onto another file system falls back to copying the content and removing the source.
+    let other_fs = Path::new("/dev/shm");
+    match other_fs.metadata() {
+        Ok(meta) if meta.dev() != path_from.metadata().unwrap().dev() => {}
+        _ => return,
+    }
+    let path_to = other_fs.join(format!("fs_extra_{}", std::process::id()));
+    create_all(&path_to, true).unwrap();
+    fs_extra::file::write_all(path_from.join("test1.txt"), "content1").unwrap();
+    fs_extra::file::write_all(sub.join("test2.txt"), "content22").unwrap();
+
+    let mut options = CopyOptions::new();
+    options.buffer_size = 4;
+    let mut infos = Vec::new();
+    let result = move_dir_with_progress(&path_from, &path_to, &options, |info| {
+        infos.push(info);
+        TransitProcessResult::ContinueOrAbort
+    });
+    let moved = path_to.join("sub").join("sub").join("test2.txt").exists();
+    let _ = remove(&path_to);
+
+    assert_eq!(17, result.unwrap());
+    assert!(moved);
+    assert!(!path_from.exists());
+    let copied: Vec<u64> = infos.iter().map(|info| info.copied_bytes).collect();
+    assert_eq!(5, copied.len());
+    assert!(copied.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(Some(&17), copied.last());
+    let names: HashSet<&str> = infos.iter().map(|info| info.file_name.as_str()).collect();
+    assert_eq!(2, names.len());
+}
+
 #[test]
 fn it_move_not_folder() {
     let mut path_from = PathBuf::from(TEST_FOLDER);
@@ -1872,7 +1959,7 @@ fn it_move_source_not_exist() {
                 assert_eq!(wrong_path, err.to_string());
             }
             _ => {
-                panic!(format!("wrong error {}", err.to_string()));
+                panic!("wrong error {}", err);
             }
         },
         Ok(_) => {
@@ -1888,7 +1975,7 @@ fn it_move_exist_overwrite() {
     path_from.push("it_move_exist_overwrite");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
     let same_file = "test.txt";
 
     create_all(&path_from, true).unwrap();
@@ -1899,7 +1986,7 @@ fn it_move_exist_overwrite() {
     let mut file1_path = path_from.clone();
     file1_path.push(same_file);
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -1908,11 +1995,11 @@ fn it_move_exist_overwrite() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut exist_path = path_to.clone();
-    exist_path.push(&test_name);
+    exist_path.push(test_name);
     create(&exist_path, true).unwrap();
     assert!(exist_path.exists());
     exist_path.push(same_file);
@@ -1948,11 +2035,11 @@ fn it_move_exist_not_overwrite() {
     let mut file1_path = path_from.clone();
     file1_path.push(same_file);
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut exist_path = path_to.clone();
-    exist_path.push(&test_name);
+    exist_path.push(test_name);
     create(&exist_path, true).unwrap();
     assert!(exist_path.exists());
     exist_path.push(same_file);
@@ -1969,7 +2056,7 @@ fn it_move_exist_not_overwrite() {
                 assert_eq!(wrong_path, err.to_string());
             }
             _ => {
-                panic!(format!("wrong error {}", err.to_string()));
+                panic!("wrong error {}", err);
             }
         },
         Ok(_) => {
@@ -1985,7 +2072,7 @@ fn it_move_exist_skip_exist() {
     path_from.push("it_move_exist_skip_exist");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
     let same_file = "test.txt";
 
     create_all(&path_from, true).unwrap();
@@ -1996,7 +2083,7 @@ fn it_move_exist_skip_exist() {
     let mut file1_path = path_from.clone();
     file1_path.push(same_file);
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -2005,11 +2092,11 @@ fn it_move_exist_skip_exist() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut exist_path = path_to.clone();
-    exist_path.push(&test_name);
+    exist_path.push(test_name);
     create(&exist_path, true).unwrap();
     assert!(exist_path.exists());
     exist_path.push(same_file);
@@ -2038,7 +2125,7 @@ fn it_move_exist_overwrite_and_skip_exist() {
     path_from.push("it_move_exist_overwrite_and_skip_exist");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
     let same_file = "test.txt";
 
     create_all(&path_from, true).unwrap();
@@ -2049,7 +2136,7 @@ fn it_move_exist_overwrite_and_skip_exist() {
     let mut file1_path = path_from.clone();
     file1_path.push(same_file);
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -2058,11 +2145,11 @@ fn it_move_exist_overwrite_and_skip_exist() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut exist_path = path_to.clone();
-    exist_path.push(&test_name);
+    exist_path.push(test_name);
     create(&exist_path, true).unwrap();
     assert!(exist_path.exists());
     exist_path.push(same_file);
@@ -2218,7 +2305,7 @@ fn it_move_inside_work_target_dir_exist_with_source_dir_exist() {
                 assert_eq!(1, 1);
             }
             _ => {
-                panic!(format!("wrong error {}", err.to_string()));
+                panic!("wrong error {}", err);
             }
         },
         Ok(_) => {
@@ -2307,7 +2394,7 @@ fn it_move_progress_work() {
     path_from.push("it_move_progress_work");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -2317,7 +2404,7 @@ fn it_move_progress_work() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -2326,7 +2413,7 @@ fn it_move_progress_work() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -2355,6 +2442,10 @@ fn it_move_progress_work() {
                 } else if process_info.file_name == "test1.txt" {
                     assert_eq!(7, process_info.file_total_bytes);
                     assert_eq!(get_dir_size() * 2 + 15, process_info.total_bytes);
+                } else if process_info.file_name.is_empty() {
+                    // The whole directory was moved with a single rename.
+                    assert_eq!(15, process_info.copied_bytes);
+                    assert_eq!(15, process_info.total_bytes);
                 } else {
                     panic!("Unknow file name!");
                 }
@@ -2368,7 +2459,7 @@ fn it_move_progress_work() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -2422,17 +2513,19 @@ fn it_move_with_progress_work_dif_buf_size() {
     path_from.push("it_move_with_progress_work_dif_buf_size");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
-    create_all(&path_to, true).unwrap();
+    // An existing destination has to be merged, so the content is moved file
+    // by file instead of renaming the whole directory.
+    create_all(path_to.join(test_name), true).unwrap();
     assert!(path_to.exists());
 
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content1";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -2441,7 +2534,7 @@ fn it_move_with_progress_work_dif_buf_size() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -2465,7 +2558,7 @@ fn it_move_with_progress_work_dif_buf_size() {
         let mut file1_path = path_from.clone();
         file1_path.push("test1.txt");
         let content1 = "content1";
-        fs_extra::file::write_all(&file1_path, &content1).unwrap();
+        fs_extra::file::write_all(&file1_path, content1).unwrap();
         assert!(file1_path.exists());
 
         let mut sub_dir_path = path_from.clone();
@@ -2474,7 +2567,7 @@ fn it_move_with_progress_work_dif_buf_size() {
         let mut file2_path = sub_dir_path.clone();
         file2_path.push("test2.txt");
         let content2 = "content2";
-        fs_extra::file::write_all(&file2_path, &content2).unwrap();
+        fs_extra::file::write_all(&file2_path, content2).unwrap();
         assert!(file2_path.exists());
 
         let mut options = CopyOptions::new();
@@ -2493,46 +2586,33 @@ fn it_move_with_progress_work_dif_buf_size() {
             assert!(!path_from.exists());
         })
         .join();
-        for i in 1..5 {
+        for i in 1..3 {
             let process_info: TransitProcess = rx.recv().unwrap();
-            assert_eq!(i * 2, process_info.file_bytes_copied);
-            assert_eq!(i * 2, process_info.copied_bytes);
-            assert_eq!(8, process_info.file_total_bytes);
-            assert_eq!(get_dir_size() * 2 + 16, process_info.total_bytes);
-        }
-        for i in 1..5 {
-            let process_info: TransitProcess = rx.recv().unwrap();
-            assert_eq!(i * 2 + 8, process_info.copied_bytes);
-            assert_eq!(i * 2, process_info.file_bytes_copied);
+            assert_eq!(8, process_info.file_bytes_copied);
+            assert_eq!(i * 8, process_info.copied_bytes);
             assert_eq!(8, process_info.file_total_bytes);
             assert_eq!(get_dir_size() * 2 + 16, process_info.total_bytes);
         }
 
         match result {
             Ok(_) => {}
-            Err(err) => panic!(err),
+            Err(err) => std::panic::resume_unwind(err),
         }
     })
     .join();
 
-    for i in 1..9 {
+    // Files are renamed into place, so each file reports its progress only once.
+    for i in 1..3 {
         let process_info: TransitProcess = rx.recv().unwrap();
-        assert_eq!(i, process_info.file_bytes_copied);
-        assert_eq!(i, process_info.copied_bytes);
-        assert_eq!(8, process_info.file_total_bytes);
-        assert_eq!(get_dir_size() * 2 + 16, process_info.total_bytes);
-    }
-    for i in 1..9 {
-        let process_info: TransitProcess = rx.recv().unwrap();
-        assert_eq!(i + 8, process_info.copied_bytes);
-        assert_eq!(i, process_info.file_bytes_copied);
+        assert_eq!(8, process_info.file_bytes_copied);
+        assert_eq!(i * 8, process_info.copied_bytes);
         assert_eq!(8, process_info.file_total_bytes);
         assert_eq!(get_dir_size() * 2 + 16, process_info.total_bytes);
     }
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 #[test]
@@ -2566,7 +2646,7 @@ fn it_move_with_progress_source_not_exist() {
                     assert_eq!(wrong_path, err.to_string());
                 }
                 _ => {
-                    panic!(format!("wrong error {}", err.to_string()));
+                    panic!("wrong error {}", err);
                 }
             },
             Ok(_) => {
@@ -2577,7 +2657,7 @@ fn it_move_with_progress_source_not_exist() {
     .join();
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
     match rx.recv() {
@@ -2593,7 +2673,7 @@ fn it_move_with_progress_exist_overwrite() {
     path_from.push("it_move_with_progress_exist_overwrite");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -2603,7 +2683,7 @@ fn it_move_with_progress_exist_overwrite() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -2612,7 +2692,7 @@ fn it_move_with_progress_exist_overwrite() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -2638,7 +2718,7 @@ fn it_move_with_progress_exist_overwrite() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.recv().unwrap();
 }
@@ -2650,7 +2730,7 @@ fn it_move_with_progress_exist_not_overwrite() {
     path_from.push("it_move_with_progress_exist_not_overwrite");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -2660,7 +2740,7 @@ fn it_move_with_progress_exist_not_overwrite() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -2669,7 +2749,7 @@ fn it_move_with_progress_exist_not_overwrite() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -2696,14 +2776,11 @@ fn it_move_with_progress_exist_not_overwrite() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => {
-            panic!("Error not should be!");
-        }
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Error not should be!");
     }
 }
 
@@ -2714,7 +2791,7 @@ fn it_move_with_progress_exist_skip_exist() {
     path_from.push("it_move_with_progress_exist_skip_exist");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -2724,7 +2801,7 @@ fn it_move_with_progress_exist_skip_exist() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -2733,7 +2810,7 @@ fn it_move_with_progress_exist_skip_exist() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -2759,7 +2836,7 @@ fn it_move_with_progress_exist_skip_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
     match rx.recv() {
@@ -2775,7 +2852,7 @@ fn it_move_with_progress_exist_overwrite_and_skip_exist() {
     path_from.push("it_move_with_progress_exist_overwrite_and_skip_exist");
     let mut path_to = path_from.clone();
     path_to.push("out");
-    path_from.push(&test_name);
+    path_from.push(test_name);
 
     create_all(&path_from, true).unwrap();
     assert!(path_from.exists());
@@ -2785,7 +2862,7 @@ fn it_move_with_progress_exist_overwrite_and_skip_exist() {
     let mut file1_path = path_from.clone();
     file1_path.push("test1.txt");
     let content1 = "content";
-    fs_extra::file::write_all(&file1_path, &content1).unwrap();
+    fs_extra::file::write_all(&file1_path, content1).unwrap();
     assert!(file1_path.exists());
 
     let mut sub_dir_path = path_from.clone();
@@ -2794,7 +2871,7 @@ fn it_move_with_progress_exist_overwrite_and_skip_exist() {
     let mut file2_path = sub_dir_path.clone();
     file2_path.push("test2.txt");
     let content2 = "content2";
-    fs_extra::file::write_all(&file2_path, &content2).unwrap();
+    fs_extra::file::write_all(&file2_path, content2).unwrap();
     assert!(file2_path.exists());
 
     let mut options = CopyOptions::new();
@@ -2821,7 +2898,7 @@ fn it_move_with_progress_exist_overwrite_and_skip_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.recv().unwrap();
 }
@@ -2999,19 +3076,21 @@ fn it_get_dir_content_many_levels() {
     assert_eq!(5, result.files.len());
     assert_eq!(5, result.directories.len());
 
-    let mut directories = Vec::new();
-    directories.push(file1.parent().unwrap().to_str().unwrap().to_string());
-    directories.push(file2.parent().unwrap().to_str().unwrap().to_string());
-    directories.push(file3.parent().unwrap().to_str().unwrap().to_string());
-    directories.push(file4.parent().unwrap().to_str().unwrap().to_string());
-    directories.push(file5.parent().unwrap().to_str().unwrap().to_string());
-
-    let mut files = Vec::new();
-    files.push(file1.to_str().unwrap().to_string());
-    files.push(file2.to_str().unwrap().to_string());
-    files.push(file3.to_str().unwrap().to_string());
-    files.push(file4.to_str().unwrap().to_string());
-    files.push(file5.to_str().unwrap().to_string());
+    let directories = [
+        file1.parent().unwrap().to_str().unwrap().to_string(),
+        file2.parent().unwrap().to_str().unwrap().to_string(),
+        file3.parent().unwrap().to_str().unwrap().to_string(),
+        file4.parent().unwrap().to_str().unwrap().to_string(),
+        file5.parent().unwrap().to_str().unwrap().to_string(),
+    ];
+
+    let files = [
+        file1.to_str().unwrap().to_string(),
+        file2.to_str().unwrap().to_string(),
+        file3.to_str().unwrap().to_string(),
+        file4.to_str().unwrap().to_string(),
+        file5.to_str().unwrap().to_string(),
+    ];
 
     let mut files_correct = true;
     for file in result.files {
@@ -3038,7 +3117,7 @@ fn it_get_dir_content_many_levels() {
     assert_eq!(2, result.directories.len());
     files_correct = true;
     for file in &result.files {
-        if !files.contains(&file) {
+        if !files.contains(file) {
             files_correct = false;
         }
     }
@@ -3047,7 +3126,7 @@ fn it_get_dir_content_many_levels() {
 
     directories_correct = true;
     for dir in &result.directories {
-        if !directories.contains(&dir) {
+        if !directories.contains(dir) {
             directories_correct = false;
         }
     }
@@ -3068,7 +3147,7 @@ fn it_get_dir_content_many_levels() {
     assert_eq!(5, result.directories.len());
     files_correct = true;
     for file in &result.files {
-        if !files.contains(&file) {
+        if !files.contains(file) {
             files_correct = false;
         }
     }
@@ -3080,7 +3159,7 @@ fn it_get_dir_content_many_levels() {
 
     directories_correct = true;
     for dir in &result.directories {
-        if !directories.contains(&dir) {
+        if !directories.contains(dir) {
             directories_correct = false;
         }
     }
@@ -3159,70 +3238,50 @@ fn it_details_item_dir() {
     assert_eq!(10, item.len());
 
     let mut fields = 0;
-    if let Some(name) = item.get(&DirEntryAttr::Name) {
-        if let &DirEntryValue::String(ref name) = name {
-            assert_eq!("it_details_item_dir", name);
-            fields += 1;
-        }
-    }
-    if let Some(ext) = item.get(&DirEntryAttr::Ext) {
-        if let &DirEntryValue::String(ref ext) = ext {
-            assert_eq!("", ext);
-            fields += 1;
-        }
+    if let Some(DirEntryValue::String(name)) = item.get(&DirEntryAttr::Name) {
+        assert_eq!("it_details_item_dir", name);
+        fields += 1;
     }
-    if let Some(fname) = item.get(&DirEntryAttr::FullName) {
-        if let &DirEntryValue::String(ref fname) = fname {
-            assert_eq!("it_details_item_dir", fname);
-            fields += 1;
-        }
+    if let Some(DirEntryValue::String(ext)) = item.get(&DirEntryAttr::Ext) {
+        assert_eq!("", ext);
+        fields += 1;
     }
-    if let Some(path) = item.get(&DirEntryAttr::Path) {
-        if let &DirEntryValue::String(ref path) = path {
-            if !path.is_empty() {
-                fields += 1;
-            }
-        }
+    if let Some(DirEntryValue::String(fname)) = item.get(&DirEntryAttr::FullName) {
+        assert_eq!("it_details_item_dir", fname);
+        fields += 1;
     }
-    if let Some(path) = item.get(&DirEntryAttr::DosPath) {
-        if let &DirEntryValue::String(ref path) = path {
-            if !path.is_empty() {
-                fields += 1;
-            }
-        }
-    }
-    if let Some(size) = item.get(&DirEntryAttr::Size) {
-        if let &DirEntryValue::U64(size) = size {
-            assert_eq!(0, size);
+    if let Some(DirEntryValue::String(path)) = item.get(&DirEntryAttr::Path) {
+        if !path.is_empty() {
             fields += 1;
         }
     }
-    if let Some(is_dir) = item.get(&DirEntryAttr::IsDir) {
-        if let &DirEntryValue::Boolean(is_dir) = is_dir {
-            assert_eq!(true, is_dir);
+    if let Some(DirEntryValue::String(path)) = item.get(&DirEntryAttr::DosPath) {
+        if !path.is_empty() {
             fields += 1;
         }
     }
+    if let Some(&DirEntryValue::U64(size)) = item.get(&DirEntryAttr::Size) {
+        assert_eq!(0, size);
+        fields += 1;
+    }
+    if let Some(&DirEntryValue::Boolean(is_dir)) = item.get(&DirEntryAttr::IsDir) {
+        assert!(is_dir);
+        fields += 1;
+    }
 
-    if let Some(is_file) = item.get(&DirEntryAttr::IsFile) {
-        if let &DirEntryValue::Boolean(is_file) = is_file {
-            assert_eq!(false, is_file);
-            fields += 1;
-        }
+    if let Some(&DirEntryValue::Boolean(is_file)) = item.get(&DirEntryAttr::IsFile) {
+        assert!(!is_file);
+        fields += 1;
     }
 
-    if let Some(modified) = item.get(&DirEntryAttr::Modified) {
-        if let &DirEntryValue::SystemTime(modified) = modified {
-            if modified.elapsed().unwrap().as_secs() == 0 {
-                fields += 1;
-            }
+    if let Some(&DirEntryValue::SystemTime(modified)) = item.get(&DirEntryAttr::Modified) {
+        if modified.elapsed().unwrap().as_secs() == 0 {
+            fields += 1;
         }
     }
-    if let Some(accessed) = item.get(&DirEntryAttr::Accessed) {
-        if let &DirEntryValue::SystemTime(accessed) = accessed {
-            if accessed.elapsed().unwrap().as_secs() == 0 {
-                fields += 1;
-            }
+    if let Some(&DirEntryValue::SystemTime(accessed)) = item.get(&DirEntryAttr::Accessed) {
+        if accessed.elapsed().unwrap().as_secs() == 0 {
+            fields += 1;
         }
     }
 
@@ -3252,76 +3311,54 @@ fn it_details_file_item() {
     assert_eq!(11, item.len());
 
     let mut fields = 0;
-    if let Some(name) = item.get(&DirEntryAttr::Name) {
-        if let &DirEntryValue::String(ref name) = name {
-            assert_eq!("file", name);
-            fields += 1;
-        }
+    if let Some(DirEntryValue::String(name)) = item.get(&DirEntryAttr::Name) {
+        assert_eq!("file", name);
+        fields += 1;
     }
-    if let Some(ext) = item.get(&DirEntryAttr::Ext) {
-        if let &DirEntryValue::String(ref ext) = ext {
-            assert_eq!("txt", ext);
-            fields += 1;
-        }
+    if let Some(DirEntryValue::String(ext)) = item.get(&DirEntryAttr::Ext) {
+        assert_eq!("txt", ext);
+        fields += 1;
     }
-    if let Some(fname) = item.get(&DirEntryAttr::FullName) {
-        if let &DirEntryValue::String(ref fname) = fname {
-            assert_eq!("file.txt", fname);
-            fields += 1;
-        }
+    if let Some(DirEntryValue::String(fname)) = item.get(&DirEntryAttr::FullName) {
+        assert_eq!("file.txt", fname);
+        fields += 1;
     }
-    if let Some(path) = item.get(&DirEntryAttr::Path) {
-        if let &DirEntryValue::String(ref path) = path {
-            if !path.is_empty() {
-                fields += 1;
-            }
-        }
-    }
-    if let Some(path) = item.get(&DirEntryAttr::DosPath) {
-        if let &DirEntryValue::String(ref path) = path {
-            if !path.is_empty() {
-                fields += 1;
-            }
-        }
-    }
-    if let Some(size) = item.get(&DirEntryAttr::Size) {
-        if let &DirEntryValue::U64(size) = size {
-            assert_eq!(7, size);
+    if let Some(DirEntryValue::String(path)) = item.get(&DirEntryAttr::Path) {
+        if !path.is_empty() {
             fields += 1;
         }
     }
-    if let Some(size) = item.get(&DirEntryAttr::FileSize) {
-        if let &DirEntryValue::U64(size) = size {
-            assert_eq!(7, size);
+    if let Some(DirEntryValue::String(path)) = item.get(&DirEntryAttr::DosPath) {
+        if !path.is_empty() {
             fields += 1;
         }
     }
-    if let Some(is_dir) = item.get(&DirEntryAttr::IsDir) {
-        if let &DirEntryValue::Boolean(is_dir) = is_dir {
-            assert_eq!(false, is_dir);
-            fields += 1;
-        }
+    if let Some(&DirEntryValue::U64(size)) = item.get(&DirEntryAttr::Size) {
+        assert_eq!(7, size);
+        fields += 1;
+    }
+    if let Some(&DirEntryValue::U64(size)) = item.get(&DirEntryAttr::FileSize) {
+        assert_eq!(7, size);
+        fields += 1;
+    }
+    if let Some(&DirEntryValue::Boolean(is_dir)) = item.get(&DirEntryAttr::IsDir) {
+        assert!(!is_dir);
+        fields += 1;
     }
 
-    if let Some(is_file) = item.get(&DirEntryAttr::IsFile) {
-        if let &DirEntryValue::Boolean(is_file) = is_file {
-            assert_eq!(true, is_file);
-            fields += 1;
-        }
+    if let Some(&DirEntryValue::Boolean(is_file)) = item.get(&DirEntryAttr::IsFile) {
+        assert!(is_file);
+        fields += 1;
     }
 
-    if let Some(modified) = item.get(&DirEntryAttr::Modified) {
-        if let &DirEntryValue::SystemTime(modified) = modified {
-            if modified.elapsed().unwrap().as_secs() == 0 {
-                fields += 1;
-            }
+    if let Some(&DirEntryValue::SystemTime(modified)) = item.get(&DirEntryAttr::Modified) {
+        if modified.elapsed().unwrap().as_secs() == 0 {
+            fields += 1;
         }
     }
-    if let Some(accessed) = item.get(&DirEntryAttr::Accessed) {
-        if let &DirEntryValue::SystemTime(accessed) = accessed {
-            if accessed.elapsed().unwrap().as_secs() == 0 {
-                fields += 1;
-            }
+    if let Some(&DirEntryValue::SystemTime(accessed)) = item.get(&DirEntryAttr::Accessed) {
+        if accessed.elapsed().unwrap().as_secs() == 0 {
+            fields += 1;
         }
     }
 
@@ -3339,15 +3376,11 @@ fn it_details_item_dir_short() {
     let item = get_details_entry(test_dir, &config).unwrap();
     assert_eq!(2, item.len());
 
-    if let Some(name) = item.get(&DirEntryAttr::Name) {
-        if let &DirEntryValue::String(ref name) = name {
-            assert_eq!("it_details_item_dir_short", name);
-        }
+    if let Some(DirEntryValue::String(name)) = item.get(&DirEntryAttr::Name) {
+        assert_eq!("it_details_item_dir_short", name);
     }
-    if let Some(size) = item.get(&DirEntryAttr::Size) {
-        if let &DirEntryValue::U64(size) = size {
-            assert_eq!(0, size);
-        }
+    if let Some(&DirEntryValue::U64(size)) = item.get(&DirEntryAttr::Size) {
+        assert_eq!(0, size);
     }
 }
 
@@ -3364,15 +3397,11 @@ fn it_details_item_file_short() {
     let item = get_details_entry(file, &config).unwrap();
     assert_eq!(2, item.len());
 
-    if let Some(name) = item.get(&DirEntryAttr::Name) {
-        if let &DirEntryValue::String(ref name) = name {
-            assert_eq!("file", name);
-        }
+    if let Some(DirEntryValue::String(name)) = item.get(&DirEntryAttr::Name) {
+        assert_eq!("file", name);
     }
-    if let Some(size) = item.get(&DirEntryAttr::Size) {
-        if let &DirEntryValue::U64(size) = size {
-            assert_eq!(7, size);
-        }
+    if let Some(&DirEntryValue::U64(size)) = item.get(&DirEntryAttr::Size) {
+        assert_eq!(7, size);
     }
 }
 
@@ -3395,36 +3424,24 @@ fn it_ls() {
     assert_eq!(2, ls_result.items.len());
     assert_eq!(3, ls_result.base.len());
 
-    if let Some(name) = ls_result.base.get(&DirEntryAttr::Name) {
-        if let &DirEntryValue::String(ref name) = name {
-            assert_eq!("it_ls", name);
-        }
+    if let Some(DirEntryValue::String(name)) = ls_result.base.get(&DirEntryAttr::Name) {
+        assert_eq!("it_ls", name);
     }
-    if let Some(size) = ls_result.base.get(&DirEntryAttr::Size) {
-        if let &DirEntryValue::U64(size) = size {
-            assert_eq!(14, size);
-        }
+    if let Some(&DirEntryValue::U64(size)) = ls_result.base.get(&DirEntryAttr::Size) {
+        assert_eq!(14, size);
     }
-    if let Some(is_dir) = ls_result.base.get(&DirEntryAttr::IsDir) {
-        if let &DirEntryValue::Boolean(is_dir) = is_dir {
-            assert_eq!(true, is_dir);
-        }
+    if let Some(&DirEntryValue::Boolean(is_dir)) = ls_result.base.get(&DirEntryAttr::IsDir) {
+        assert!(is_dir);
     }
     for item in ls_result.items {
-        if let Some(name) = item.get(&DirEntryAttr::Name) {
-            if let &DirEntryValue::String(ref name) = name {
-                assert_eq!(String::from("file"), name[..4]);
-            }
+        if let Some(DirEntryValue::String(name)) = item.get(&DirEntryAttr::Name) {
+            assert_eq!(String::from("file"), name[..4]);
         }
-        if let Some(size) = item.get(&DirEntryAttr::Size) {
-            if let &DirEntryValue::U64(size) = size {
-                assert_eq!(7, size);
-            }
+        if let Some(&DirEntryValue::U64(size)) = item.get(&DirEntryAttr::Size) {
+            assert_eq!(7, size);
         }
-        if let Some(is_dir) = item.get(&DirEntryAttr::IsDir) {
-            if let &DirEntryValue::Boolean(is_dir) = is_dir {
-                assert_eq!(false, is_dir);
-            }
+        if let Some(&DirEntryValue::Boolean(is_dir)) = item.get(&DirEntryAttr::IsDir) {
+            assert!(!is_dir);
         }
     }
 }
@@ -3487,7 +3504,7 @@ fn it_copy_with_progress_exist_user_decide_overwrite() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3551,7 +3568,7 @@ fn it_copy_with_progress_exist_user_decide_overwrite_all() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3614,7 +3631,7 @@ fn it_copy_with_progress_exist_user_decide_skip() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3677,7 +3694,7 @@ fn it_copy_with_progress_exist_user_decide_skip_all() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3744,7 +3761,7 @@ fn it_copy_with_progress_exist_user_decide_retry() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3814,7 +3831,7 @@ fn it_copy_with_progress_inside_work_target_dir_not_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -3895,7 +3912,7 @@ fn it_copy_with_progress_inside_work_target_dir_exist_with_no_source_dir_named_s
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -3984,7 +4001,7 @@ fn it_copy_with_progress_inside_no_overwrite_work_target_dir_exist_with_source_d
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -4072,7 +4089,7 @@ fn it_copy_with_progress_inside_overwrite_work_target_dir_exist_with_source_dir_
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -4133,7 +4150,7 @@ fn it_move_with_progress_exist_user_decide_overwrite() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -4196,7 +4213,7 @@ fn it_move_with_progress_exist_user_decide_overwrite_all() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -4258,7 +4275,7 @@ fn it_move_with_progress_exist_user_decide_skip() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -4320,7 +4337,7 @@ fn it_move_with_progress_exist_user_decide_skip_all() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -4386,7 +4403,7 @@ fn it_move_with_progress_exist_user_decide_retry() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -4447,6 +4464,10 @@ fn it_move_dir_with_progress_inside_work_target_dir_not_exist() {
                 } else if process_info.file_name == "file1.txt" {
                     assert_eq!(7, process_info.file_total_bytes);
                     assert_eq!(get_dir_size() * 2 + 15, process_info.total_bytes);
+                } else if process_info.file_name.is_empty() {
+                    // The whole directory was moved with a single rename.
+                    assert_eq!(15, process_info.copied_bytes);
+                    assert_eq!(15, process_info.total_bytes);
                 } else {
                     panic!("Unknow file name!");
                 }
@@ -4460,7 +4481,7 @@ fn it_move_dir_with_progress_inside_work_target_dir_not_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -4534,6 +4555,10 @@ fn it_move_dir_with_progress_inside_work_target_dir_exist_with_no_source_dir_nam
                 } else if process_info.file_name == "file1.txt" {
                     assert_eq!(8, process_info.file_total_bytes);
                     assert_eq!(get_dir_size() * 2 + 17, process_info.total_bytes);
+                } else if process_info.file_name.is_empty() {
+                    // The whole directory was moved with a single rename.
+                    assert_eq!(17, process_info.copied_bytes);
+                    assert_eq!(17, process_info.total_bytes);
                 } else {
                     panic!("Unknow file name!");
                 }
@@ -4547,7 +4572,7 @@ fn it_move_dir_with_progress_inside_work_target_dir_exist_with_no_source_dir_nam
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -4646,7 +4671,7 @@ fn it_move_dir_with_progress_inside_no_overwrite_work_target_dir_exist_with_sour
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -4741,7 +4766,7 @@ fn it_move_dir_with_progress_inside_overwrite_work_target_dir_exist_with_source_
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 #[test]
@@ -4814,11 +4839,1478 @@ fn it_move_with_progress_content_only_option() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
+    }
+}
+
+#[cfg(unix)]
+fn create_symlink_tree(root: &Path) -> (PathBuf, PathBuf) {
+    let from = root.join("dir");
+    let sub = from.join("sub");
+    create_all(root, true).unwrap();
+    create_all(&sub, false).unwrap();
+    create_all(root.join("out"), false).unwrap();
+    fs_extra::file::write_all(from.join("test1.txt"), "content1").unwrap();
+    fs_extra::file::write_all(sub.join("test2.txt"), "content22").unwrap();
+    std::os::unix::fs::symlink("test1.txt", from.join("link_file")).unwrap();
+    std::os::unix::fs::symlink("sub", from.join("link_dir")).unwrap();
+    (from, root.join("out"))
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_symlink_preserve_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_symlink_preserve_work");
+    let (from, to) = create_symlink_tree(&root);
+
+    let options = CopyOptions::new().symlinks(SymlinkPolicy::Preserve);
+    copy(&from, &to, &options).unwrap();
+
+    let out = to.join("dir");
+    assert_eq!(
+        Path::new("test1.txt"),
+        fs::read_link(out.join("link_file")).unwrap()
+    );
+    assert_eq!(
+        Path::new("sub"),
+        fs::read_link(out.join("link_dir")).unwrap()
+    );
+    assert!(out.join("sub").join("test2.txt").exists());
+    assert!(!out.join("link_dir").symlink_metadata().unwrap().is_dir());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_with_progress_symlink_follow_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_symlink_follow_work");
+    let (from, to) = create_symlink_tree(&root);
+
+    let options = CopyOptions::new();
+    copy_with_progress(&from, &to, &options, |_| {
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+
+    let out = to.join("dir");
+    assert!(out.join("link_file").symlink_metadata().unwrap().is_file());
+    assert!(out.join("link_dir").symlink_metadata().unwrap().is_dir());
+    assert!(files_eq(
+        out.join("link_dir").join("test2.txt"),
+        from.join("sub").join("test2.txt")
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_symlink_skip_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_symlink_skip_work");
+    let (from, to) = create_symlink_tree(&root);
+
+    let options = CopyOptions::new().symlinks(SymlinkPolicy::Skip);
+    assert_eq!(17, copy(&from, &to, &options).unwrap());
+
+    let out = to.join("dir");
+    assert!(out.join("test1.txt").exists());
+    assert!(out.join("link_file").symlink_metadata().is_err());
+    assert!(out.join("link_dir").symlink_metadata().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_symlink_error() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_symlink_error");
+    let (from, to) = create_symlink_tree(&root);
+
+    let options = CopyOptions::new().symlinks(SymlinkPolicy::Error);
+    match copy(&from, &to, &options) {
+        Err(err) => match err.kind {
+            ErrorKind::Symlink => {}
+            _ => panic!("wrong error"),
+        },
+        Ok(_) => panic!("should be error"),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn it_move_dir_symlink_preserve_work() {
+    let root = Path::new(TEST_FOLDER).join("it_move_dir_symlink_preserve_work");
+    let (from, to) = create_symlink_tree(&root);
+    // The destination exists, so the content is moved entry by entry.
+    create_all(to.join("dir"), true).unwrap();
+
+    let options = CopyOptions::new().symlinks(SymlinkPolicy::Preserve);
+    move_dir(&from, &to, &options).unwrap();
+
+    let out = to.join("dir");
+    assert!(!from.exists());
+    assert_eq!(
+        Path::new("sub"),
+        fs::read_link(out.join("link_dir")).unwrap()
+    );
+    assert!(out.join("link_file").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_get_dir_content_symlink_preserve_work() {
+    let root = Path::new(TEST_FOLDER).join("it_get_dir_content_symlink_preserve_work");
+    let (from, _) = create_symlink_tree(&root);
+
+    let mut options = DirOptions::new();
+    options.symlinks = SymlinkPolicy::Preserve;
+    let result = get_dir_content2(&from, &options).unwrap();
+    assert_eq!(2, result.files.len());
+    assert_eq!(2, result.directories.len());
+    assert_eq!(2, result.symlinks.len());
+    assert!(result
+        .symlinks
+        .contains(&from.join("link_dir").to_str().unwrap().to_string()));
+
+    let result = get_dir_content(&from).unwrap();
+    assert_eq!(4, result.files.len());
+    assert_eq!(3, result.directories.len());
+    assert!(result.symlinks.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_get_size2_symlink_work() {
+    let root = Path::new(TEST_FOLDER).join("it_get_size2_symlink_work");
+    let (from, _) = create_symlink_tree(&root);
+    let links_size = fs::symlink_metadata(from.join("link_file")).unwrap().len()
+        + fs::symlink_metadata(from.join("link_dir")).unwrap().len();
+
+    let mut options = DirOptions::new();
+    assert_eq!(34, get_size2(&from, &options).unwrap());
+    options.symlinks = SymlinkPolicy::Skip;
+    assert_eq!(17, get_size2(&from, &options).unwrap());
+    options.symlinks = SymlinkPolicy::Preserve;
+    assert_eq!(17 + links_size, get_size2(&from, &options).unwrap());
+    assert_eq!(17 + links_size, get_size(&from).unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_get_dir_content_cycle_error() {
+    let root = Path::new(TEST_FOLDER).join("it_get_dir_content_cycle_error");
+    let (from, _) = create_symlink_tree(&root);
+    std::os::unix::fs::symlink("..", from.join("sub").join("loop")).unwrap();
+
+    match get_dir_content(&from) {
+        Err(err) => match err.kind {
+            ErrorKind::Cycle => {}
+            _ => panic!("wrong error"),
+        },
+        Ok(_) => panic!("should be error"),
+    }
+    match get_size2(&from, &DirOptions::new()) {
+        Err(err) => match err.kind {
+            ErrorKind::Cycle => {}
+            _ => panic!("wrong error"),
+        },
+        Ok(_) => panic!("should be error"),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn it_get_dir_content_cycle_skip() {
+    let root = Path::new(TEST_FOLDER).join("it_get_dir_content_cycle_skip");
+    let (from, _) = create_symlink_tree(&root);
+    let cycle = from.join("sub").join("loop");
+    std::os::unix::fs::symlink("..", &cycle).unwrap();
+
+    let mut options = DirOptions::new();
+    options.cycles = CyclePolicy::Skip;
+    let result = get_dir_content2(&from, &options).unwrap();
+    // The loop is reached through `sub` and through `link_dir`, which points to `sub`.
+    assert_eq!(2, result.cycles.len());
+    assert!(result.cycles.contains(&cycle.to_str().unwrap().to_string()));
+    // `link_dir` itself points to a sibling, which is not a cycle.
+    assert_eq!(4, result.files.len());
+    assert_eq!(3, result.directories.len());
+    assert_eq!(34, get_size2(&from, &options).unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_get_dir_content_revisit_work() {
+    let root = Path::new(TEST_FOLDER).join("it_get_dir_content_revisit_work");
+    let from = root.join("src");
+    let out = root.join("out");
+    create_all(&root, true).unwrap();
+    create_all(from.join("v5"), false).unwrap();
+    create_all(&out, false).unwrap();
+    fs_extra::file::write_all(from.join("v5").join("test.txt"), "content22").unwrap();
+    // `v5` is reached a second time through `current`, which is not a loop.
+    std::os::unix::fs::symlink("v5", from.join("current")).unwrap();
+
+    let result = get_dir_content(&from).unwrap();
+    assert!(result.cycles.is_empty());
+    assert_eq!(2, result.files.len());
+    assert_eq!(3, result.directories.len());
+    assert_eq!(18, get_size2(&from, &DirOptions::new()).unwrap());
+
+    assert_eq!(18, copy(&from, &out, &CopyOptions::new()).unwrap());
+    let copied = out.join("src").join("current").join("test.txt");
+    assert_eq!("content22", fs::read_to_string(copied).unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_preserve_times_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_preserve_times_work");
+    let from = root.join("dir");
+    let sub = from.join("sub");
+    let to = root.join("out");
+    create_all(&sub, true).unwrap();
+    create_all(&to, true).unwrap();
+    fs_extra::file::write_all(sub.join("test.txt"), "content").unwrap();
+
+    let time = std::time::UNIX_EPOCH + std::time::Duration::new(1_000_000_000, 500);
+    let times = fs::FileTimes::new().set_accessed(time).set_modified(time);
+    fs::File::options()
+        .write(true)
+        .open(sub.join("test.txt"))
+        .unwrap()
+        .set_times(times)
+        .unwrap();
+    for dir in [&sub, &from] {
+        fs::File::open(dir).unwrap().set_times(times).unwrap();
+    }
+
+    let options = CopyOptions::new().preserve_times(true);
+    copy_with_progress(&from, &to, &options, |_| {
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+
+    let out = to.join("dir");
+    for path in [
+        out.clone(),
+        out.join("sub"),
+        out.join("sub").join("test.txt"),
+    ] {
+        assert_eq!(time, fs::metadata(path).unwrap().modified().unwrap());
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_dir_permissions_work() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = Path::new(TEST_FOLDER).join("it_copy_dir_permissions_work");
+    let from = root.join("dir");
+    let private = from.join("private");
+    let shared = from.join("shared");
+    let read_only = from.join("read_only");
+    let to = root.join("out");
+    create_all(&root, true).unwrap();
+    for dir in [&private, &shared, &read_only, &to] {
+        create_all(dir, false).unwrap();
+    }
+    fs_extra::file::write_all(read_only.join("test.txt"), "content").unwrap();
+    fs::set_permissions(&private, fs::Permissions::from_mode(0o700)).unwrap();
+    fs::set_permissions(&shared, fs::Permissions::from_mode(0o1775)).unwrap();
+    fs::set_permissions(&read_only, fs::Permissions::from_mode(0o555)).unwrap();
+
+    let options = CopyOptions::new();
+    let result = copy(&from, &to, &options);
+    let mode = |path: PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+    let out = to.join("dir");
+    let read_only_mode = mode(out.join("read_only"));
+    for dir in [read_only, out.join("read_only")] {
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    assert_eq!(7, result.unwrap());
+    assert_eq!(0o700, mode(out.join("private")));
+    assert_eq!(0o1775, mode(out.join("shared")));
+    assert_eq!(0o555, read_only_mode);
+    assert!(out.join("read_only").join("test.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_preserve_owner_work() {
+    use std::os::unix::fs::MetadataExt;
+
+    let root = Path::new(TEST_FOLDER).join("it_copy_preserve_owner_work");
+    let (from, to) = create_symlink_tree(&root);
+    let uid = fs::metadata(&from).unwrap().uid();
+
+    let options = CopyOptions::new()
+        .symlinks(SymlinkPolicy::Preserve)
+        .preserve_owner(true)
+        .owner_map(|uid, gid| (uid + 1234, gid + 1234));
+    copy(&from, &to, &options).unwrap();
+
+    let out = to.join("dir");
+    let expected = if uid == 0 { 1234 } else { uid };
+    for path in [
+        out.clone(),
+        out.join("sub"),
+        out.join("test1.txt"),
+        out.join("sub").join("test2.txt"),
+        out.join("link_file"),
+        out.join("link_dir"),
+    ] {
+        assert_eq!(expected, path.symlink_metadata().unwrap().uid());
+    }
+
+    let options = options.overwrite(true).strict_owner(true);
+    let result = copy(&from, &to, &options);
+    if uid == 0 {
+        result.unwrap();
+    } else {
+        match result.unwrap_err().kind {
+            ErrorKind::PermissionDenied => {}
+            _ => panic!("wrong error"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_copy_with_progress_preserve_xattrs_work() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_preserve_xattrs_work");
+    let from = root.join("dir");
+    let sub = from.join("sub");
+    let to = root.join("out");
+    create_all(&root, true).unwrap();
+    create_all(&sub, false).unwrap();
+    create_all(&to, false).unwrap();
+    let file1 = from.join("test1.txt");
+    let file2 = sub.join("test2.txt");
+    fs_extra::file::write_all(&file1, "content1").unwrap();
+    fs_extra::file::write_all(&file2, "content22").unwrap();
+    for path in [&file1, &file2, &sub] {
+        fs_extra::file::set_xattrs(path, vec![("user.origin", "build-42")]).unwrap();
+    }
+    // Unprivileged processes can not set user attributes on a read-only file.
+    fs::set_permissions(&file2, fs::Permissions::from_mode(0o444)).unwrap();
+    let is_root = fs::metadata(&from).unwrap().uid() == 0;
+
+    let options = CopyOptions::new().preserve_xattrs(true);
+    let mut failed = Vec::new();
+    let result = copy_with_progress(&from, &to, &options, |info| {
+        if info.state == TransitState::XattrFailed {
+            failed.push(info.file_name);
+        }
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+
+    let out = to.join("dir");
+    let origin = vec![("user.origin".into(), b"build-42".to_vec())];
+    assert_eq!(17, result);
+    assert_eq!(
+        origin,
+        fs_extra::file::get_xattrs(out.join("test1.txt")).unwrap()
+    );
+    assert_eq!(origin, fs_extra::file::get_xattrs(out.join("sub")).unwrap());
+    assert!(fs_extra::file::get_xattrs(&out).unwrap().is_empty());
+    if is_root {
+        assert!(failed.is_empty());
+    } else {
+        assert_eq!(vec!["test2.txt".to_string()], failed);
+    }
+    assert!(out.join("sub").join("test2.txt").exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_move_with_progress_preserve_xattrs_work() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let from = Path::new(TEST_FOLDER)
+        .join("it_move_with_progress_preserve_xattrs_work")
+        .join("dir");
+    let sub = from.join("sub");
+    create_all(&sub, true).unwrap();
+    // Attributes are only copied when the move falls back to a copy on another file system.
+    let other_fs = Path::new("/dev/shm");
+    match other_fs.metadata() {
+        Ok(meta) if meta.dev() != from.metadata().unwrap().dev() => {}
+        _ => return,
+    }
+    let to = other_fs.join(format!("fs_extra_xattrs_{}", std::process::id()));
+    create_all(&to, true).unwrap();
+    let file1 = from.join("test1.txt");
+    let file2 = sub.join("test2.txt");
+    fs_extra::file::write_all(&file1, "content1").unwrap();
+    fs_extra::file::write_all(&file2, "content22").unwrap();
+    for path in [&file1, &file2, &sub] {
+        fs_extra::file::set_xattrs(path, vec![("user.origin", "build-42")]).unwrap();
+    }
+    // Unprivileged processes can not set user attributes on a read-only file.
+    fs::set_permissions(&file2, fs::Permissions::from_mode(0o444)).unwrap();
+    let is_root = fs::metadata(&from).unwrap().uid() == 0;
+
+    let options = CopyOptions::new().preserve_xattrs(true);
+    let mut failed = Vec::new();
+    let result = move_dir_with_progress(&from, &to, &options, |info| {
+        if info.state == TransitState::XattrFailed {
+            failed.push(info.file_name);
+        }
+        TransitProcessResult::ContinueOrAbort
+    });
+    let out = to.join("dir");
+    let xattrs = fs_extra::file::get_xattrs(out.join("test1.txt"));
+    let moved = out.join("sub").join("test2.txt").exists();
+    let _ = remove(&to);
+
+    assert_eq!(17, result.unwrap());
+    assert!(moved);
+    assert!(!from.exists());
+    let origin = vec![("user.origin".into(), b"build-42".to_vec())];
+    assert_eq!(origin, xattrs.unwrap());
+    if is_root {
+        assert!(failed.is_empty());
+    } else {
+        assert_eq!(vec!["test2.txt".to_string()], failed);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_with_progress_preserve_hard_links_work() {
+    use std::os::unix::fs::MetadataExt;
+
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_preserve_hard_links_work");
+    let from = root.join("dir");
+    let to = root.join("out");
+    create_all(&root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    create_all(&to, false).unwrap();
+    fs_extra::file::write_all(from.join("test1.txt"), "content1").unwrap();
+    fs_extra::file::write_all(from.join("test2.txt"), "content22").unwrap();
+    fs::hard_link(from.join("test1.txt"), from.join("link1.txt")).unwrap();
+    fs::hard_link(from.join("test1.txt"), from.join("sub").join("link2.txt")).unwrap();
+
+    let options = CopyOptions::new().preserve_hard_links(true);
+    let mut total_bytes = 0;
+    let mut copied_bytes = 0;
+    let result = copy_with_progress(&from, &to, &options, |info| {
+        total_bytes = info.total_bytes;
+        copied_bytes = info.copied_bytes;
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+
+    let out = to.join("dir");
+    let inode = |path: PathBuf| fs::metadata(path).unwrap().ino();
+    let dir_size = get_dir_content(&from).unwrap().dir_size;
+    assert_eq!(17, result);
+    assert_eq!(dir_size - 16, total_bytes);
+    assert_eq!(17, copied_bytes);
+    assert_eq!(3, fs::metadata(out.join("test1.txt")).unwrap().nlink());
+    assert_eq!(inode(out.join("test1.txt")), inode(out.join("link1.txt")));
+    assert_eq!(
+        inode(out.join("test1.txt")),
+        inode(out.join("sub").join("link2.txt"))
+    );
+    assert_ne!(inode(from.join("test1.txt")), inode(out.join("test1.txt")));
+    assert_eq!(1, fs::metadata(out.join("test2.txt")).unwrap().nlink());
+    assert!(compare_dir(&from, &to));
+
+    remove(&out).unwrap();
+    assert_eq!(33, copy(&from, &to, &CopyOptions::new()).unwrap());
+    assert_eq!(1, fs::metadata(out.join("link1.txt")).unwrap().nlink());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_preserve_hard_links_skip_exist_work() {
+    use std::os::unix::fs::MetadataExt;
+
+    let root = Path::new(TEST_FOLDER).join("it_copy_preserve_hard_links_skip_exist_work");
+    let from = root.join("dir");
+    let out = root.join("out").join("dir");
+    create_all(&root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    fs_extra::file::write_all(from.join("test1.txt"), "content1").unwrap();
+    fs::hard_link(from.join("test1.txt"), from.join("link1.txt")).unwrap();
+    fs::hard_link(from.join("test1.txt"), from.join("sub").join("link2.txt")).unwrap();
+
+    let options = CopyOptions::new()
+        .preserve_hard_links(true)
+        .skip_exist(true);
+    let inode = |path: PathBuf| fs::metadata(path).unwrap().ino();
+    for with_progress in [false, true] {
+        create_all(&out, true).unwrap();
+        fs_extra::file::write_all(out.join("test1.txt"), "old").unwrap();
+        if with_progress {
+            copy_with_progress(&from, root.join("out"), &options, |_| {
+                TransitProcessResult::ContinueOrAbort
+            })
+            .unwrap();
+        } else {
+            copy(&from, root.join("out"), &options).unwrap();
+        }
+
+        // The kept file is not linked to the copies of the other names.
+        assert_eq!("old", fs::read_to_string(out.join("test1.txt")).unwrap());
+        assert_eq!(
+            "content1",
+            fs::read_to_string(out.join("link1.txt")).unwrap()
+        );
+        assert_eq!(1, fs::metadata(out.join("test1.txt")).unwrap().nlink());
+        assert_eq!(
+            inode(out.join("link1.txt")),
+            inode(out.join("sub").join("link2.txt"))
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_copy_special_files_work() {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+    use std::process::Command;
+
+    let root = Path::new(TEST_FOLDER).join("it_copy_special_files_work");
+    let from = root.join("dir");
+    let to = root.join("out");
+    create_all(&root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    create_all(&to, false).unwrap();
+    fs_extra::file::write_all(from.join("test.txt"), "content").unwrap();
+    assert!(Command::new("mkfifo")
+        .arg(from.join("fifo"))
+        .status()
+        .unwrap()
+        .success());
+    let _listener = std::os::unix::net::UnixListener::bind(from.join("socket")).unwrap();
+    let is_root = fs::metadata(&from).unwrap().uid() == 0;
+    if is_root {
+        let null = from.join("sub").join("null");
+        assert!(Command::new("mknod")
+            .args([null.as_os_str(), "c".as_ref(), "1".as_ref(), "3".as_ref()])
+            .status()
+            .unwrap()
+            .success());
+    }
+
+    let dir_content = get_dir_content(&from).unwrap();
+    assert_eq!(
+        vec![from.join("test.txt").to_str().unwrap()],
+        dir_content.files
+    );
+    assert_eq!(if is_root { 3 } else { 2 }, dir_content.special_files.len());
+
+    let options = CopyOptions::new().special_files(SpecialFilePolicy::Error);
+    match copy(&from, &to, &options) {
+        Ok(_) => panic!("should be error"),
+        Err(err) => match err.kind {
+            ErrorKind::SpecialFile => {}
+            _ => panic!("wrong error"),
+        },
+    }
+
+    let options = CopyOptions::new()
+        .overwrite(true)
+        .special_files(SpecialFilePolicy::Skip);
+    copy(&from, &to, &options).unwrap();
+    let out = to.join("dir");
+    assert!(out.join("test.txt").exists());
+    assert!(!out.join("fifo").exists());
+
+    let options = CopyOptions::new().overwrite(true);
+    copy(&from, &to, &options).unwrap();
+    assert!(fs::metadata(out.join("fifo"))
+        .unwrap()
+        .file_type()
+        .is_fifo());
+    assert!(out.join("socket").symlink_metadata().is_err());
+    if is_root {
+        let metadata = fs::metadata(out.join("sub").join("null")).unwrap();
+        assert!(metadata.file_type().is_char_device());
+        assert_eq!(
+            fs::metadata(from.join("sub").join("null")).unwrap().rdev(),
+            metadata.rdev()
+        );
+    }
+}
+
+#[test]
+fn it_copy_with_progress_overwrite_policy_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_overwrite_policy_work");
+    let from = root.join("dir");
+    let out = root.join("out").join("dir");
+    create_all(&root, true).unwrap();
+    create_all(&from, false).unwrap();
+    create_all(&out, false).unwrap();
+    fs_extra::file::write_all(from.join("same.txt"), "content").unwrap();
+    fs_extra::file::write_all(from.join("changed.txt"), "content").unwrap();
+    fs_extra::file::write_all(from.join("new.txt"), "content").unwrap();
+    fs_extra::file::write_all(out.join("same.txt"), "content").unwrap();
+    fs_extra::file::write_all(out.join("changed.txt"), "CONTENT").unwrap();
+
+    let options =
+        CopyOptions::new().overwrite_policy(fs_extra::file::OverwritePolicy::IfContentDiffers);
+    let mut files = Vec::new();
+    let result = copy_with_progress(&from, root.join("out"), &options, |info| {
+        assert!(info.state == TransitState::Normal);
+        files.push(info.file_name);
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    files.sort();
+    files.dedup();
+
+    assert_eq!(14, result);
+    assert_eq!(vec!["changed.txt", "new.txt"], files);
+    assert!(compare_dir(&from, root.join("out")));
+
+    let options =
+        CopyOptions::new().overwrite_policy(fs_extra::file::OverwritePolicy::IfSizeDiffers);
+    assert_eq!(0, copy(&from, root.join("out"), &options).unwrap());
+    let options = CopyOptions::new().overwrite_policy(fs_extra::file::OverwritePolicy::Skip);
+    assert_eq!(0, move_dir(&from, root.join("out"), &options).unwrap());
+    assert!(from.exists());
+}
+
+#[test]
+fn it_mirror_work() {
+    let root = Path::new(TEST_FOLDER).join("it_mirror_work");
+    let from = root.join("dir");
+    let out = root.join("out").join("dir");
+    create_all(&root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    create_all(out.join("sub"), false).unwrap();
+    create_all(out.join("old").join("deep"), false).unwrap();
+    create_all(out.join("cache"), false).unwrap();
+    fs_extra::file::write_all(from.join("file.txt"), "content").unwrap();
+    fs_extra::file::write_all(from.join("sub").join("file.txt"), "content").unwrap();
+    fs_extra::file::write_all(out.join("extra.txt"), "extra").unwrap();
+    fs_extra::file::write_all(out.join("sub").join("extra.txt"), "extra").unwrap();
+    fs_extra::file::write_all(out.join("old").join("deep").join("file.txt"), "old").unwrap();
+    fs_extra::file::write_all(out.join("cache").join("file.txt"), "cache").unwrap();
+
+    let options = CopyOptions::new()
+        .mirror_dry_run(true)
+        .mirror_exclude(&["cache"]);
+    let result = mirror(&from, root.join("out"), &options).unwrap();
+    assert_eq!(14, result.copied_bytes);
+    assert_eq!(5, result.deleted_entries);
+    assert!(out.join("extra.txt").exists());
+    assert!(out.join("old").join("deep").join("file.txt").exists());
+
+    let options = CopyOptions::new()
+        .overwrite(true)
+        .mirror_exclude(&["cache"]);
+    let result = mirror(&from, root.join("out"), &options).unwrap();
+    assert_eq!(
+        MirrorResult {
+            copied_bytes: 14,
+            deleted_entries: 5
+        },
+        result
+    );
+    assert!(!out.join("extra.txt").exists());
+    assert!(!out.join("sub").join("extra.txt").exists());
+    assert!(!out.join("old").exists());
+    assert!(out.join("cache").join("file.txt").exists());
+
+    let options = CopyOptions::new().overwrite(true).mirror_copy(true);
+    assert_eq!(14, copy(&from, root.join("out"), &options).unwrap());
+    assert!(compare_dir(&from, root.join("out")));
+}
+
+#[test]
+fn it_copy_with_progress_mirror_copy_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_mirror_copy_work");
+    let from = root.join("dir");
+    let out = root.join("out").join("dir");
+    create_all(&root, true).unwrap();
+    create_all(&from, false).unwrap();
+    create_all(out.join("old"), false).unwrap();
+    fs_extra::file::write_all(from.join("file.txt"), "content").unwrap();
+    fs_extra::file::write_all(out.join("extra.txt"), "extra").unwrap();
+    fs_extra::file::write_all(out.join("old").join("file.txt"), "old").unwrap();
+
+    let options = CopyOptions::new().mirror_copy(true);
+    let mut deleted = Vec::new();
+    let result = copy_with_progress(&from, root.join("out"), &options, |info| {
+        if info.deleted_entries > deleted.len() as u64 {
+            deleted.push(info.file_name);
+        }
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    deleted.sort();
+
+    assert_eq!(7, result);
+    assert_eq!(vec!["extra.txt", "old", "old/file.txt"], deleted);
+    assert!(compare_dir(&from, root.join("out")));
+}
+
+#[test]
+fn it_copy_mirror_copy_rename_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_mirror_copy_rename_work");
+    let from = root.join("dir");
+    let out = root.join("out").join("dir");
+    create_all(&root, true).unwrap();
+    create_all(&from, false).unwrap();
+    fs_extra::file::write_all(from.join("file.txt"), "content").unwrap();
+
+    let options = CopyOptions::new()
+        .mirror_copy(true)
+        .overwrite_policy(fs_extra::file::OverwritePolicy::Rename);
+    for with_progress in [false, true] {
+        create_all(&out, true).unwrap();
+        fs_extra::file::write_all(out.join("file.txt"), "old").unwrap();
+        fs_extra::file::write_all(out.join("extra.txt"), "extra").unwrap();
+        if with_progress {
+            copy_with_progress(&from, root.join("out"), &options, |_| {
+                TransitProcessResult::ContinueOrAbort
+            })
+            .unwrap();
+        } else {
+            copy(&from, root.join("out"), &options).unwrap();
+        }
+
+        // The renamed copy is kept, other entries missing in the source are removed.
+        let renamed = out.join("file (1).txt");
+        assert_eq!("content", fs::read_to_string(renamed).unwrap());
+        assert_eq!("old", fs::read_to_string(out.join("file.txt")).unwrap());
+        assert!(!out.join("extra.txt").exists());
+    }
+}
+
+#[test]
+fn it_copy_mirror_copy_backup_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_mirror_copy_backup_work");
+    let from = root.join("dir");
+    let out = root.join("out").join("dir");
+    create_all(&root, true).unwrap();
+    create_all(&from, false).unwrap();
+    fs_extra::file::write_all(from.join("file.txt"), "content").unwrap();
+
+    let backups = [
+        (fs_extra::file::Backup::Simple, "file.txt~"),
+        (fs_extra::file::Backup::Numbered, "file.txt.~1~"),
+    ];
+    for (backup, backup_name) in backups {
+        let options = CopyOptions::new()
+            .mirror_copy(true)
+            .overwrite(true)
+            .backup(backup);
+        for with_progress in [false, true] {
+            create_all(&out, true).unwrap();
+            fs_extra::file::write_all(out.join("file.txt"), "old").unwrap();
+            fs_extra::file::write_all(out.join("extra.txt"), "extra").unwrap();
+            if with_progress {
+                copy_with_progress(&from, root.join("out"), &options, |_| {
+                    TransitProcessResult::ContinueOrAbort
+                })
+                .unwrap();
+            } else {
+                copy(&from, root.join("out"), &options).unwrap();
+            }
+
+            // The backup made by the copy is kept, other entries missing in the source are
+            // removed.
+            let backup = out.join(backup_name);
+            assert_eq!("old", fs::read_to_string(backup).unwrap());
+            assert_eq!("content", fs::read_to_string(out.join("file.txt")).unwrap());
+            assert!(!out.join("extra.txt").exists());
+        }
+    }
+}
+
+// Creates a source tree with build output and temporary files for the filter tests.
+fn create_filter_tree(root: &Path) -> PathBuf {
+    let from = root.join("project");
+    create_all(root, true).unwrap();
+    for dir in &[
+        "src/sub",
+        "target/debug",
+        "node_modules/pkg",
+        ".git",
+        "docs/sub",
+    ] {
+        create_all(from.join(dir), false).unwrap();
+    }
+    for file in &[
+        "src/main.rs",
+        "src/sub/lib.rs",
+        "src/sub/notes.tmp",
+        "target/debug/app",
+        "node_modules/pkg/index.js",
+        ".git/config",
+        "docs/readme.md",
+        "docs/sub/lib.rs",
+        "a.tmp",
+    ] {
+        fs_extra::file::write_all(from.join(file), "12345").unwrap();
+    }
+    from
+}
+
+#[test]
+fn it_get_dir_content2_filter_work() {
+    let root = Path::new(TEST_FOLDER).join("it_get_dir_content2_filter_work");
+    let from = create_filter_tree(&root);
+    let relative = |paths: Vec<String>| {
+        let mut paths: Vec<String> = paths
+            .iter()
+            .map(|path| {
+                let path = Path::new(path).strip_prefix(&from).unwrap();
+                path.to_str().unwrap().replace('\\', "/")
+            })
+            .collect();
+        paths.sort();
+        paths
+    };
+
+    let options = DirOptions {
+        exclude: vec![
+            "target/".to_string(),
+            "node_modules".to_string(),
+            "*.tmp".to_string(),
+            "/.git/".to_string(),
+            "docs/sub".to_string(),
+        ],
+        ..Default::default()
+    };
+    let content = get_dir_content2(&from, &options).unwrap();
+    assert_eq!(
+        vec!["docs/readme.md", "src/main.rs", "src/sub/lib.rs"],
+        relative(content.files)
+    );
+    assert_eq!(
+        vec!["", "docs", "src", "src/sub"],
+        relative(content.directories)
+    );
+    assert_eq!(15, get_size2(&from, &options).unwrap());
+
+    let options = DirOptions {
+        include: vec!["src/**/*.rs".to_string(), "docs/".to_string()],
+        exclude: vec!["sub".to_string()],
+        ..Default::default()
+    };
+    let content = get_dir_content2(&from, &options).unwrap();
+    assert_eq!(
+        vec!["docs/readme.md", "src/main.rs"],
+        relative(content.files)
+    );
+    assert_eq!(10, get_size2(&from, &options).unwrap());
+
+    let options = DirOptions {
+        include: vec!["[a-c].tmp".to_string(), "**/?ain.rs".to_string()],
+        ..Default::default()
+    };
+    let content = get_dir_content2(&from, &options).unwrap();
+    assert_eq!(vec!["a.tmp", "src/main.rs"], relative(content.files));
+}
+
+#[test]
+fn it_copy_filter_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_filter_work");
+    let from = create_filter_tree(&root);
+    let out = root.join("out");
+    create_all(&out, false).unwrap();
+
+    let options = CopyOptions::new().exclude(&["target/", "node_modules/", "*.tmp", ".git/"]);
+    let mut total_bytes = 0;
+    let result = copy_with_progress(&from, &out, &options, |info| {
+        total_bytes = info.total_bytes;
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(20, result);
+    let read_options = DirOptions {
+        exclude: options.exclude.clone(),
+        ..Default::default()
+    };
+    let dir_size = get_dir_content2(&from, &read_options).unwrap().dir_size;
+    assert_eq!(dir_size, total_bytes);
+    assert!(dir_size < get_dir_content(&from).unwrap().dir_size);
+    assert!(out.join("project/docs/sub/lib.rs").exists());
+    assert!(!out.join("project/target").exists());
+    assert!(!out.join("project/src/sub/notes.tmp").exists());
+    assert!(!out.join("project/.git").exists());
+
+    let options = CopyOptions::new()
+        .overwrite(true)
+        .include(&["*.rs"])
+        .mirror_copy(true);
+    fs_extra::file::write_all(out.join("project/extra.rs"), "extra").unwrap();
+    assert_eq!(15, copy(&from, &out, &options).unwrap());
+    assert!(!out.join("project/extra.rs").exists());
+    assert!(out.join("project/docs/readme.md").exists());
+}
+
+#[test]
+fn it_move_dir_filter_work() {
+    let root = Path::new(TEST_FOLDER).join("it_move_dir_filter_work");
+    let from = create_filter_tree(&root);
+    let out = root.join("out");
+    create_all(&out, false).unwrap();
+
+    let options = CopyOptions::new().exclude(&["target/", "*.tmp"]);
+    assert_eq!(30, move_dir(&from, &out, &options).unwrap());
+    assert!(out.join("project/.git/config").exists());
+    assert!(out.join("project/src/sub/lib.rs").exists());
+    assert!(!out.join("project/target").exists());
+    assert!(!out.join("project/a.tmp").exists());
+
+    assert!(from.join("target/debug/app").exists());
+    assert!(from.join("src/sub/notes.tmp").exists());
+    assert!(from.join("a.tmp").exists());
+    assert!(!from.join("src/main.rs").exists());
+    assert!(!from.join("docs").exists());
+    assert!(!from.join(".git").exists());
+}
+
+// Prunes `target`, skips `docs` and files which are not 5 bytes long.
+fn filter_entry(path: &Path, metadata: &fs::Metadata) -> FilterResult {
+    match path.file_name().and_then(|name| name.to_str()) {
+        Some("target") => FilterResult::Prune,
+        Some("docs") => FilterResult::Skip,
+        _ if metadata.is_file() && metadata.len() != 5 => FilterResult::Skip,
+        _ => FilterResult::Include,
+    }
+}
+
+#[test]
+fn it_get_dir_content2_predicate_work() {
+    let root = Path::new(TEST_FOLDER).join("it_get_dir_content2_predicate_work");
+    let from = create_filter_tree(&root);
+    fs_extra::file::write_all(from.join("src/big.rs"), "1234567").unwrap();
+    fs_extra::file::write_all(from.join("docs/readme.md"), "1234567").unwrap();
+    let mut visited = Vec::new();
+    let relative = |paths: Vec<String>| {
+        let mut paths: Vec<String> = paths
+            .iter()
+            .map(|path| {
+                let path = Path::new(path).strip_prefix(&from).unwrap();
+                path.to_str().unwrap().replace('\\', "/")
+            })
+            .collect();
+        paths.sort();
+        paths
+    };
+
+    let options = DirOptions {
+        exclude: vec!["node_modules".to_string(), ".git".to_string()],
+        filter: Some(std::sync::Arc::new(filter_entry)),
+        ..Default::default()
+    };
+    let content = get_dir_content2(&from, &options).unwrap();
+    assert_eq!(
+        vec![
+            "a.tmp",
+            "docs/sub/lib.rs",
+            "src/main.rs",
+            "src/sub/lib.rs",
+            "src/sub/notes.tmp"
+        ],
+        relative(content.files)
+    );
+    assert_eq!(
+        vec!["", "docs", "docs/sub", "src", "src/sub"],
+        relative(content.directories)
+    );
+    assert_eq!(25, get_size2(&from, &options).unwrap());
+
+    let mut config = HashSet::new();
+    config.insert(DirEntryAttr::Name);
+    let result = ls_filtered(&from, &config, &options).unwrap();
+    for item in result.items {
+        if let Some(DirEntryValue::String(name)) = item.get(&DirEntryAttr::Name) {
+            visited.push(name.clone());
+        }
+    }
+    visited.sort();
+    assert_eq!(vec!["a", "src"], visited);
+
+    fs::remove_dir_all(from.join("docs/sub")).unwrap();
+    let content = get_dir_content2(&from, &options).unwrap();
+    assert_eq!(vec!["", "src", "src/sub"], relative(content.directories));
+}
+
+#[test]
+fn it_copy_with_progress_predicate_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_predicate_work");
+    let from = create_filter_tree(&root);
+    fs_extra::file::write_all(from.join("src/big.rs"), "1234567").unwrap();
+    let out = root.join("out");
+    create_all(&out, false).unwrap();
+
+    let options = CopyOptions::new().filter(filter_entry);
+    let result = copy_with_progress(&from, &out, &options, |_| {
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(40, result);
+    assert!(out.join("project/docs/sub/lib.rs").exists());
+    assert!(!out.join("project/target").exists());
+    assert!(!out.join("project/src/big.rs").exists());
+
+    let options = options.copy_inside(true);
+    let result = move_dir(&from, root.join("moved"), &options).unwrap();
+    assert_eq!(40, result);
+    assert!(root.join("moved/src/main.rs").exists());
+    assert!(from.join("target/debug/app").exists());
+    assert!(from.join("src/big.rs").exists());
+    assert!(!from.join("src/main.rs").exists());
+    assert!(!from.join(".git").exists());
+}
+
+// Adds ignore files to the tree of `create_filter_tree`.
+fn create_ignore_files(from: &Path) {
+    create_all(from.join(".git/info"), false).unwrap();
+    fs_extra::file::write_all(from.join(".git/info/exclude"), "node_modules\n*.md\n").unwrap();
+    fs_extra::file::write_all(
+        from.join(".gitignore"),
+        "# build\ntarget/\n*.tmp\n!a.tmp\n!/docs/readme.md\n",
+    )
+    .unwrap();
+    fs_extra::file::write_all(from.join("src/.gitignore"), "!notes.tmp\nsub/lib.rs\n").unwrap();
+    fs_extra::file::write_all(from.join("docs/.fsextraignore"), "sub/\n").unwrap();
+}
+
+#[test]
+fn it_get_dir_content2_gitignore_work() {
+    let root = Path::new(TEST_FOLDER).join("it_get_dir_content2_gitignore_work");
+    let from = create_filter_tree(&root);
+    create_ignore_files(&from);
+
+    let options = DirOptions {
+        gitignore: true,
+        ..Default::default()
+    };
+    let result = get_dir_content2(&from, &options).unwrap();
+    let mut files: Vec<String> = result
+        .files
+        .iter()
+        .map(|file| {
+            let path = Path::new(file).strip_prefix(&from).unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect();
+    files.sort();
+    assert_eq!(
+        vec![
+            ".gitignore",
+            "a.tmp",
+            "docs/.fsextraignore",
+            "docs/readme.md",
+            "docs/sub/lib.rs",
+            "src/.gitignore",
+            "src/main.rs",
+            "src/sub/notes.tmp",
+        ],
+        files
+    );
+    assert!(!result
+        .directories
+        .contains(&from.join("target").to_str().unwrap().to_string()));
+    assert!(!result
+        .directories
+        .contains(&from.join(".git").to_str().unwrap().to_string()));
+
+    let options = DirOptions {
+        ignore_files: vec![".fsextraignore".to_string()],
+        ..options
+    };
+    let result = get_dir_content2(&from, &options).unwrap();
+    assert_eq!(7, result.files.len());
+    assert!(!result
+        .directories
+        .contains(&from.join("docs/sub").to_str().unwrap().to_string()));
+
+    let files_size: u64 = result
+        .files
+        .iter()
+        .map(|file| fs::metadata(file).unwrap().len())
+        .sum();
+    assert_eq!(files_size, get_size2(&from, &options).unwrap());
+
+    let options = DirOptions {
+        ignore_files: vec![".fsextraignore".to_string()],
+        ..Default::default()
+    };
+    let result = get_dir_content2(&from, &options).unwrap();
+    assert!(result
+        .files
+        .contains(&from.join("target/debug/app").to_str().unwrap().to_string()));
+    assert!(!result
+        .files
+        .contains(&from.join("docs/sub/lib.rs").to_str().unwrap().to_string()));
+}
+
+#[test]
+fn it_copy_gitignore_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_gitignore_work");
+    let from = create_filter_tree(&root);
+    create_ignore_files(&from);
+    let out = root.join("out");
+    create_all(&out, false).unwrap();
+
+    let options = CopyOptions::new()
+        .gitignore(true)
+        .ignore_files(&[".fsextraignore"]);
+    let result = copy_with_progress(&from, &out, &options, |_| {
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    let read_options = DirOptions {
+        gitignore: true,
+        ignore_files: options.ignore_files.clone(),
+        ..Default::default()
+    };
+    assert_eq!(get_size2(&from, &read_options).unwrap(), result);
+    assert!(out.join("project/src/main.rs").exists());
+    assert!(out.join("project/src/sub/notes.tmp").exists());
+    assert!(!out.join("project/src/sub/lib.rs").exists());
+    assert!(out.join("project/docs/readme.md").exists());
+    assert!(!out.join("project/docs/sub").exists());
+    assert!(!out.join("project/target").exists());
+    assert!(!out.join("project/node_modules").exists());
+    assert!(!out.join("project/.git").exists());
+
+    let out = root.join("moved");
+    create_all(&out, false).unwrap();
+    move_dir(&from, &out, &options).unwrap();
+    assert!(out.join("project/a.tmp").exists());
+    assert!(!from.join("a.tmp").exists());
+    assert!(from.join("target/debug/app").exists());
+    assert!(from.join(".git/config").exists());
+}
+
+#[test]
+fn it_copy_with_progress_rename_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_rename_work");
+    let from = root.join("photos");
+    let out = root.join("out");
+    create_all(&root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    create_all(out.join("photos/sub"), false).unwrap();
+    fs_extra::file::write_all(from.join("a.jpg"), "new a").unwrap();
+    fs_extra::file::write_all(from.join("b.jpg"), "new b").unwrap();
+    fs_extra::file::write_all(from.join("sub/c.jpg"), "new c").unwrap();
+    fs_extra::file::write_all(out.join("photos/a.jpg"), "old a").unwrap();
+    fs_extra::file::write_all(out.join("photos/b.jpg"), "old b").unwrap();
+    fs_extra::file::write_all(out.join("photos/b (1).jpg"), "old b1").unwrap();
+    fs_extra::file::write_all(out.join("photos/sub/c.jpg"), "old c").unwrap();
+
+    let mut renamed = Vec::new();
+    let options = CopyOptions::new();
+    let result = copy_with_progress(&from, &out, &options, |info| {
+        if info.state == TransitState::Exists {
+            return TransitProcessResult::RenameAll;
+        }
+        if let Some(path) = info.renamed_to {
+            if !renamed.contains(&path) {
+                renamed.push(path);
+            }
+        }
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(15, result);
+    renamed.sort();
+    assert_eq!(
+        vec![
+            out.join("photos/a (1).jpg"),
+            out.join("photos/b (2).jpg"),
+            out.join("photos/sub/c (1).jpg"),
+        ],
+        renamed
+    );
+    assert_eq!(
+        "old a",
+        fs_extra::file::read_to_string(out.join("photos/a.jpg")).unwrap()
+    );
+    assert_eq!(
+        "new b",
+        fs_extra::file::read_to_string(out.join("photos/b (2).jpg")).unwrap()
+    );
+
+    let options = CopyOptions::new().overwrite_policy(fs_extra::file::OverwritePolicy::Rename);
+    assert_eq!(15, copy(&from, &out, &options).unwrap());
+    assert!(out.join("photos/a (2).jpg").exists());
+    assert!(out.join("photos/sub/c (2).jpg").exists());
+
+    let options = CopyOptions::new();
+    let result = move_dir_with_progress(&from, &out, &options, |info| {
+        if info.state == TransitState::Exists {
+            return TransitProcessResult::Rename;
+        }
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(15, result);
+    assert!(!from.exists());
+    assert!(out.join("photos/a (3).jpg").exists());
+    assert!(out.join("photos/b (4).jpg").exists());
+}
+
+#[test]
+fn it_copy_with_progress_backup_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_backup_work");
+    let from = root.join("dir");
+    let out = root.join("out");
+    let backup_dir = root.join("backups");
+    create_all(&root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    create_all(out.join("dir/sub"), false).unwrap();
+    fs_extra::file::write_all(from.join("file1.txt"), "new1").unwrap();
+    fs_extra::file::write_all(from.join("sub/file2.txt"), "new2").unwrap();
+    fs_extra::file::write_all(out.join("dir/sub/file2.txt"), "old2").unwrap();
+
+    let options = CopyOptions::new()
+        .overwrite(true)
+        .backup(fs_extra::file::Backup::Numbered);
+    assert_eq!(8, copy(&from, &out, &options).unwrap());
+    assert_eq!(
+        "old2",
+        fs_extra::file::read_to_string(out.join("dir/sub/file2.txt.~1~")).unwrap()
+    );
+    assert!(!out.join("dir/file1.txt.~1~").exists());
+
+    let options = options.backup_dir(&backup_dir);
+    let mut backups = Vec::new();
+    let result = copy_with_progress(&from, &out, &options, |info| {
+        if let Some(backup) = info.backup {
+            if !backups.contains(&backup) {
+                backups.push(backup);
+            }
+        }
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(8, result);
+    backups.sort();
+    assert_eq!(
+        vec![
+            backup_dir.join("file1.txt.~1~"),
+            backup_dir.join("sub/file2.txt.~1~"),
+        ],
+        backups
+    );
+    assert_eq!(
+        "new2",
+        fs_extra::file::read_to_string(backup_dir.join("sub/file2.txt.~1~")).unwrap()
+    );
+
+    let options = CopyOptions::new()
+        .overwrite(true)
+        .backup(fs_extra::file::Backup::Simple)
+        .backup_dir(&backup_dir);
+    move_dir(&from, &out, &options).unwrap();
+    assert!(!from.exists());
+    assert!(backup_dir.join("file1.txt~").exists());
+    assert!(backup_dir.join("sub/file2.txt~").exists());
+}
+
+#[test]
+fn it_copy_atomic_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_atomic_work");
+    let from = root.join("dir");
+    let out = root.join("out");
+    create_all(&root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    create_all(out.join("dir"), false).unwrap();
+    fs_extra::file::write_all(from.join("file1.txt"), "new1").unwrap();
+    fs_extra::file::write_all(from.join("sub/file2.txt"), "new2").unwrap();
+    fs_extra::file::write_all(out.join("dir/file1.txt"), "old1").unwrap();
+
+    let options = CopyOptions::new().overwrite(true).atomic(true);
+    let result = copy_with_progress(&from, &out, &options, |info| {
+        if info.file_name == "file1.txt" && info.file_bytes_copied < info.file_total_bytes {
+            assert_eq!(
+                "old1",
+                fs_extra::file::read_to_string(out.join("dir/file1.txt")).unwrap()
+            );
+        }
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(8, result);
+    assert!(compare_dir(&from, &out));
+    let content = get_dir_content(&out).unwrap();
+    assert_eq!(2, content.files.len());
+}
+
+#[test]
+fn it_copy_and_move_durability_work() {
+    use fs_extra::file::Durability;
+
+    let root = Path::new(TEST_FOLDER).join("it_copy_and_move_durability_work");
+    let from = root.join("dir");
+    let out = root.join("out");
+    let moved = root.join("moved");
+    create_all(&root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    create_all(&out, false).unwrap();
+    create_all(&moved, false).unwrap();
+    fs_extra::file::write_all(from.join("file1.txt"), "content1").unwrap();
+    fs_extra::file::write_all(from.join("sub/file2.txt"), "content2").unwrap();
+
+    let options = CopyOptions::new().durability(Durability::FileAndDirectory);
+    assert_eq!(16, copy(&from, &out, &options).unwrap());
+    assert!(compare_dir(&from, &out));
+
+    // Merges into an existing copy, so every file is moved on its own.
+    let options = options.overwrite(true);
+    copy(&from, &moved, &options).unwrap();
+    let result = move_dir_with_progress(out.join("dir"), &moved, &options, |_| {
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(16, result);
+    assert!(!out.join("dir").exists());
+    assert!(compare_dir(&from, &moved));
+
+    // A single rename.
+    assert_eq!(16, move_dir(moved.join("dir"), &out, &options).unwrap());
+    assert!(!moved.join("dir").exists());
+    assert!(compare_dir(&from, &out));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_copy_with_progress_verify_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_verify_work");
+    let from = root.join("dir");
+    let out = root.join("out");
+    create_all(&root, true).unwrap();
+    create_all(&from, false).unwrap();
+    create_all(&out, false).unwrap();
+    fs_extra::file::write_all(from.join("file1.txt"), "content1").unwrap();
+    // The read counters of `/proc/self/io` change with every read, so no copy of it matches.
+    std::os::unix::fs::symlink("/proc/self/io", from.join("io")).unwrap();
+
+    let options = CopyOptions::new().verify(fs_extra::file::Verify::Fast);
+    let mut failed = 0;
+    copy_with_progress(&from, &out, &options, |info| {
+        if info.state == TransitState::VerificationFailed {
+            failed += 1;
+            if failed == 1 {
+                return TransitProcessResult::Retry;
+            }
+            return TransitProcessResult::Skip;
+        }
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(2, failed);
+    assert!(files_eq(from.join("file1.txt"), out.join("dir/file1.txt")));
+    assert!(!out.join("dir/io").exists());
+
+    let result = copy_with_progress(&from, &out, &options.overwrite(true), |_| {
+        TransitProcessResult::Abort
+    });
+    match result {
+        Err(err) => assert!(matches!(err.kind, ErrorKind::VerificationFailed)),
+        Ok(_) => panic!("should be error"),
+    }
+}
+
+#[test]
+fn it_copy_with_progress_resume_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_resume_work");
+    let from = root.join("dir");
+    let out = root.join("out");
+    create_all(&root, true).unwrap();
+    create_all(&from, false).unwrap();
+    create_all(out.join("dir"), false).unwrap();
+    let content: Vec<u8> = (0..50_000u32).map(|i| (i % 251) as u8).collect();
+    fs::write(from.join("file1.bin"), &content).unwrap();
+    fs::write(out.join("dir/file1.bin"), &content[..30_000]).unwrap();
+
+    let options = CopyOptions::new()
+        .resume(true)
+        .reflink(fs_extra::file::Reflink::Never)
+        .buffer_size(5_000);
+    let mut first = None;
+    let result = copy_with_progress(&from, &out, &options, |info| {
+        first.get_or_insert(info.copied_bytes);
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(50_000, result);
+    assert_eq!(Some(35_000), first);
+    assert_eq!(content, fs::read(out.join("dir/file1.bin")).unwrap());
+}
+
+#[test]
+fn it_copy_with_progress_journal_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_journal_work");
+    let from = root.join("dir");
+    let out = root.join("out");
+    let journal_path = root.join("copy.journal");
+    create_all(&root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    create_all(&out, false).unwrap();
+    fs_extra::file::write_all(from.join("file1.txt"), "content1").unwrap();
+    fs_extra::file::write_all(from.join("sub/file2.txt"), "content2").unwrap();
+
+    let options = CopyOptions::new().journal(&journal_path);
+    let result = copy_with_progress(&from, &out, &options, |_| {
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(16, result);
+    assert!(compare_dir(&from, &out));
+    assert!(fs_extra::journal::pending(&journal_path)
+        .unwrap()
+        .is_empty());
+
+    // Completed files are skipped, a changed source is copied again.
+    fs_extra::file::write_all(from.join("file1.txt"), "new content1").unwrap();
+    let mut exists = Vec::new();
+    let result = copy_with_progress(&from, &out, &options, |info| {
+        if info.state == TransitState::Exists {
+            exists.push(info.file_name);
+            return TransitProcessResult::Overwrite;
+        }
+        TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(12, result);
+    assert_eq!(vec!["file1.txt".to_string()], exists);
+    assert!(compare_dir(&from, &out));
+}
+
+#[test]
+fn it_copy_with_progress_journal_keep_exist_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_with_progress_journal_keep_exist_work");
+    let from = root.join("dir");
+    let out = root.join("out");
+    let journal_path = root.join("copy.journal");
+    create_all(&root, true).unwrap();
+    create_all(&from, false).unwrap();
+    create_all(out.join("dir"), false).unwrap();
+    fs_extra::file::write_all(from.join("file1.txt"), "content1").unwrap();
+    fs_extra::file::write_all(out.join("dir/file1.txt"), "precious").unwrap();
+
+    // The existing file is refused by the first run and by every run after it.
+    let options = CopyOptions::new().journal(&journal_path);
+    for _ in 0..2 {
+        let result = copy_with_progress(&from, &out, &options, |_| {
+            TransitProcessResult::ContinueOrAbort
+        });
+        match result {
+            Err(err) => assert!(matches!(err.kind, ErrorKind::AlreadyExists)),
+            Ok(_) => panic!("should be error"),
+        }
+        assert_eq!(
+            "precious",
+            fs_extra::file::read_to_string(out.join("dir/file1.txt")).unwrap()
+        );
     }
 }
@@ -7,7 +7,7 @@ extern crate fs_extra;
 use fs_extra::error::*;
 use fs_extra::file::*;
 
-const TEST_FOLDER: &'static str = "./tests/temp/file";
+const TEST_FOLDER: &str = "./tests/temp/file";
 
 fn files_eq<P, Q>(file1: P, file2: Q) -> Result<bool>
 where
@@ -27,11 +27,11 @@ fn it_read_and_write_work() {
     fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
     let content1 = "test_1";
     let content2 = "test_2";
-    write_all(&test_file, &content1).unwrap();
+    write_all(&test_file, content1).unwrap();
     assert!(test_file.exists());
     let read1 = read_to_string(&test_file).unwrap();
     assert_eq!(content1, read1);
-    write_all(&test_file, &content2).unwrap();
+    write_all(&test_file, content2).unwrap();
     let read2 = read_to_string(&test_file).unwrap();
     assert_eq!(content2, read2);
 }
@@ -103,8 +103,8 @@ fn it_copy_work() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     assert!(test_file.exists());
@@ -125,8 +125,8 @@ fn it_copy_not_file() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     assert!(test_file.exists());
@@ -158,8 +158,8 @@ fn it_copy_source_not_exist() {
     test_file.push("test1.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     assert!(!test_file.exists());
     let options = CopyOptions::new();
@@ -173,7 +173,6 @@ fn it_copy_source_not_exist() {
                     test_file.to_str().unwrap()
                 );
                 assert_eq!(wrong_path, err.to_string());
-                ()
             }
             _ => panic!("wrong error"),
         },
@@ -188,8 +187,8 @@ fn it_copy_exist_overwrite() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -202,9 +201,8 @@ fn it_copy_exist_overwrite() {
         Ok(_) => {
             assert!(test_file.exists());
             assert_eq!(read_to_string(test_file_out).unwrap(), "test_data2");
-            ()
         }
-        Err(err) => panic!(err.to_string()),
+        Err(err) => panic!("{}", err.to_string()),
     }
 }
 
@@ -216,8 +214,8 @@ fn it_copy_exist_not_overwrite() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -231,7 +229,6 @@ fn it_copy_exist_not_overwrite() {
             let file_path = format!("Path \"{}\" exists", test_file_out.to_str().unwrap());
             assert_eq!(file_path, err.to_string());
             assert!(!files_eq(test_file, test_file_out).unwrap());
-            ()
         }
     }
 }
@@ -244,8 +241,8 @@ fn it_copy_exist_skip_exist() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -256,7 +253,6 @@ fn it_copy_exist_skip_exist() {
     match copy(&test_file, &test_file_out, &options) {
         Ok(_) => {
             assert!(!files_eq(test_file, test_file_out).unwrap());
-            ()
         }
         Err(_) => panic!("should be error"),
     }
@@ -270,8 +266,8 @@ fn it_copy_exist_overwrite_and_skip_exist() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -285,9 +281,8 @@ fn it_copy_exist_overwrite_and_skip_exist() {
         Ok(_) => {
             assert!(test_file.exists());
             assert_eq!(read_to_string(test_file_out).unwrap(), "test_data2");
-            ()
         }
-        Err(err) => panic!(err.to_string()),
+        Err(err) => panic!("{}", err.to_string()),
     }
 }
 
@@ -299,8 +294,8 @@ fn it_copy_with_progress_work() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     assert!(test_file.exists());
@@ -325,6 +320,22 @@ fn it_copy_with_progress_work() {
     }
 }
 
+#[cfg(target_os = "linux")]
+#[test]
+fn it_copy_with_progress_procfs_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_with_progress_procfs_work");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    // procfs reports a size of 0, and `copy_file_range` copies nothing from it.
+    let test_file = Path::new("/proc/version");
+    let test_file_out = test_dir.join("version");
+
+    copy_with_progress(test_file, &test_file_out, &CopyOptions::new(), |_| {}).unwrap();
+
+    let content = read_to_string(&test_file_out).unwrap();
+    assert!(!content.is_empty());
+    assert_eq!(read_to_string(test_file).unwrap(), content);
+}
+
 #[test]
 fn it_copy_progress_not_file() {
     let mut test_file = PathBuf::from(TEST_FOLDER);
@@ -333,8 +344,8 @@ fn it_copy_progress_not_file() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     assert!(test_file.exists());
@@ -367,8 +378,8 @@ fn it_copy_with_progress_work_dif_buf_size() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data_").unwrap();
     assert!(test_file.exists());
@@ -417,8 +428,8 @@ fn it_copy_with_progress_source_not_exist() {
     test_file.push("test1.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     assert!(!test_file.exists());
     let options = CopyOptions::new();
@@ -436,7 +447,6 @@ fn it_copy_with_progress_source_not_exist() {
                 );
 
                 assert_eq!(wrong_path, err.to_string());
-                ()
             }
             _ => panic!("wrong error"),
         },
@@ -451,8 +461,8 @@ fn it_copy_with_progress_exist_overwrite() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -468,9 +478,8 @@ fn it_copy_with_progress_exist_overwrite() {
         Ok(_) => {
             assert!(test_file.exists());
             assert_eq!(read_to_string(test_file_out).unwrap(), "test_data2");
-            ()
         }
-        Err(err) => panic!(err.to_string()),
+        Err(err) => panic!("{}", err.to_string()),
     }
 }
 
@@ -482,8 +491,8 @@ fn it_copy_with_progress_exist_not_overwrite() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -501,7 +510,6 @@ fn it_copy_with_progress_exist_not_overwrite() {
 
             assert_eq!(file_path, err.to_string());
             assert!(!files_eq(test_file, test_file_out).unwrap());
-            ()
         }
     }
 }
@@ -514,8 +522,8 @@ fn it_copy_with_progress_exist_skip_exist() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -529,7 +537,6 @@ fn it_copy_with_progress_exist_skip_exist() {
     match copy_with_progress(&test_file, &test_file_out, &options, func_test) {
         Ok(_) => {
             assert!(!files_eq(test_file, test_file_out).unwrap());
-            ()
         }
         Err(_) => panic!("should be error"),
     }
@@ -543,8 +550,8 @@ fn it_copy_with_progress_exist_overwrite_and_skip_exist() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -561,9 +568,8 @@ fn it_copy_with_progress_exist_overwrite_and_skip_exist() {
         Ok(_) => {
             assert!(test_file.exists());
             assert_eq!(read_to_string(test_file_out).unwrap(), "test_data2");
-            ()
         }
-        Err(err) => panic!(err.to_string()),
+        Err(err) => panic!("{}", err.to_string()),
     }
 }
 
@@ -575,8 +581,8 @@ fn it_move_work() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     assert!(test_file.exists());
@@ -600,8 +606,8 @@ fn it_move_not_file() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     assert!(test_file.exists());
@@ -633,8 +639,8 @@ fn it_move_source_not_exist() {
     test_file.push("test1.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     assert!(!test_file.exists());
     let options = CopyOptions::new();
@@ -649,7 +655,6 @@ fn it_move_source_not_exist() {
                 );
 
                 assert_eq!(wrong_path, err.to_string());
-                ()
             }
             _ => panic!("wrong error"),
         },
@@ -664,8 +669,8 @@ fn it_move_exist_overwrite() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -678,9 +683,8 @@ fn it_move_exist_overwrite() {
         Ok(_) => {
             assert!(!test_file.exists());
             assert_eq!(read_to_string(test_file_out).unwrap(), "test_data2");
-            ()
         }
-        Err(err) => panic!(err.to_string()),
+        Err(err) => panic!("{}", err.to_string()),
     }
 }
 
@@ -692,8 +696,8 @@ fn it_move_exist_not_overwrite() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -708,7 +712,6 @@ fn it_move_exist_not_overwrite() {
 
             assert_eq!(file_path, err.to_string());
             assert!(!files_eq(test_file, test_file_out).unwrap());
-            ()
         }
     }
 }
@@ -721,8 +724,8 @@ fn it_move_exist_skip_exist() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -733,7 +736,6 @@ fn it_move_exist_skip_exist() {
     match move_file(&test_file, &test_file_out, &options) {
         Ok(_) => {
             assert!(!files_eq(test_file, test_file_out).unwrap());
-            ()
         }
         Err(_) => panic!("should be error"),
     }
@@ -747,8 +749,8 @@ fn it_move_exist_overwrite_and_skip_exist() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -762,9 +764,8 @@ fn it_move_exist_overwrite_and_skip_exist() {
         Ok(_) => {
             assert!(!test_file.exists());
             assert_eq!(read_to_string(test_file_out).unwrap(), "test_data2");
-            ()
         }
-        Err(err) => panic!(err.to_string()),
+        Err(err) => panic!("{}", err.to_string()),
     }
 }
 
@@ -776,8 +777,8 @@ fn it_move_with_progress_work() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     assert!(test_file.exists());
@@ -798,11 +799,31 @@ fn it_move_with_progress_work() {
         let new_content = read_to_string(&test_file_out).unwrap();
         assert_eq!(old_content, new_content);
     });
-    for i in 1..10 {
-        let process_info: TransitProcess = rx.recv().unwrap();
-        assert_eq!(i, process_info.copied_bytes);
-        assert_eq!(9, process_info.total_bytes);
-    }
+    // The file is renamed on the same file system, so progress is reported once.
+    let process_info: TransitProcess = rx.recv().unwrap();
+    assert_eq!(9, process_info.copied_bytes);
+    assert_eq!(9, process_info.total_bytes);
+    assert!(rx.recv().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_move_rename_keep_inode() {
+    use std::os::unix::fs::MetadataExt;
+
+    let test_file = Path::new(TEST_FOLDER).join("it_move_rename_keep_inode");
+    let test_file_out = test_file.join("out").join("test.txt");
+    let test_file = test_file.join("test.txt");
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
+    write_all(&test_file, "test_data").unwrap();
+    write_all(&test_file_out, "old_data").unwrap();
+    let ino = std::fs::metadata(&test_file).unwrap().ino();
+
+    let options = CopyOptions::new().overwrite(true);
+    assert_eq!(9, move_file(&test_file, &test_file_out, &options).unwrap());
+    assert!(!test_file.exists());
+    assert_eq!("test_data", read_to_string(&test_file_out).unwrap());
+    assert_eq!(ino, std::fs::metadata(&test_file_out).unwrap().ino());
 }
 
 #[test]
@@ -813,8 +834,8 @@ fn it_move_progress_not_file() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     assert!(test_file.exists());
@@ -847,8 +868,8 @@ fn it_move_with_progress_work_dif_buf_size() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data_").unwrap();
     assert!(test_file.exists());
@@ -869,11 +890,45 @@ fn it_move_with_progress_work_dif_buf_size() {
         let new_content = read_to_string(&test_file_out).unwrap();
         assert_eq!(old_content, new_content);
     });
-    for i in 1..6 {
-        let process_info: TransitProcess = rx.recv().unwrap();
-        assert_eq!(i * 2, process_info.copied_bytes);
-        assert_eq!(10, process_info.total_bytes);
+    // The file is renamed on the same file system, so progress is reported once.
+    let process_info: TransitProcess = rx.recv().unwrap();
+    assert_eq!(10, process_info.copied_bytes);
+    assert_eq!(10, process_info.total_bytes);
+    assert!(rx.recv().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_move_with_progress_cross_device_work() {
+    use std::os::unix::fs::MetadataExt;
+
+    let test_dir = Path::new(TEST_FOLDER).join("it_move_with_progress_cross_device_work");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    // A move onto another file system falls back to copying the file and removing the source.
+    let other_fs = Path::new("/dev/shm");
+    match other_fs.metadata() {
+        Ok(meta) if meta.dev() != test_dir.metadata().unwrap().dev() => {}
+        _ => return,
     }
+    let test_file = test_dir.join("test.txt");
+    let test_file_out = other_fs.join(format!("fs_extra_{}.txt", std::process::id()));
+    write_all(&test_file, "test_data_").unwrap();
+
+    let mut options = CopyOptions::new();
+    options.buffer_size = 2;
+    let mut infos = Vec::new();
+    let result = move_file_with_progress(&test_file, &test_file_out, &options, |info| {
+        infos.push(info)
+    });
+    let content = read_to_string(&test_file_out);
+    let _ = remove(&test_file_out);
+
+    assert_eq!(10, result.unwrap());
+    assert_eq!("test_data_", content.unwrap());
+    assert!(!test_file.exists());
+    let copied: Vec<u64> = infos.iter().map(|info| info.copied_bytes).collect();
+    assert_eq!(vec![2, 4, 6, 8, 10], copied);
+    assert!(infos.iter().all(|info| info.total_bytes == 10));
 }
 
 #[test]
@@ -884,8 +939,8 @@ fn it_move_with_progress_source_not_exist() {
     test_file.push("test1.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     assert!(!test_file.exists());
     let options = CopyOptions::new();
@@ -903,7 +958,6 @@ fn it_move_with_progress_source_not_exist() {
                 );
 
                 assert_eq!(wrong_path, err.to_string());
-                ()
             }
             _ => panic!("wrong error"),
         },
@@ -918,8 +972,8 @@ fn it_move_with_progress_exist_overwrite() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -935,9 +989,8 @@ fn it_move_with_progress_exist_overwrite() {
         Ok(_) => {
             assert!(!test_file.exists());
             assert_eq!(read_to_string(test_file_out).unwrap(), "test_data2");
-            ()
         }
-        Err(err) => panic!(err.to_string()),
+        Err(err) => panic!("{}", err.to_string()),
     }
 }
 
@@ -949,8 +1002,8 @@ fn it_move_with_progress_exist_not_overwrite() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -968,7 +1021,6 @@ fn it_move_with_progress_exist_not_overwrite() {
 
             assert_eq!(file_path, err.to_string());
             assert!(!files_eq(test_file, test_file_out).unwrap());
-            ()
         }
     }
 }
@@ -981,8 +1033,8 @@ fn it_move_with_progress_exist_skip_exist() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -996,7 +1048,6 @@ fn it_move_with_progress_exist_skip_exist() {
     match move_file_with_progress(&test_file, &test_file_out, &options, func_test) {
         Ok(_) => {
             assert!(!files_eq(test_file, test_file_out).unwrap());
-            ()
         }
         Err(_) => panic!("should be error"),
     }
@@ -1010,8 +1061,8 @@ fn it_move_with_progress_exist_overwrite_and_skip_exist() {
     test_file.push("test.txt");
     test_file_out.push("out");
     test_file_out.push("test.txt");
-    fs_extra::dir::create_all(&test_file.parent().unwrap(), true).unwrap();
-    fs_extra::dir::create_all(&test_file_out.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file.parent().unwrap(), true).unwrap();
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
 
     write_all(&test_file, "test_data").unwrap();
     let mut options = CopyOptions::new();
@@ -1029,8 +1080,676 @@ fn it_move_with_progress_exist_overwrite_and_skip_exist() {
             assert!(!test_file.exists());
             assert!(test_file_out.exists());
             assert_eq!(read_to_string(test_file_out).unwrap(), "test_data2");
-            ()
         }
-        Err(err) => panic!(err.to_string()),
+        Err(err) => panic!("{}", err.to_string()),
+    }
+}
+
+fn set_old_times(path: &Path) -> std::time::SystemTime {
+    let time = std::time::UNIX_EPOCH + std::time::Duration::new(1_000_000_000, 123_456_789);
+    let times = std::fs::FileTimes::new()
+        .set_accessed(time)
+        .set_modified(time);
+    std::fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_times(times)
+        .unwrap();
+    time
+}
+
+#[test]
+fn it_copy_preserve_times_work() {
+    let test_file = Path::new(TEST_FOLDER).join("it_copy_preserve_times_work");
+    let test_file_out = test_file.join("out").join("test.txt");
+    let test_file = test_file.join("test.txt");
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
+    write_all(&test_file, "test_data").unwrap();
+    let time = set_old_times(&test_file);
+
+    let options = CopyOptions::new().preserve_times(true);
+    copy(&test_file, &test_file_out, &options).unwrap();
+    let metadata = std::fs::metadata(&test_file_out).unwrap();
+    assert_eq!(time, metadata.modified().unwrap());
+    assert_eq!(time, metadata.accessed().unwrap());
+
+    let options = CopyOptions::new().overwrite(true);
+    copy(&test_file, &test_file_out, &options).unwrap();
+    let metadata = std::fs::metadata(&test_file_out).unwrap();
+    assert_ne!(time, metadata.modified().unwrap());
+}
+
+#[test]
+fn it_copy_with_progress_preserve_times_work() {
+    let test_file = Path::new(TEST_FOLDER).join("it_copy_with_progress_preserve_times_work");
+    let test_file_out = test_file.join("out").join("test.txt");
+    let test_file = test_file.join("test.txt");
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
+    write_all(&test_file, "test_data").unwrap();
+    let time = set_old_times(&test_file);
+
+    let options = CopyOptions::new().preserve_times(true).buffer_size(2);
+    copy_with_progress(&test_file, &test_file_out, &options, |_| {}).unwrap();
+    let metadata = std::fs::metadata(&test_file_out).unwrap();
+    assert_eq!(time, metadata.modified().unwrap());
+    assert_eq!(time, metadata.accessed().unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_preserve_owner_work() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let test_file = Path::new(TEST_FOLDER).join("it_copy_preserve_owner_work");
+    let test_file_out = test_file.join("out").join("test.txt");
+    let test_file = test_file.join("test.txt");
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
+    write_all(&test_file, "test_data").unwrap();
+    std::fs::set_permissions(&test_file, std::fs::Permissions::from_mode(0o6755)).unwrap();
+    let metadata = std::fs::metadata(&test_file).unwrap();
+    let is_root = metadata.uid() == 0;
+
+    let options = CopyOptions::new()
+        .preserve_owner(true)
+        .owner_map(|uid, gid| (uid + 1234, gid + 1234));
+    copy(&test_file, &test_file_out, &options).unwrap();
+    let out_metadata = std::fs::metadata(&test_file_out).unwrap();
+    if is_root {
+        assert_eq!(1234, out_metadata.uid());
+        assert_eq!(1234, out_metadata.gid());
+        assert_eq!(0o6755, out_metadata.permissions().mode() & 0o7777);
+    } else {
+        assert_eq!(metadata.uid(), out_metadata.uid());
+    }
+
+    let options = options.overwrite(true).strict_owner(true);
+    let result = copy_with_progress(&test_file, &test_file_out, &options, |_| {});
+    if is_root {
+        result.unwrap();
+        assert_eq!(1234, std::fs::metadata(&test_file_out).unwrap().uid());
+    } else {
+        match result.unwrap_err().kind {
+            ErrorKind::PermissionDenied => {}
+            _ => panic!("wrong error"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_get_set_xattrs_work() {
+    let test_file = Path::new(TEST_FOLDER).join("it_get_set_xattrs_work.txt");
+    write_all(&test_file, "test_data").unwrap();
+
+    set_xattrs(
+        &test_file,
+        vec![("user.origin", "build-42"), ("user.empty", "")],
+    )
+    .unwrap();
+    let mut xattrs = get_xattrs(&test_file).unwrap();
+    xattrs.sort();
+    assert_eq!(
+        vec![
+            ("user.empty".into(), Vec::new()),
+            ("user.origin".into(), b"build-42".to_vec()),
+        ],
+        xattrs
+    );
+
+    match set_xattrs(&test_file, vec![("invalid", "value")]) {
+        Ok(_) => panic!("should be error"),
+        Err(err) => match err.kind {
+            ErrorKind::Io(_) => {}
+            _ => panic!("wrong error"),
+        },
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_copy_preserve_xattrs_work() {
+    use std::os::unix::fs::MetadataExt;
+
+    let test_file = Path::new(TEST_FOLDER).join("it_copy_preserve_xattrs_work");
+    let test_file_out = test_file.join("out").join("test.txt");
+    let test_file = test_file.join("test.txt");
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
+    write_all(&test_file, "test_data").unwrap();
+    set_xattrs(&test_file, vec![("user.origin", "build-42")]).unwrap();
+    // Only privileged processes can use the trusted namespace.
+    let is_root = std::fs::metadata(&test_file).unwrap().uid() == 0;
+    if is_root {
+        set_xattrs(&test_file, vec![("trusted.origin", "build-43")]).unwrap();
+    }
+    let names = |path: &Path| {
+        let mut names: Vec<_> = get_xattrs(path)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        names
+    };
+
+    let options = CopyOptions::new();
+    copy(&test_file, &test_file_out, &options).unwrap();
+    assert!(get_xattrs(&test_file_out).unwrap().is_empty());
+
+    let options = options.overwrite(true).preserve_xattrs(true);
+    copy(&test_file, &test_file_out, &options).unwrap();
+    assert_eq!(
+        vec![("user.origin".into(), b"build-42".to_vec())],
+        get_xattrs(&test_file_out).unwrap()
+    );
+
+    if is_root {
+        let options = options.xattr_namespaces(&["user", "trusted"]);
+        remove(&test_file_out).unwrap();
+        copy_with_progress(&test_file, &test_file_out, &options, |_| {}).unwrap();
+        assert_eq!(names(&test_file), names(&test_file_out));
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_copy_sparse_work() {
+    use std::io::{Seek, SeekFrom, Write};
+    use std::os::unix::fs::MetadataExt;
+
+    let test_file = Path::new(TEST_FOLDER).join("it_copy_sparse_work");
+    let test_file_out = test_file.join("out").join("test.bin");
+    let test_file_out2 = test_file.join("out").join("test2.bin");
+    let test_file = test_file.join("test.bin");
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
+    let len = 32 * 1024 * 1024;
+    {
+        let mut file = std::fs::File::create(&test_file).unwrap();
+        file.write_all(b"head").unwrap();
+        file.seek(SeekFrom::Start(len / 2)).unwrap();
+        file.write_all(b"middle").unwrap();
+        file.set_len(len).unwrap();
+    }
+
+    let options = CopyOptions::new().sparse(true);
+    assert_eq!(len, copy(&test_file, &test_file_out, &options).unwrap());
+    let mut progress = Vec::new();
+    let result = copy_with_progress(&test_file, &test_file_out2, &options, |info| {
+        progress.push((info.copied_bytes, info.total_bytes));
+    })
+    .unwrap();
+
+    assert_eq!(len, result);
+    let (copied_bytes, total_bytes) = *progress.last().unwrap();
+    assert_eq!(copied_bytes, total_bytes);
+    assert!(total_bytes < len / 4);
+    let content = std::fs::read(&test_file).unwrap();
+    for path in [&test_file_out, &test_file_out2] {
+        let metadata = std::fs::metadata(path).unwrap();
+        assert_eq!(len, metadata.len());
+        assert!(metadata.blocks() * 512 < len / 4);
+        assert!(content == std::fs::read(path).unwrap());
+    }
+}
+
+#[test]
+fn it_copy_with_progress_reflink_work() {
+    let test_file = Path::new(TEST_FOLDER).join("it_copy_with_progress_reflink_work");
+    let test_file_out = test_file.join("out").join("test.txt");
+    let test_file = test_file.join("test.txt");
+    fs_extra::dir::create_all(test_file_out.parent().unwrap(), true).unwrap();
+    write_all(&test_file, "test_data_for_chunks!!").unwrap();
+
+    for reflink in [Reflink::Auto, Reflink::Never] {
+        let options = CopyOptions::new()
+            .overwrite(true)
+            .buffer_size(5)
+            .reflink(reflink);
+        let mut progress = Vec::new();
+        let result = copy_with_progress(&test_file, &test_file_out, &options, |info| {
+            progress.push(info.copied_bytes);
+        })
+        .unwrap();
+        assert_eq!(22, result);
+        assert_eq!(22, *progress.last().unwrap());
+        assert!(progress.len() == 1 || progress == vec![5, 10, 15, 20, 22]);
+        assert_eq!(
+            read_to_string(&test_file).unwrap(),
+            read_to_string(&test_file_out).unwrap()
+        );
+    }
+
+    // Whether a reflink is possible depends on the file system of the test folder.
+    let options = CopyOptions::new().overwrite(true).reflink(Reflink::Always);
+    match copy(&test_file, &test_file_out, &options) {
+        Ok(result) => {
+            assert_eq!(22, result);
+            assert_eq!(
+                read_to_string(&test_file).unwrap(),
+                read_to_string(&test_file_out).unwrap()
+            );
+        }
+        Err(err) => match err.kind {
+            ErrorKind::Io(_) => {}
+            _ => panic!("wrong error"),
+        },
+    }
+}
+
+#[test]
+fn it_copy_overwrite_policy_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_overwrite_policy_work");
+    let test_file = test_dir.join("test.txt");
+    let test_file_out = test_dir.join("out.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    let prepare = |from: &str, to: &str, to_is_newer: bool| {
+        write_all(&test_file, from).unwrap();
+        write_all(&test_file_out, to).unwrap();
+        let old = set_old_times(if to_is_newer {
+            &test_file
+        } else {
+            &test_file_out
+        });
+        assert!(old < std::time::SystemTime::now());
+    };
+    let copied = |policy: OverwritePolicy| {
+        let options = CopyOptions::new().overwrite_policy(policy);
+        copy(&test_file, &test_file_out, &options).unwrap() > 0
+    };
+
+    prepare("source", "dest", false);
+    match copy(&test_file, &test_file_out, &CopyOptions::new()) {
+        Ok(_) => panic!("should be error"),
+        Err(err) => match err.kind {
+            ErrorKind::AlreadyExists => {}
+            _ => panic!("wrong error"),
+        },
+    }
+    assert!(!copied(OverwritePolicy::Skip));
+    assert!(copied(OverwritePolicy::Always));
+    assert_eq!("source", read_to_string(&test_file_out).unwrap());
+
+    prepare("source", "dest", true);
+    assert!(!copied(OverwritePolicy::IfNewer));
+    assert!(copied(OverwritePolicy::IfSizeDiffers));
+    prepare("source", "source", true);
+    assert!(!copied(OverwritePolicy::IfSizeDiffers));
+    assert!(!copied(OverwritePolicy::IfContentDiffers));
+    assert!(!copied(OverwritePolicy::IfNewerOrSizeDiffers));
+    prepare("source", "sauce!", true);
+    assert!(copied(OverwritePolicy::IfContentDiffers));
+    prepare("source", "source", false);
+    assert!(copied(OverwritePolicy::IfNewer));
+    prepare("source", "source", false);
+    assert!(copied(OverwritePolicy::IfNewerOrSizeDiffers));
+
+    // The booleans are shorthands for Always and Skip.
+    prepare("source", "dest", true);
+    let options = CopyOptions::new()
+        .skip_exist(true)
+        .overwrite_policy(OverwritePolicy::IfSizeDiffers);
+    assert_eq!(6, copy(&test_file, &test_file_out, &options).unwrap());
+    prepare("source", "dest", true);
+    let options = CopyOptions::new()
+        .overwrite(true)
+        .overwrite_policy(OverwritePolicy::IfNewer);
+    assert_eq!(6, copy(&test_file, &test_file_out, &options).unwrap());
+}
+
+#[test]
+fn it_move_overwrite_policy_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_move_overwrite_policy_work");
+    let test_file = test_dir.join("test.txt");
+    let test_file_out = test_dir.join("out.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    write_all(&test_file, "source").unwrap();
+    write_all(&test_file_out, "source").unwrap();
+
+    let options = CopyOptions::new().overwrite_policy(OverwritePolicy::IfContentDiffers);
+    assert_eq!(0, move_file(&test_file, &test_file_out, &options).unwrap());
+    assert!(test_file.exists());
+
+    write_all(&test_file, "content").unwrap();
+    let result = move_file_with_progress(&test_file, &test_file_out, &options, |_| {}).unwrap();
+    assert_eq!(7, result);
+    assert!(!test_file.exists());
+    assert_eq!("content", read_to_string(&test_file_out).unwrap());
+}
+
+#[test]
+fn it_copy_rename_policy_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_rename_policy_work");
+    let test_file = test_dir.join("test.txt");
+    let test_file_out = test_dir.join("out.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    write_all(&test_file, "source").unwrap();
+    write_all(&test_file_out, "dest").unwrap();
+
+    let options = CopyOptions::new().overwrite_policy(OverwritePolicy::Rename);
+    assert_eq!(6, copy(&test_file, &test_file_out, &options).unwrap());
+    assert_eq!("dest", read_to_string(&test_file_out).unwrap());
+    assert_eq!(
+        "source",
+        read_to_string(test_dir.join("out (1).txt")).unwrap()
+    );
+
+    let mut renamed_to = Vec::new();
+    let result = copy_with_progress(&test_file, &test_file_out, &options, |info| {
+        renamed_to.push(info.renamed_to)
+    })
+    .unwrap();
+    assert_eq!(6, result);
+    assert!(test_dir.join("out (2).txt").exists());
+    assert_eq!(Some(&Some(test_dir.join("out (2).txt"))), renamed_to.last());
+
+    let no_extension = test_dir.join("out");
+    write_all(&no_extension, "dest").unwrap();
+    assert_eq!(6, move_file(&test_file, &no_extension, &options).unwrap());
+    assert!(!test_file.exists());
+    assert_eq!("source", read_to_string(test_dir.join("out (1)")).unwrap());
+    assert_eq!("dest", read_to_string(&no_extension).unwrap());
+
+    write_all(&test_file, "source").unwrap();
+    let mut renamed_to = Vec::new();
+    move_file_with_progress(&test_file, &no_extension, &options, |info| {
+        renamed_to.push(info.renamed_to)
+    })
+    .unwrap();
+    assert_eq!(vec![Some(test_dir.join("out (2)"))], renamed_to);
+
+    let options = CopyOptions::new().overwrite(true);
+    copy_with_progress(&test_file_out, &no_extension, &options, |info| {
+        assert_eq!(None, info.renamed_to)
+    })
+    .unwrap();
+}
+
+#[test]
+fn it_copy_backup_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_backup_work");
+    let test_file = test_dir.join("test.txt");
+    let test_file_out = test_dir.join("out.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    write_all(&test_file, "source").unwrap();
+    write_all(&test_file_out, "old1").unwrap();
+
+    let options = CopyOptions::new().overwrite(true).backup(Backup::Simple);
+    assert_eq!(6, copy(&test_file, &test_file_out, &options).unwrap());
+    assert_eq!("source", read_to_string(&test_file_out).unwrap());
+    assert_eq!("old1", read_to_string(test_dir.join("out.txt~")).unwrap());
+
+    // Without numbered backups `Existing` makes a simple one.
+    write_all(&test_file_out, "old2").unwrap();
+    let options = options.backup(Backup::Existing).backup_suffix(".bak");
+    copy(&test_file, &test_file_out, &options).unwrap();
+    assert_eq!(
+        "old2",
+        read_to_string(test_dir.join("out.txt.bak")).unwrap()
+    );
+
+    let options = options.backup(Backup::Numbered);
+    let mut backups = Vec::new();
+    for _ in 0..2 {
+        copy_with_progress(&test_file, &test_file_out, &options, |info| {
+            backups.push(info.backup)
+        })
+        .unwrap();
+    }
+    assert!(backups.contains(&Some(test_dir.join("out.txt.~1~"))));
+    assert!(backups.contains(&Some(test_dir.join("out.txt.~2~"))));
+
+    write_all(&test_file_out, "old3").unwrap();
+    let options = options.backup(Backup::Existing);
+    copy(&test_file, &test_file_out, &options).unwrap();
+    assert_eq!(
+        "old3",
+        read_to_string(test_dir.join("out.txt.~3~")).unwrap()
+    );
+
+    // Nothing is backed up if the destination is kept.
+    let options = CopyOptions::new().skip_exist(true).backup(Backup::Numbered);
+    assert_eq!(0, copy(&test_file, &test_file_out, &options).unwrap());
+    assert!(!test_dir.join("out.txt.~4~").exists());
+}
+
+#[test]
+fn it_move_backup_dir_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_move_backup_dir_work");
+    let test_file = test_dir.join("test.txt");
+    let test_file_out = test_dir.join("out.txt");
+    let backup_dir = test_dir.join("backups");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    write_all(&test_file, "source").unwrap();
+    write_all(&test_file_out, "old").unwrap();
+
+    let options = CopyOptions::new()
+        .overwrite(true)
+        .backup(Backup::Simple)
+        .backup_dir(&backup_dir);
+    let mut backup = None;
+    let result = move_file_with_progress(&test_file, &test_file_out, &options, |info| {
+        backup = info.backup
+    })
+    .unwrap();
+    assert_eq!(6, result);
+    assert!(!test_file.exists());
+    assert_eq!(Some(backup_dir.join("out.txt~")), backup);
+    assert_eq!("old", read_to_string(backup_dir.join("out.txt~")).unwrap());
+    assert_eq!("source", read_to_string(&test_file_out).unwrap());
+}
+
+// Names of the files in `dir`.
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn it_write_all_atomic_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_write_all_atomic_work");
+    let test_file = test_dir.join("config.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+
+    write_all_atomic(&test_file, "first").unwrap();
+    assert_eq!("first", read_to_string(&test_file).unwrap());
+    write_all_atomic(&test_file, "second").unwrap();
+    assert_eq!("second", read_to_string(&test_file).unwrap());
+    assert_eq!(vec!["config.txt"], file_names(&test_dir));
+
+    match write_all_atomic(&test_dir, "content") {
+        Err(err) => match err.kind {
+            ErrorKind::InvalidFile => {}
+            _ => panic!("wrong error"),
+        },
+        Ok(_) => panic!("should be error"),
+    }
+}
+
+#[test]
+fn it_copy_atomic_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_atomic_work");
+    let test_file = test_dir.join("test.txt");
+    let test_file_out = test_dir.join("out.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    write_all(&test_file, "new content").unwrap();
+    write_all(&test_file_out, "old").unwrap();
+
+    let options = CopyOptions::new()
+        .overwrite(true)
+        .atomic(true)
+        .reflink(Reflink::Never)
+        .buffer_size(2);
+    let mut progress_calls = 0;
+    let result = copy_with_progress(&test_file, &test_file_out, &options, |_| {
+        progress_calls += 1;
+        // The destination is replaced only once the copy is complete.
+        assert_eq!("old", read_to_string(&test_file_out).unwrap());
+    })
+    .unwrap();
+    assert_eq!(11, result);
+    assert!(progress_calls > 1);
+    assert_eq!("new content", read_to_string(&test_file_out).unwrap());
+
+    write_all(&test_file, "other content").unwrap();
+    assert_eq!(13, copy(&test_file, &test_file_out, &options).unwrap());
+    assert_eq!("other content", read_to_string(&test_file_out).unwrap());
+    assert_eq!(vec!["out.txt", "test.txt"], file_names(&test_dir));
+}
+
+#[cfg(unix)]
+#[test]
+fn it_copy_with_progress_permissions_work() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_with_progress_permissions_work");
+    let test_file = test_dir.join("test.txt");
+    let test_file_out = test_dir.join("out.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    write_all(&test_file, "content").unwrap();
+    std::fs::set_permissions(&test_file, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+    for (sparse, atomic) in [(true, false), (false, true)] {
+        write_all(&test_file_out, "old").unwrap();
+        std::fs::set_permissions(&test_file_out, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let options = CopyOptions::new()
+            .overwrite(true)
+            .sparse(sparse)
+            .atomic(atomic);
+        copy_with_progress(&test_file, &test_file_out, &options, |_| {}).unwrap();
+        let mode = std::fs::metadata(&test_file_out)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(0o750, mode & 0o777);
+    }
+}
+
+#[test]
+fn it_copy_and_move_durability_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_and_move_durability_work");
+    let test_file = test_dir.join("test.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    write_all(&test_file, "content").unwrap();
+
+    for durability in [
+        Durability::None,
+        Durability::FileData,
+        Durability::FileAndDirectory,
+    ] {
+        let options = CopyOptions::new().overwrite(true).durability(durability);
+        let copied = test_dir.join("copied.txt");
+        assert_eq!(7, copy(&test_file, &copied, &options).unwrap());
+        let atomic_options = options.clone().atomic(true);
+        assert_eq!(7, copy(&test_file, &copied, &atomic_options).unwrap());
+        let result = copy_with_progress(&test_file, &copied, &options, |_| {}).unwrap();
+        assert_eq!(7, result);
+
+        let moved = test_dir.join("moved.txt");
+        assert_eq!(7, move_file(&copied, &moved, &options).unwrap());
+        assert!(!copied.exists());
+        let result = move_file_with_progress(&moved, &copied, &options, |_| {}).unwrap();
+        assert_eq!(7, result);
+        assert!(!moved.exists());
+        assert_eq!("content", read_to_string(&copied).unwrap());
+    }
+}
+
+#[test]
+fn it_copy_verify_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_verify_work");
+    let test_file = test_dir.join("test.txt");
+    let test_file_out = test_dir.join("out.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    write_all(&test_file, "content").unwrap();
+
+    for verify in [
+        Verify::Fast,
+        #[cfg(feature = "sha256")]
+        Verify::Sha256,
+    ] {
+        let options = CopyOptions::new().overwrite(true).verify(verify);
+        assert_eq!(7, copy(&test_file, &test_file_out, &options).unwrap());
+        let options = options.reflink(Reflink::Never);
+        let result = copy_with_progress(&test_file, &test_file_out, &options, |_| {}).unwrap();
+        assert_eq!(7, result);
+        assert!(files_eq(&test_file, &test_file_out).unwrap());
+    }
+}
+
+// The read counters of `/proc/self/io` change with every read, so no copy of it matches.
+#[cfg(target_os = "linux")]
+#[test]
+fn it_copy_verify_mismatch_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_verify_mismatch_work");
+    let test_file_out = test_dir.join("out.txt");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+
+    let options = CopyOptions::new().verify(Verify::Fast);
+    for options in [options.clone(), options.atomic(true)] {
+        match copy("/proc/self/io", &test_file_out, &options) {
+            Err(err) => assert!(matches!(err.kind, ErrorKind::VerificationFailed)),
+            Ok(_) => panic!("should be error"),
+        }
+        assert!(!test_file_out.exists());
+        assert_eq!(
+            0,
+            fs_extra::dir::get_dir_content(&test_dir)
+                .unwrap()
+                .files
+                .len()
+        );
+    }
+}
+
+#[test]
+fn it_copy_resume_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_resume_work");
+    let test_file = test_dir.join("test.bin");
+    let test_file_out = test_dir.join("out.bin");
+    fs_extra::dir::create_all(&test_dir, true).unwrap();
+    let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&test_file, &content).unwrap();
+
+    // Only the last block of the destination is compared, so a difference before it shows
+    // that the destination was continued instead of copied again.
+    let mut partial = content[..150_000].to_vec();
+    partial[0] = 255;
+    std::fs::write(&test_file_out, &partial).unwrap();
+    let options = CopyOptions::new().resume(true);
+    assert_eq!(200_000, copy(&test_file, &test_file_out, &options).unwrap());
+    let copied = std::fs::read(&test_file_out).unwrap();
+    assert_eq!(255, copied[0]);
+    assert_eq!(content[1..], copied[1..]);
+
+    let options = options.reflink(Reflink::Never).buffer_size(10_000);
+    std::fs::write(&test_file_out, &content[..100_000]).unwrap();
+    let mut first = None;
+    let result = copy_with_progress(&test_file, &test_file_out, &options, |info| {
+        first.get_or_insert(info.copied_bytes);
+    })
+    .unwrap();
+    assert_eq!(200_000, result);
+    assert_eq!(Some(110_000), first);
+    assert_eq!(content, std::fs::read(&test_file_out).unwrap());
+
+    // A destination which does not match the source is handled by the overwrite policy.
+    let mut partial = content[..100_000].to_vec();
+    partial[99_999] = 255;
+    std::fs::write(&test_file_out, &partial).unwrap();
+    match copy_with_progress(&test_file, &test_file_out, &options, |_| {}) {
+        Err(err) => assert!(matches!(err.kind, ErrorKind::AlreadyExists)),
+        Ok(_) => panic!("should be error"),
     }
+    let mut first = None;
+    let options = options.overwrite(true);
+    copy_with_progress(&test_file, &test_file_out, &options, |info| {
+        first.get_or_insert(info.copied_bytes);
+    })
+    .unwrap();
+    assert_eq!(Some(10_000), first);
+    assert_eq!(content, std::fs::read(&test_file_out).unwrap());
 }
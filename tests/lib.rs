@@ -23,7 +23,7 @@ where
     Q: AsRef<Path>,
 {
     let mut path_to = path_to.as_ref().to_path_buf();
-    match path_from.as_ref().components().last() {
+    match path_from.as_ref().components().next_back() {
         None => panic!("Invalid folder from"),
         Some(dir_name) => {
             path_to.push(dir_name.as_os_str());
@@ -46,9 +46,7 @@ where
                 None => panic!("No file name"),
                 Some(file_name) => {
                     path_to.push(file_name);
-                    if !path_to.exists() {
-                        return false;
-                    } else if !files_eq(&path, path_to.clone()) {
+                    if !path_to.exists() || !files_eq(&path, path_to.clone()) {
                         return false;
                     }
                 }
@@ -59,7 +57,7 @@ where
     true
 }
 
-const TEST_FOLDER: &'static str = "./tests/temp/lib";
+const TEST_FOLDER: &str = "./tests/temp/lib";
 
 #[test]
 fn it_copy_work() {
@@ -74,10 +72,7 @@ fn it_copy_work() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
     dir::create_all(&sub.0, true).unwrap();
@@ -106,11 +101,12 @@ fn it_copy_work() {
     assert!(!file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let options = dir::CopyOptions::new();
     let result = copy_items(&from_paths, &path_to, &options).unwrap();
@@ -131,10 +127,7 @@ fn it_copy_source_not_exist() {
     let sub = (dir1.0.join("sub"), dir1.1.join("sub"));
     let file1 = (test_dir.join("file1.txt"), path_to.join("file1.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     assert!(!dir1.0.exists());
     assert!(!dir1.1.exists());
@@ -146,18 +139,12 @@ fn it_copy_source_not_exist() {
     assert!(!file1.0.exists());
     assert!(!file1.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
+    let from_paths = vec![dir1.0.as_path(), dir2.0.as_path(), file1.0.as_path()];
 
     let options = dir::CopyOptions::new();
     match copy_items(&from_paths, &path_to, &options) {
         Ok(_) => panic!("Should be a error!"),
-        Err(err) => match err.kind {
-            ErrorKind::NotFound => {}
-            _ => {}
-        },
+        Err(err) => assert!(matches!(err.kind, ErrorKind::NotFound)),
     };
 }
 
@@ -174,10 +161,7 @@ fn it_copy_exist_overwrite() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -212,11 +196,12 @@ fn it_copy_exist_overwrite() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let mut options = dir::CopyOptions::new();
     options.overwrite = true;
@@ -242,10 +227,7 @@ fn it_copy_exist_not_overwrite() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -280,18 +262,19 @@ fn it_copy_exist_not_overwrite() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let options = dir::CopyOptions::new();
     match copy_items(&from_paths, &path_to, &options) {
         Ok(_) => panic!("Should be a error!"),
         Err(err) => match err.kind {
             ErrorKind::AlreadyExists => {}
-            _ => panic!(format!("{}", err.to_string())),
+            _ => panic!("{}", err),
         },
     };
 }
@@ -309,10 +292,7 @@ fn it_copy_exist_skip() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -347,11 +327,12 @@ fn it_copy_exist_skip() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let mut options = dir::CopyOptions::new();
     options.skip_exist = true;
@@ -377,10 +358,7 @@ fn it_copy_exist_overwrite_and_skip_exist() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -415,11 +393,12 @@ fn it_copy_exist_overwrite_and_skip_exist() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let mut options = dir::CopyOptions::new();
     options.overwrite = true;
@@ -581,10 +560,11 @@ fn it_copy_using_first_levels() {
 
     assert!(!file31.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(d_level_1.0.as_path());
-    from_paths.push(d2_level_1.0.as_path());
-    from_paths.push(d3_level_1.0.as_path());
+    let from_paths = vec![
+        d_level_1.0.as_path(),
+        d2_level_1.0.as_path(),
+        d3_level_1.0.as_path(),
+    ];
 
     let mut options = dir::CopyOptions::new();
     options.depth = 1;
@@ -772,10 +752,11 @@ fn it_copy_using_four_levels() {
 
     assert!(!file31.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(d_level_1.0.as_path());
-    from_paths.push(d2_level_1.0.as_path());
-    from_paths.push(d3_level_1.0.as_path());
+    let from_paths = vec![
+        d_level_1.0.as_path(),
+        d2_level_1.0.as_path(),
+        d3_level_1.0.as_path(),
+    ];
 
     let mut options = dir::CopyOptions::new();
     options.depth = 4;
@@ -824,13 +805,13 @@ fn it_copy_content_only_opton() {
 
     let mut options = dir::CopyOptions::new();
     options.content_only = true;
-    match copy_items(&vec![&file1.0], &file1.1, &options) {
+    match copy_items(&[&file1.0], &file1.1, &options) {
         Err(err) => match err.kind {
             ErrorKind::Other => {
                 assert_eq!(1, 1);
             }
             _ => {
-                panic!(format!("wrong error {}", err.to_string()));
+                panic!("wrong error {}", err);
             }
         },
         Ok(_) => {
@@ -852,10 +833,7 @@ fn it_copy_progress_work() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
     dir::create_all(&sub.0, true).unwrap();
@@ -887,11 +865,12 @@ fn it_copy_progress_work() {
     let options = dir::CopyOptions::new();
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -927,7 +906,7 @@ fn it_copy_progress_work() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -944,10 +923,7 @@ fn it_copy_with_progress_work_dif_buf_size() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
     dir::create_all(&sub.0, true).unwrap();
@@ -980,11 +956,12 @@ fn it_copy_with_progress_work_dif_buf_size() {
     options.buffer_size = 1;
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(file1.0.as_path().to_str().unwrap().to_string());
-        from_paths.push(file2.0.as_path().to_str().unwrap().to_string());
-        from_paths.push(dir1.0.as_path().to_str().unwrap().to_string());
-        from_paths.push(dir2.0.as_path().to_str().unwrap().to_string());
+        let from_paths = vec![
+            file1.0.as_path().to_str().unwrap().to_string(),
+            file2.0.as_path().to_str().unwrap().to_string(),
+            dir1.0.as_path().to_str().unwrap().to_string(),
+            dir2.0.as_path().to_str().unwrap().to_string(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -1035,7 +1012,7 @@ fn it_copy_with_progress_work_dif_buf_size() {
 
         match result {
             Ok(_) => {}
-            Err(err) => panic!(err),
+            Err(err) => std::panic::resume_unwind(err),
         }
     })
     .join();
@@ -1057,7 +1034,7 @@ fn it_copy_with_progress_work_dif_buf_size() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -1070,10 +1047,7 @@ fn it_copy_with_progress_source_not_exist() {
     let sub = (dir1.0.join("sub"), dir1.1.join("sub"));
     let file1 = (test_dir.join("file1.txt"), path_to.join("file1.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     assert!(!dir1.0.exists());
     assert!(!dir1.1.exists());
@@ -1085,10 +1059,7 @@ fn it_copy_with_progress_source_not_exist() {
     assert!(!file1.0.exists());
     assert!(!file1.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
+    let from_paths = vec![dir1.0.as_path(), dir2.0.as_path(), file1.0.as_path()];
 
     let func_test = |process_info: TransitProcess| {
         println!("{}", process_info.total_bytes);
@@ -1098,10 +1069,7 @@ fn it_copy_with_progress_source_not_exist() {
     let options = dir::CopyOptions::new();
     match copy_items_with_progress(&from_paths, &path_to, &options, func_test) {
         Ok(_) => panic!("Should be a error!"),
-        Err(err) => match err.kind {
-            ErrorKind::NotFound => {}
-            _ => {}
-        },
+        Err(err) => assert!(matches!(err.kind, ErrorKind::NotFound)),
     };
 }
 
@@ -1118,10 +1086,7 @@ fn it_copy_with_progress_exist_overwrite() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -1160,11 +1125,12 @@ fn it_copy_with_progress_exist_overwrite() {
     options.overwrite = true;
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -1183,12 +1149,11 @@ fn it_copy_with_progress_exist_overwrite() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -1205,10 +1170,7 @@ fn it_copy_with_progress_exist_not_overwrite() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -1243,11 +1205,12 @@ fn it_copy_with_progress_exist_not_overwrite() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let options = dir::CopyOptions::new();
     let func_test = |process_info: TransitProcess| {
@@ -1259,7 +1222,7 @@ fn it_copy_with_progress_exist_not_overwrite() {
         Ok(_) => panic!("Should be a error!"),
         Err(err) => match err.kind {
             ErrorKind::AlreadyExists => {}
-            _ => panic!(format!("{}", err.to_string())),
+            _ => panic!("{}", err),
         },
     };
 }
@@ -1277,10 +1240,7 @@ fn it_copy_with_progress_exist_skip_exist() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -1319,11 +1279,12 @@ fn it_copy_with_progress_exist_skip_exist() {
     options.skip_exist = true;
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -1342,12 +1303,11 @@ fn it_copy_with_progress_exist_skip_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -1365,10 +1325,7 @@ fn it_copy_with_progress_exist_overwrite_and_skip_exist() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -1408,11 +1365,12 @@ fn it_copy_with_progress_exist_overwrite_and_skip_exist() {
     options.skip_exist = true;
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -1431,12 +1389,11 @@ fn it_copy_with_progress_exist_overwrite_and_skip_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -1592,10 +1549,11 @@ fn it_copy_with_progress_using_first_levels() {
     options.depth = 1;
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(d_level_1.0.as_path());
-        from_paths.push(d2_level_1.0.as_path());
-        from_paths.push(d3_level_1.0.as_path());
+        let from_paths = vec![
+            d_level_1.0.as_path(),
+            d2_level_1.0.as_path(),
+            d3_level_1.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -1641,12 +1599,11 @@ fn it_copy_with_progress_using_first_levels() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -1802,10 +1759,11 @@ fn it_copy_with_progress_using_four_levels() {
     options.depth = 4;
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(d_level_1.0.as_path());
-        from_paths.push(d2_level_1.0.as_path());
-        from_paths.push(d3_level_1.0.as_path());
+        let from_paths = vec![
+            d_level_1.0.as_path(),
+            d2_level_1.0.as_path(),
+            d3_level_1.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -1851,12 +1809,11 @@ fn it_copy_with_progress_using_four_levels() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -1873,11 +1830,11 @@ fn it_copy_with_progress_content_only_opton() {
         println!("{}", process_info.total_bytes);
         dir::TransitProcessResult::ContinueOrAbort
     };
-    match copy_items_with_progress(&vec![&file1.0], &file1.1, &options, func_test) {
+    match copy_items_with_progress(&[&file1.0], &file1.1, &options, func_test) {
         Ok(_) => panic!("Should be a error!"),
         Err(err) => match err.kind {
             ErrorKind::Other => {}
-            _ => panic!(format!("wrong error {}", err.to_string())),
+            _ => panic!("wrong error {}", err),
         },
     };
 }
@@ -1895,10 +1852,7 @@ fn it_move_work() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
     dir::create_all(&sub.0, true).unwrap();
@@ -1927,11 +1881,12 @@ fn it_move_work() {
     assert!(!file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let options = dir::CopyOptions::new();
     let result = move_items(&from_paths, &path_to, &options).unwrap();
@@ -1958,10 +1913,7 @@ fn it_move_source_not_exist() {
     let sub = (dir1.0.join("sub"), dir1.1.join("sub"));
     let file1 = (test_dir.join("file1.txt"), path_to.join("file1.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     assert!(!dir1.0.exists());
     assert!(!dir1.1.exists());
@@ -1973,18 +1925,12 @@ fn it_move_source_not_exist() {
     assert!(!file1.0.exists());
     assert!(!file1.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
+    let from_paths = vec![dir1.0.as_path(), dir2.0.as_path(), file1.0.as_path()];
 
     let options = dir::CopyOptions::new();
     match move_items(&from_paths, &path_to, &options) {
         Ok(_) => panic!("Should be a error!"),
-        Err(err) => match err.kind {
-            ErrorKind::NotFound => {}
-            _ => {}
-        },
+        Err(err) => assert!(matches!(err.kind, ErrorKind::NotFound)),
     };
 }
 
@@ -2001,10 +1947,7 @@ fn it_move_exist_overwrite() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -2039,11 +1982,12 @@ fn it_move_exist_overwrite() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let mut options = dir::CopyOptions::new();
     options.overwrite = true;
@@ -2075,10 +2019,7 @@ fn it_move_exist_not_overwrite() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -2113,18 +2054,19 @@ fn it_move_exist_not_overwrite() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let options = dir::CopyOptions::new();
     match move_items(&from_paths, &path_to, &options) {
         Ok(_) => panic!("Should be a error!"),
         Err(err) => match err.kind {
             ErrorKind::AlreadyExists => {}
-            _ => panic!(format!("{}", err.to_string())),
+            _ => panic!("{}", err),
         },
     };
 }
@@ -2142,10 +2084,7 @@ fn it_move_exist_skip() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -2180,11 +2119,12 @@ fn it_move_exist_skip() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let mut options = dir::CopyOptions::new();
     options.skip_exist = true;
@@ -2211,10 +2151,7 @@ fn it_move_exist_overwrite_and_skip_exist() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -2249,11 +2186,12 @@ fn it_move_exist_overwrite_and_skip_exist() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let mut options = dir::CopyOptions::new();
     options.overwrite = true;
@@ -2281,13 +2219,13 @@ fn it_move_content_only_option() {
 
     let mut options = dir::CopyOptions::new();
     options.content_only = true;
-    match move_items(&vec![&file1.0], &file1.1, &options) {
+    match move_items(&[&file1.0], &file1.1, &options) {
         Err(err) => match err.kind {
             ErrorKind::Other => {
                 assert_eq!(1, 1);
             }
             _ => {
-                panic!(format!("wrong error {}", err.to_string()));
+                panic!("wrong error {}", err);
             }
         },
         Ok(_) => {
@@ -2309,10 +2247,7 @@ fn it_move_progress_work() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
     dir::create_all(&sub.0, true).unwrap();
@@ -2344,11 +2279,12 @@ fn it_move_progress_work() {
     let options = dir::CopyOptions::new();
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -2390,7 +2326,7 @@ fn it_move_progress_work() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 }
 
@@ -2403,10 +2339,7 @@ fn it_move_with_progress_source_not_exist() {
     let sub = (dir1.0.join("sub"), dir1.1.join("sub"));
     let file1 = (test_dir.join("file1.txt"), path_to.join("file1.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     assert!(!dir1.0.exists());
     assert!(!dir1.1.exists());
@@ -2418,10 +2351,7 @@ fn it_move_with_progress_source_not_exist() {
     assert!(!file1.0.exists());
     assert!(!file1.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
+    let from_paths = vec![dir1.0.as_path(), dir2.0.as_path(), file1.0.as_path()];
 
     let func_test = |process_info: TransitProcess| {
         println!("{}", process_info.total_bytes);
@@ -2430,10 +2360,7 @@ fn it_move_with_progress_source_not_exist() {
     let options = dir::CopyOptions::new();
     match move_items_with_progress(&from_paths, &path_to, &options, func_test) {
         Ok(_) => panic!("Should be a error!"),
-        Err(err) => match err.kind {
-            ErrorKind::NotFound => {}
-            _ => {}
-        },
+        Err(err) => assert!(matches!(err.kind, ErrorKind::NotFound)),
     };
 }
 
@@ -2450,10 +2377,7 @@ fn it_move_with_progress_exist_overwrite() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -2492,11 +2416,12 @@ fn it_move_with_progress_exist_overwrite() {
     options.overwrite = true;
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -2520,12 +2445,11 @@ fn it_move_with_progress_exist_overwrite() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -2542,10 +2466,7 @@ fn it_move_with_progress_exist_not_overwrite() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -2580,11 +2501,12 @@ fn it_move_with_progress_exist_not_overwrite() {
     assert!(file4.1.exists());
     assert!(!file5.1.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.0.as_path());
-    from_paths.push(dir2.0.as_path());
-    from_paths.push(file1.0.as_path());
-    from_paths.push(file2.0.as_path());
+    let from_paths = vec![
+        dir1.0.as_path(),
+        dir2.0.as_path(),
+        file1.0.as_path(),
+        file2.0.as_path(),
+    ];
 
     let options = dir::CopyOptions::new();
     let func_test = |process_info: TransitProcess| {
@@ -2595,7 +2517,7 @@ fn it_move_with_progress_exist_not_overwrite() {
         Ok(_) => panic!("Should be a error!"),
         Err(err) => match err.kind {
             ErrorKind::AlreadyExists => {}
-            _ => panic!(format!("{}", err.to_string())),
+            _ => panic!("{}", err),
         },
     };
 }
@@ -2613,10 +2535,7 @@ fn it_move_with_progress_exist_skip_exist() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -2658,11 +2577,12 @@ fn it_move_with_progress_exist_skip_exist() {
     options.overwrite = false;
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -2687,12 +2607,11 @@ fn it_move_with_progress_exist_skip_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -2710,10 +2629,7 @@ fn it_move_with_progress_exist_overwrite_and_skip_exist() {
     let file4 = (sub.0.join("file4.txt"), sub.1.join("file4.txt"));
     let file5 = (dir2.0.join("file5.txt"), dir2.1.join("file5.txt"));
 
-    match dir::create_all(&path_to, true) {
-        Ok(_) => {}
-        Err(_) => {}
-    };
+    let _ = dir::create_all(&path_to, true);
 
     dir::create_all(&dir1.0, true).unwrap();
     dir::create_all(&dir2.0, true).unwrap();
@@ -2753,11 +2669,12 @@ fn it_move_with_progress_exist_overwrite_and_skip_exist() {
     options.skip_exist = true;
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let func_test = |process_info: TransitProcess| {
             tx.send(process_info).unwrap();
@@ -2781,12 +2698,11 @@ fn it_move_with_progress_exist_overwrite_and_skip_exist() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
 
-    match rx.recv() {
-        Err(_) => panic!("Errors should not be!"),
-        _ => {}
+    if rx.recv().is_err() {
+        panic!("Errors should not be!")
     }
 }
 
@@ -2803,11 +2719,11 @@ fn it_move_with_progress_content_only_option() {
         println!("{}", process_info.total_bytes);
         dir::TransitProcessResult::ContinueOrAbort
     };
-    match move_items_with_progress(&vec![&file1.0], &file1.1, &options, func_test) {
+    match move_items_with_progress(&[&file1.0], &file1.1, &options, func_test) {
         Ok(_) => panic!("Should be a error!"),
         Err(err) => match err.kind {
             ErrorKind::Other => {}
-            _ => panic!(format!("wrong error {}", err.to_string())),
+            _ => panic!("wrong error {}", err),
         },
     };
 }
@@ -2844,11 +2760,12 @@ fn it_remove_work() {
     assert!(file4.exists());
     assert!(file5.exists());
 
-    let mut from_paths = Vec::new();
-    from_paths.push(dir1.as_path());
-    from_paths.push(dir2.as_path());
-    from_paths.push(file1.as_path());
-    from_paths.push(file2.as_path());
+    let from_paths = vec![
+        dir1.as_path(),
+        dir2.as_path(),
+        file1.as_path(),
+        file2.as_path(),
+    ];
 
     remove_items(&from_paths).unwrap();
     assert!(!file1.exists());
@@ -2917,11 +2834,12 @@ fn it_copy_with_progress_exist_user_decide_overwrite() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -2956,7 +2874,7 @@ fn it_copy_with_progress_exist_user_decide_overwrite() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3021,11 +2939,12 @@ fn it_copy_with_progress_exist_user_decide_overwrite_all() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -3060,7 +2979,7 @@ fn it_copy_with_progress_exist_user_decide_overwrite_all() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3124,11 +3043,12 @@ fn it_copy_with_progress_exist_user_decide_skip() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -3163,7 +3083,7 @@ fn it_copy_with_progress_exist_user_decide_skip() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3227,11 +3147,12 @@ fn it_copy_with_progress_exist_user_decide_skip_all() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -3266,7 +3187,7 @@ fn it_copy_with_progress_exist_user_decide_skip_all() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3330,11 +3251,12 @@ fn it_copy_with_progress_exist_user_decide_retry() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -3374,7 +3296,7 @@ fn it_copy_with_progress_exist_user_decide_retry() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3438,11 +3360,12 @@ fn it_move_with_progress_exist_user_decide_overwrite() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -3473,7 +3396,7 @@ fn it_move_with_progress_exist_user_decide_overwrite() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3538,11 +3461,12 @@ fn it_move_with_progress_exist_user_decide_overwrite_all() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -3573,7 +3497,7 @@ fn it_move_with_progress_exist_user_decide_overwrite_all() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3637,11 +3561,12 @@ fn it_move_with_progress_exist_user_decide_skip() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -3672,7 +3597,7 @@ fn it_move_with_progress_exist_user_decide_skip() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3736,11 +3661,12 @@ fn it_move_with_progress_exist_user_decide_skip_all() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -3773,7 +3699,7 @@ fn it_move_with_progress_exist_user_decide_skip_all() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
@@ -3837,11 +3763,12 @@ fn it_move_with_progress_exist_user_decide_retry() {
     let (tx, rx) = mpsc::channel();
     let result = thread::spawn(move || {
         let mut count_exist_files = 0;
-        let mut from_paths = Vec::new();
-        from_paths.push(dir1.0.as_path());
-        from_paths.push(dir2.0.as_path());
-        from_paths.push(file1.0.as_path());
-        from_paths.push(file2.0.as_path());
+        let from_paths = vec![
+            dir1.0.as_path(),
+            dir2.0.as_path(),
+            file1.0.as_path(),
+            file2.0.as_path(),
+        ];
 
         let result: u64;
         {
@@ -3877,7 +3804,214 @@ fn it_move_with_progress_exist_user_decide_retry() {
 
     match result {
         Ok(_) => {}
-        Err(err) => panic!(err),
+        Err(err) => std::panic::resume_unwind(err),
     }
     rx.try_recv().unwrap();
 }
+
+#[test]
+fn it_copy_items_overwrite_policy_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_items_overwrite_policy_work");
+    let path_to = test_dir.join("out");
+    let dir1 = (test_dir.join("dir1"), path_to.join("dir1"));
+    let file1 = (test_dir.join("file1.txt"), path_to.join("file1.txt"));
+    let file2 = (dir1.0.join("file2.txt"), dir1.1.join("file2.txt"));
+
+    dir::create_all(&test_dir, true).unwrap();
+    dir::create_all(&dir1.0, true).unwrap();
+    dir::create_all(&dir1.1, true).unwrap();
+    file::write_all(&file1.0, "content1").unwrap();
+    file::write_all(&file2.0, "content2").unwrap();
+    file::write_all(&file1.1, "old1").unwrap();
+    file::write_all(&file2.1, "old2___!").unwrap();
+
+    let from_paths = vec![dir1.0.as_path(), file1.0.as_path()];
+    let options = dir::CopyOptions::new().overwrite_policy(file::OverwritePolicy::IfSizeDiffers);
+    let result = copy_items(&from_paths, &path_to, &options).unwrap();
+    assert_eq!(8, result);
+    assert!(files_eq(&file1.0, &file1.1));
+    assert_eq!("old2___!", file::read_to_string(&file2.1).unwrap());
+
+    let options = dir::CopyOptions::new().overwrite_policy(file::OverwritePolicy::IfContentDiffers);
+    let result = move_items_with_progress(&from_paths, &path_to, &options, |_| {
+        dir::TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    // file1.txt has the same content at the destination and is kept in the source.
+    assert_eq!(8, result);
+    assert!(file1.0.exists());
+    assert!(!file2.0.exists());
+    assert_eq!("content2", file::read_to_string(&file2.1).unwrap());
+}
+
+#[test]
+fn it_copy_items_with_progress_rename_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_items_with_progress_rename_work");
+    let path_to = test_dir.join("out");
+    let file1 = test_dir.join("file1.txt");
+    dir::create_all(&path_to, true).unwrap();
+    file::write_all(&file1, "content1").unwrap();
+    file::write_all(path_to.join("file1.txt"), "old").unwrap();
+
+    let mut renamed = None;
+    let options = dir::CopyOptions::new();
+    let result = copy_items_with_progress(&[&file1], &path_to, &options, |info| {
+        if info.state == dir::TransitState::Exists {
+            return dir::TransitProcessResult::Rename;
+        }
+        renamed = info.renamed_to;
+        dir::TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(8, result);
+    assert_eq!(Some(path_to.join("file1 (1).txt")), renamed);
+    assert_eq!(
+        "old",
+        file::read_to_string(path_to.join("file1.txt")).unwrap()
+    );
+    assert!(files_eq(&file1, path_to.join("file1 (1).txt")));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_copy_items_with_progress_verify_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_items_with_progress_verify_work");
+    let path_to = test_dir.join("out");
+    let file1 = test_dir.join("file1.txt");
+    dir::create_all(&path_to, true).unwrap();
+    file::write_all(&file1, "content1").unwrap();
+
+    // The read counters of `/proc/self/io` change with every read, so no copy of it matches.
+    let io = Path::new("/proc/self/io");
+    let options = dir::CopyOptions::new().verify(file::Verify::Fast);
+    let mut failed = 0;
+    let result = copy_items_with_progress(&[io, &file1], &path_to, &options, |info| {
+        if info.state == dir::TransitState::VerificationFailed {
+            failed += 1;
+            return dir::TransitProcessResult::SkipAll;
+        }
+        dir::TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(1, failed);
+    assert_eq!(8, result);
+    assert!(!path_to.join("io").exists());
+    assert!(files_eq(&file1, path_to.join("file1.txt")));
+}
+
+#[test]
+fn it_copy_items_with_progress_journal_work() {
+    let test_dir = Path::new(TEST_FOLDER).join("it_copy_items_with_progress_journal_work");
+    let path_to = test_dir.join("out");
+    let dir1 = test_dir.join("dir1");
+    let file1 = dir1.join("file1.txt");
+    let file2 = test_dir.join("file2.bin");
+    let journal_path = test_dir.join("copy.journal");
+    dir::create_all(&path_to, true).unwrap();
+    dir::create_all(&dir1, true).unwrap();
+    file::write_all(&file1, "content1").unwrap();
+    let content: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&file2, &content).unwrap();
+    let _ = std::fs::remove_file(&journal_path);
+
+    let options = dir::CopyOptions::new()
+        .journal(&journal_path)
+        .reflink(file::Reflink::Never)
+        .buffer_size(10_000);
+    let items = [&dir1, &file2];
+    // Interrupts the copy in the middle of `file2.bin`.
+    let interrupted = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        copy_items_with_progress(&items, &path_to, &options, |info| {
+            if info.file_name == "file2.bin" && info.file_bytes_copied >= 50_000 {
+                panic!("interrupted");
+            }
+            dir::TransitProcessResult::ContinueOrAbort
+        })
+    }));
+    assert!(interrupted.is_err());
+    let pending = journal::pending(&journal_path).unwrap();
+    assert_eq!(1, pending.len());
+    assert_eq!(file2, pending[0].from);
+    assert_eq!(path_to.join("file2.bin"), pending[0].to);
+    assert_eq!(100_000, pending[0].total_bytes);
+
+    let mut copied = Vec::new();
+    let result = copy_items_with_progress(&items, &path_to, &options, |info| {
+        copied.push((info.file_name, info.file_bytes_copied));
+        dir::TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(100_000, result);
+    // `file1.txt` is done, `file2.bin` continues after the part which was copied before.
+    assert_eq!(("file2.bin".to_string(), 60_000), copied[0]);
+    assert!(copied.iter().all(|(name, _)| name == "file2.bin"));
+    assert!(files_eq(&file1, path_to.join("dir1/file1.txt")));
+    assert_eq!(content, std::fs::read(path_to.join("file2.bin")).unwrap());
+    assert!(journal::pending(&journal_path).unwrap().is_empty());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_copy_and_move_items_with_progress_preserve_xattrs_work() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let test_dir =
+        Path::new(TEST_FOLDER).join("it_copy_and_move_items_with_progress_preserve_xattrs_work");
+    let path_to = test_dir.join("out");
+    let file1 = test_dir.join("file1.txt");
+    let file2 = test_dir.join("file2.txt");
+    dir::create_all(&path_to, true).unwrap();
+    file::write_all(&file1, "content1").unwrap();
+    file::write_all(&file2, "content22").unwrap();
+    for path in [&file1, &file2] {
+        file::set_xattrs(path, vec![("user.origin", "build-42")]).unwrap();
+    }
+    // Unprivileged processes can not set user attributes on a read-only file.
+    std::fs::set_permissions(&file2, std::fs::Permissions::from_mode(0o444)).unwrap();
+    let is_root = std::fs::metadata(&file1).unwrap().uid() == 0;
+
+    let options = dir::CopyOptions::new().preserve_xattrs(true);
+    let mut failed = Vec::new();
+    let result = copy_items_with_progress(&[&file1, &file2], &path_to, &options, |info| {
+        if info.state == dir::TransitState::XattrFailed {
+            failed.push(info.file_name);
+        }
+        dir::TransitProcessResult::ContinueOrAbort
+    })
+    .unwrap();
+    assert_eq!(17, result);
+    let origin = vec![("user.origin".into(), b"build-42".to_vec())];
+    assert_eq!(origin, file::get_xattrs(path_to.join("file1.txt")).unwrap());
+    assert!(path_to.join("file2.txt").exists());
+    let expected_failed = if is_root {
+        Vec::new()
+    } else {
+        vec!["file2.txt".to_string()]
+    };
+    assert_eq!(expected_failed, failed);
+
+    // Attributes are only copied when the move falls back to a copy on another file system.
+    let other_fs = Path::new("/dev/shm");
+    match other_fs.metadata() {
+        Ok(meta) if meta.dev() != test_dir.metadata().unwrap().dev() => {}
+        _ => return,
+    }
+    let other_to = other_fs.join(format!("fs_extra_items_xattrs_{}", std::process::id()));
+    dir::create_all(&other_to, true).unwrap();
+    let mut failed = Vec::new();
+    let result = move_items_with_progress(&[&file1, &file2], &other_to, &options, |info| {
+        if info.state == dir::TransitState::XattrFailed {
+            failed.push(info.file_name);
+        }
+        dir::TransitProcessResult::ContinueOrAbort
+    });
+    let xattrs = file::get_xattrs(other_to.join("file1.txt"));
+    let moved = other_to.join("file2.txt").exists();
+    let _ = dir::remove(&other_to);
+
+    assert_eq!(17, result.unwrap());
+    assert!(moved);
+    assert!(!file1.exists() && !file2.exists());
+    assert_eq!(origin, xattrs.unwrap());
+    assert_eq!(expected_failed, failed);
+}
@@ -0,0 +1,360 @@
+use std::path::{Path, PathBuf};
+
+extern crate fs_extra;
+use fs_extra::dir::{create_all, CopyOptions};
+use fs_extra::error::*;
+use fs_extra::file::{read_to_string, write_all, Backup, OverwritePolicy};
+use fs_extra::plan::{self, Operation};
+
+const TEST_FOLDER: &str = "./tests/temp/plan";
+
+// Compares the operations of `plan` with `expected`. Entries of one directory are read in
+// no particular order.
+fn assert_operations(plan: &plan::Plan, expected: &[Operation]) {
+    let operations = plan.operations();
+    assert_eq!(expected.len(), operations.len(), "{:?}", operations);
+    for operation in expected {
+        assert!(operations.contains(operation), "{:?}", operations);
+    }
+}
+
+// Creates `root/dir` with `file1.txt` and `sub/file2.txt`.
+fn create_source(root: &Path) -> PathBuf {
+    let from = root.join("dir");
+    create_all(root, true).unwrap();
+    create_all(from.join("sub"), false).unwrap();
+    write_all(from.join("file1.txt"), "content1").unwrap();
+    write_all(from.join("sub").join("file2.txt"), "content22").unwrap();
+    from
+}
+
+#[test]
+fn it_copy_plan_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_plan_work");
+    let from = create_source(&root);
+    let out = root.join("out");
+    create_all(out.join("dir"), false).unwrap();
+    write_all(out.join("dir").join("file1.txt"), "old").unwrap();
+
+    let options = CopyOptions::new().overwrite(true);
+    let plan = plan::copy(&from, &out, &options).unwrap();
+    assert_operations(
+        &plan,
+        &[
+            Operation::CreateDir {
+                to: out.join("dir").join("sub"),
+            },
+            Operation::Overwrite {
+                from: from.join("file1.txt"),
+                to: out.join("dir").join("file1.txt"),
+                size: 8,
+            },
+            Operation::CopyFile {
+                from: from.join("sub").join("file2.txt"),
+                to: out.join("dir").join("sub").join("file2.txt"),
+                size: 9,
+            },
+        ],
+    );
+    assert!(!out.join("dir").join("sub").exists());
+    assert_eq!(
+        "old",
+        read_to_string(out.join("dir").join("file1.txt")).unwrap()
+    );
+
+    assert_eq!(17, plan.execute().unwrap());
+    assert_eq!(
+        "content22",
+        read_to_string(out.join("dir").join("sub").join("file2.txt")).unwrap()
+    );
+    assert_eq!(
+        "content1",
+        read_to_string(out.join("dir").join("file1.txt")).unwrap()
+    );
+}
+
+#[test]
+fn it_copy_plan_options_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_plan_options_work");
+    let from = create_source(&root);
+    let out = root.join("out");
+    create_all(&out, false).unwrap();
+    write_all(out.join("file1.txt"), "content1").unwrap();
+    write_all(out.join("extra.txt"), "extra").unwrap();
+
+    let options = CopyOptions::new()
+        .content_only(true)
+        .depth(1)
+        .overwrite_policy(OverwritePolicy::IfContentDiffers)
+        .mirror_copy(true);
+    let plan = plan::copy(&from, &out, &options).unwrap();
+    assert_operations(
+        &plan,
+        &[
+            Operation::CreateDir {
+                to: out.join("sub"),
+            },
+            Operation::Skip {
+                from: from.join("file1.txt"),
+                to: out.join("file1.txt"),
+            },
+            Operation::Remove {
+                path: out.join("extra.txt"),
+            },
+        ],
+    );
+
+    assert_eq!(0, plan.execute().unwrap());
+    assert!(!out.join("extra.txt").exists());
+    assert!(out.join("sub").exists());
+    assert!(!out.join("sub").join("file2.txt").exists());
+
+    let options = CopyOptions::new().content_only(true);
+    match plan::copy(&from, &out, &options) {
+        Err(err) => match err.kind {
+            ErrorKind::AlreadyExists => {}
+            _ => panic!("wrong error"),
+        },
+        Ok(_) => panic!("should be error"),
+    }
+}
+
+#[test]
+fn it_move_dir_plan_work() {
+    let root = Path::new(TEST_FOLDER).join("it_move_dir_plan_work");
+    let from = create_source(&root);
+    let out = root.join("out");
+    create_all(&out, false).unwrap();
+
+    let options = CopyOptions::new();
+    let plan = plan::move_dir(&from, &out, &options).unwrap();
+    assert_operations(
+        &plan,
+        &[Operation::Rename {
+            from: from.clone(),
+            to: out.join("dir"),
+        }],
+    );
+    assert!(from.exists());
+
+    assert_eq!(17, plan.execute().unwrap());
+    assert!(!from.exists());
+    assert!(out.join("dir").join("sub").join("file2.txt").exists());
+}
+
+#[test]
+fn it_move_items_plan_work() {
+    let root = Path::new(TEST_FOLDER).join("it_move_items_plan_work");
+    let from = create_source(&root);
+    let out = root.join("out");
+    create_all(out.join("dir"), false).unwrap();
+    write_all(out.join("dir").join("file1.txt"), "content1").unwrap();
+    write_all(root.join("file3.txt"), "content333").unwrap();
+
+    let options = CopyOptions::new().overwrite_policy(OverwritePolicy::IfSizeDiffers);
+    let items = [from.clone(), root.join("file3.txt")];
+    let plan = plan::move_items(&items, &out, &options).unwrap();
+    assert_operations(
+        &plan,
+        &[
+            Operation::CreateDir {
+                to: out.join("dir").join("sub"),
+            },
+            Operation::Skip {
+                from: from.join("file1.txt"),
+                to: out.join("dir").join("file1.txt"),
+            },
+            Operation::Rename {
+                from: from.join("sub").join("file2.txt"),
+                to: out.join("dir").join("sub").join("file2.txt"),
+            },
+            Operation::Rename {
+                from: root.join("file3.txt"),
+                to: out.join("file3.txt"),
+            },
+        ],
+    );
+
+    assert_eq!(19, plan.execute().unwrap());
+    assert!(from.join("file1.txt").exists());
+    assert!(!from.join("sub").join("file2.txt").exists());
+    assert!(out.join("dir").join("sub").join("file2.txt").exists());
+    assert!(out.join("file3.txt").exists());
+}
+
+#[test]
+fn it_remove_items_plan_work() {
+    let root = Path::new(TEST_FOLDER).join("it_remove_items_plan_work");
+    let from = create_source(&root);
+    let items = [from.clone(), root.join("missing.txt")];
+
+    let plan = plan::remove_items(&items).unwrap();
+    assert_operations(&plan, &[Operation::Remove { path: from.clone() }]);
+    assert!(from.exists());
+
+    assert_eq!(0, plan.execute().unwrap());
+    assert!(!from.exists());
+}
+
+#[test]
+fn it_plan_outdated_work() {
+    let root = Path::new(TEST_FOLDER).join("it_plan_outdated_work");
+    let from = create_source(&root);
+    let out = root.join("out");
+    create_all(&out, false).unwrap();
+
+    let options = CopyOptions::new();
+    let plan = plan::copy_items(&[&from], &out, &options).unwrap();
+    assert!(plan.check().is_ok());
+    write_all(from.join("sub").join("file2.txt"), "changed content").unwrap();
+
+    match plan.execute() {
+        Err(err) => match err.kind {
+            ErrorKind::PlanOutdated => {}
+            _ => panic!("wrong error"),
+        },
+        Ok(_) => panic!("should be error"),
+    }
+    assert!(!out.join("dir").exists());
+
+    let plan = plan::remove_items(&[&from]).unwrap();
+    write_all(from.join("sub").join("file3.txt"), "new").unwrap();
+    assert!(plan.execute().is_err());
+    assert!(from.exists());
+}
+
+#[test]
+fn it_move_dir_plan_filter_work() {
+    let root = Path::new(TEST_FOLDER).join("it_move_dir_plan_filter_work");
+    let from = create_source(&root);
+    write_all(from.join("sub").join("file3.tmp"), "tmp").unwrap();
+    let out = root.join("out");
+    create_all(&out, false).unwrap();
+
+    let options = CopyOptions::new().exclude(&["*.tmp"]);
+    let plan = plan::move_dir(&from, &out, &options).unwrap();
+    assert_operations(
+        &plan,
+        &[
+            Operation::CreateDir {
+                to: out.join("dir"),
+            },
+            Operation::CreateDir {
+                to: out.join("dir").join("sub"),
+            },
+            Operation::Rename {
+                from: from.join("file1.txt"),
+                to: out.join("dir").join("file1.txt"),
+            },
+            Operation::Rename {
+                from: from.join("sub").join("file2.txt"),
+                to: out.join("dir").join("sub").join("file2.txt"),
+            },
+        ],
+    );
+
+    assert_eq!(17, plan.execute().unwrap());
+    assert!(from.join("sub").join("file3.tmp").exists());
+    assert!(!from.join("file1.txt").exists());
+    assert!(!out.join("dir").join("sub").join("file3.tmp").exists());
+}
+
+#[test]
+fn it_copy_plan_rename_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_plan_rename_work");
+    let from = create_source(&root);
+    let out = root.join("out");
+    create_all(out.join("dir"), false).unwrap();
+    write_all(out.join("dir").join("file1.txt"), "old").unwrap();
+
+    let options = CopyOptions::new().overwrite_policy(OverwritePolicy::Rename);
+    let plan = plan::copy_items(&[&from, &from.join("file1.txt")], &out, &options).unwrap();
+    assert_operations(
+        &plan,
+        &[
+            Operation::CreateDir {
+                to: out.join("dir").join("sub"),
+            },
+            Operation::CopyFile {
+                from: from.join("file1.txt"),
+                to: out.join("dir").join("file1 (1).txt"),
+                size: 8,
+            },
+            Operation::CopyFile {
+                from: from.join("sub").join("file2.txt"),
+                to: out.join("dir").join("sub").join("file2.txt"),
+                size: 9,
+            },
+            Operation::CopyFile {
+                from: from.join("file1.txt"),
+                to: out.join("file1.txt"),
+                size: 8,
+            },
+        ],
+    );
+
+    assert_eq!(25, plan.execute().unwrap());
+    assert_eq!(
+        "content1",
+        read_to_string(out.join("dir").join("file1 (1).txt")).unwrap()
+    );
+}
+
+#[test]
+fn it_copy_plan_backup_work() {
+    let root = Path::new(TEST_FOLDER).join("it_copy_plan_backup_work");
+    let from = create_source(&root);
+    let out = root.join("out");
+    let backup_dir = root.join("backups");
+    create_all(out.join("dir").join("sub"), false).unwrap();
+    write_all(out.join("dir").join("file1.txt"), "old1").unwrap();
+    write_all(out.join("dir").join("sub").join("file2.txt"), "old2").unwrap();
+
+    let options = CopyOptions::new()
+        .overwrite(true)
+        .mirror_copy(true)
+        .backup(Backup::Simple);
+    let plan = plan::copy(&from, &out, &options).unwrap();
+    assert_operations(
+        &plan,
+        &[
+            Operation::Backup {
+                path: out.join("dir").join("file1.txt"),
+                backup: out.join("dir").join("file1.txt~"),
+            },
+            Operation::Overwrite {
+                from: from.join("file1.txt"),
+                to: out.join("dir").join("file1.txt"),
+                size: 8,
+            },
+            Operation::Backup {
+                path: out.join("dir").join("sub").join("file2.txt"),
+                backup: out.join("dir").join("sub").join("file2.txt~"),
+            },
+            Operation::Overwrite {
+                from: from.join("sub").join("file2.txt"),
+                to: out.join("dir").join("sub").join("file2.txt"),
+                size: 9,
+            },
+        ],
+    );
+    assert_eq!(17, plan.execute().unwrap());
+    assert_eq!(
+        "old1",
+        read_to_string(out.join("dir").join("file1.txt~")).unwrap()
+    );
+
+    // The backups of the last copy are replaced by new ones instead of being removed.
+    let plan = plan::copy(&from, &out, &options).unwrap();
+    assert!(!plan
+        .operations()
+        .iter()
+        .any(|operation| matches!(operation, Operation::Remove { .. })));
+
+    let options = options.mirror_copy(false).backup_dir(&backup_dir);
+    let plan = plan::copy(&from, &out, &options).unwrap();
+    assert!(plan.operations().contains(&Operation::Backup {
+        path: out.join("dir").join("sub").join("file2.txt"),
+        backup: backup_dir.join("sub").join("file2.txt~"),
+    }));
+}